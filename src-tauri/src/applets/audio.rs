@@ -37,6 +37,7 @@ async fn run_audio_monitor_loop(app: AppHandle, mut monitor: AudioMonitor) {
     'outer: loop {
         let is_event_driven = monitor.is_event_driven();
         let mut state_rx = monitor.state_rx();
+        monitor.publish_state();
 
         log_info(&format!("AudioApplet: active backend = {}", monitor.backend_name()));
 
@@ -123,7 +124,7 @@ fn get_volume_percentage(current: i64, min: i64, max: i64) -> u8 {
     }
 }
 
-async fn show_volume_osd(app: &AppHandle, volume_info: &VolumeInfo) {
+pub(crate) async fn show_volume_osd(app: &AppHandle, volume_info: &VolumeInfo) {
     let percentage = get_volume_percentage(volume_info.current, volume_info.min, volume_info.max);
     let icon = get_volume_icon_name(volume_info.is_muted, percentage);
     // A locale key: the OSD view translates it and fills in the percentage,
@@ -0,0 +1,227 @@
+use super::Applet;
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::error::Error;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::audio_backend::{backend, DeviceKind};
+use crate::audio_policy::{
+    choose_default, hotplug_kind, priority_list, remember_new, remember_present,
+};
+use crate::constants::CMD_PACTL;
+use crate::logger::{log_debug, log_error, log_info};
+use crate::notifications::{localized, send_system_notification};
+use crate::settings;
+use crate::structs::AudioDevice;
+
+/// Conectar unos auriculares USB crea el sink, el source y a veces un par más
+/// en ráfaga; se espera a que se calme antes de decidir.
+const SETTLE_DELAY: Duration = Duration::from_millis(400);
+
+pub struct AudioPolicyApplet;
+
+#[async_trait]
+impl Applet for AudioPolicyApplet {
+    fn name(&self) -> &'static str {
+        "audio_policy"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        log_info("AudioPolicyApplet: siguiendo conexiones de dispositivos de audio");
+
+        tokio::spawn(async move {
+            // Antes de mirar qué hay conectado, para que la entrada virtual
+            // ya cuente al armar la lista de prioridades.
+            let handle = app.clone();
+            let _ = tokio::task::spawn_blocking(move || crate::audio_effects::restore(&handle)).await;
+            run_policy_loop(app).await;
+        });
+
+        Ok(())
+    }
+}
+
+/// Lo último que se vio de un lado: qué estaba conectado y cuál era el
+/// dispositivo por defecto.
+#[derive(Default)]
+struct KnownDevices {
+    names: HashSet<String>,
+    default: Option<String>,
+}
+
+impl KnownDevices {
+    fn from_devices(devices: &[AudioDevice]) -> Self {
+        Self {
+            names: devices.iter().map(|d| d.description.clone()).collect(),
+            default: devices
+                .iter()
+                .find(|d| d.is_default)
+                .map(|d| d.description.clone()),
+        }
+    }
+}
+
+async fn list(kind: DeviceKind) -> Option<Vec<AudioDevice>> {
+    match tokio::task::spawn_blocking(move || backend().list_devices(kind)).await {
+        Ok(Ok(devices)) => Some(devices),
+        Ok(Err(e)) => {
+            log_error(&format!("AudioPolicy: no se pudo listar {}s: {}", kind.noun(), e));
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+async fn run_policy_loop(app: AppHandle) {
+    let mut sinks = KnownDevices::default();
+    let mut sources = KnownDevices::default();
+
+    for (kind, known) in [(DeviceKind::Sink, &mut sinks), (DeviceKind::Source, &mut sources)] {
+        if let Some(devices) = list(kind).await {
+            if let Err(e) = remember_present(kind, &devices) {
+                log_error(&format!("AudioPolicy: no se pudo guardar la preferencia: {}", e));
+            }
+            *known = KnownDevices::from_devices(&devices);
+        }
+    }
+
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        let child = Command::new(CMD_PACTL)
+            .arg("subscribe")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log_error(&format!("AudioPolicy: no se pudo iniciar `pactl subscribe`: {}", e));
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(30));
+                continue;
+            }
+        };
+        backoff = Duration::from_secs(1);
+
+        let Some(stdout) = child.stdout.take() else {
+            let _ = child.kill().await;
+            continue;
+        };
+        let mut lines = BufReader::new(stdout).lines();
+
+        'events: loop {
+            let mut dirty = (false, false);
+
+            match lines.next_line().await {
+                Ok(Some(line)) => match hotplug_kind(&line) {
+                    Some(DeviceKind::Sink) => dirty.0 = true,
+                    Some(DeviceKind::Source) => dirty.1 = true,
+                    None => continue,
+                },
+                Ok(None) | Err(_) => break,
+            }
+
+            // Juntar el resto de la ráfaga.
+            loop {
+                match tokio::time::timeout(SETTLE_DELAY, lines.next_line()).await {
+                    Err(_) => break,
+                    Ok(Ok(Some(line))) => match hotplug_kind(&line) {
+                        Some(DeviceKind::Sink) => dirty.0 = true,
+                        Some(DeviceKind::Source) => dirty.1 = true,
+                        None => {}
+                    },
+                    Ok(Ok(None)) | Ok(Err(_)) => break 'events,
+                }
+            }
+
+            if dirty.0 {
+                apply_policy(&app, DeviceKind::Sink, &mut sinks).await;
+            }
+            if dirty.1 {
+                apply_policy(&app, DeviceKind::Source, &mut sources).await;
+            }
+        }
+
+        log_error("AudioPolicy: `pactl subscribe` terminó; reconectando");
+        let _ = child.kill().await;
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+async fn apply_policy(app: &AppHandle, kind: DeviceKind, known: &mut KnownDevices) {
+    let Some(mut devices) = list(kind).await else {
+        return;
+    };
+
+    let added: Vec<&str> = devices
+        .iter()
+        .map(|d| d.description.as_str())
+        .filter(|name| !known.names.contains(*name))
+        .collect();
+
+    log_debug(&format!(
+        "AudioPolicy: {} {}s conectados ({} nuevos)",
+        devices.len(),
+        kind.noun(),
+        added.len()
+    ));
+
+    if settings::get().audio.auto_switch {
+        if let Err(e) = remember_new(kind, &added, &devices) {
+            log_error(&format!("AudioPolicy: no se pudo guardar la preferencia: {}", e));
+        }
+
+        let priority = priority_list(kind);
+        let target = choose_default(&priority, &devices)
+            .filter(|device| !device.is_default)
+            .map(|device| device.id.clone());
+
+        if let Some(id) = target {
+            let switched =
+                tokio::task::spawn_blocking(move || backend().set_default(kind, &id)).await;
+            match switched {
+                Ok(Ok(())) => {
+                    if let Some(updated) = list(kind).await {
+                        devices = updated;
+                    }
+                }
+                Ok(Err(e)) => log_error(&format!("AudioPolicy: no se pudo cambiar de {}: {}", kind.noun(), e)),
+                Err(_) => {}
+            }
+        }
+    }
+
+    let _ = app.emit(kind.devices_event(), &devices);
+
+    let previous_default = known.default.clone();
+    *known = KnownDevices::from_devices(&devices);
+
+    // Se avisa también cuando cambió sin que lo eligiéramos nosotros (se
+    // desconectó el que estaba y el servidor pasó a otro).
+    if known.default.is_some() && known.default != previous_default {
+        if let Some(active) = devices.iter().find(|d| d.is_default) {
+            announce(app, kind, active).await;
+        }
+    }
+}
+
+async fn announce(app: &AppHandle, kind: DeviceKind, device: &AudioDevice) {
+    log_info(&format!("AudioPolicy: {} activo: {}", kind.noun(), device.name));
+
+    let summary = match kind {
+        DeviceKind::Sink => localized(app, "audio.outputActive"),
+        DeviceKind::Source => localized(app, "audio.inputActive"),
+    };
+
+    if let Err(e) =
+        send_system_notification(summary, Some(device.name.clone()), Some("low".to_string())).await
+    {
+        log_error(&format!("AudioPolicy: no se pudo notificar: {}", e));
+    }
+}
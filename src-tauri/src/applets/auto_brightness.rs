@@ -0,0 +1,83 @@
+use super::Applet;
+use async_trait::async_trait;
+use std::error::Error;
+use tauri::{AppHandle, Emitter};
+
+use crate::auto_brightness::{
+    controller, follow_sensor, restart_signal, set_sensor_available,
+};
+use crate::brightness;
+use crate::commands::get_auto_brightness;
+use crate::session_actions::system_bus;
+use crate::settings;
+
+pub struct AutoBrightnessApplet;
+
+#[async_trait]
+impl Applet for AutoBrightnessApplet {
+    fn name(&self) -> &'static str {
+        "auto_brightness"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        tokio::spawn(run(app));
+        Ok(())
+    }
+}
+
+/// Runs the sensor loop while auto mode is on; any settings change restarts
+/// it so a new curve or the toggle takes effect right away.
+async fn run(app: AppHandle) {
+    loop {
+        if !settings::get().brightness.auto {
+            set_sensor_available(false);
+            restart_signal().notified().await;
+            continue;
+        }
+
+        let Ok(connection) = system_bus(&app).await else {
+            log::warn!("Auto brightness: no system bus connection");
+            restart_signal().notified().await;
+            continue;
+        };
+
+        let current = tokio::task::spawn_blocking(brightness::get_brightness)
+            .await
+            .ok()
+            .and_then(|info| info.ok())
+            .map(|info| info.current);
+        crate::auto_brightness::reset(current);
+        log::info!("Auto brightness enabled");
+
+        let emitter = app.clone();
+        let apply = move |percent: u32| {
+            let app = emitter.clone();
+            tokio::spawn(async move {
+                let result =
+                    tokio::task::spawn_blocking(move || brightness::set_brightness(percent)).await;
+                if let Ok(Err(e)) = result {
+                    log::warn!("Auto brightness: could not set {}%: {}", percent, e);
+                }
+                if let Ok(status) = get_auto_brightness().await {
+                    let _ = app.emit("auto-brightness-changed", status);
+                }
+            });
+        };
+
+        let result = follow_sensor(
+            &connection,
+            controller(),
+            restart_signal().notified(),
+            apply,
+        )
+        .await;
+
+        if let Err(e) = result {
+            // iio-sensor-proxy is not installed or not running: wait for the
+            // next settings change instead of retrying in a loop.
+            log::warn!("Auto brightness: sensor proxy unavailable: {}", e);
+            set_sensor_available(false);
+            restart_signal().notified().await;
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use super::Applet;
+use crate::battery_history;
+use crate::battery_warnings;
+use crate::caffeine;
+use crate::charge_limit;
 use crate::dbus_pool::DbusPool;
-use crate::structs::BatteryInfo;
+use crate::notifications::{localized, send_system_notification};
+use crate::power_profiles;
+use crate::structs::{BatteryInfo, PeripheralBattery};
 use async_trait::async_trait;
 use futures_util::StreamExt;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::sync::Mutex;
@@ -18,6 +24,29 @@ pub struct BatteryApplet;
 static BATTERY_CACHE: Mutex<Option<BatteryInfo>> = Mutex::new(None);
 // Cached device path (discovered at startup)
 static BATTERY_DEVICE_PATH: Mutex<Option<String>> = Mutex::new(None);
+// Every other UPower device with a battery, by object path
+static PERIPHERALS: Mutex<BTreeMap<String, PeripheralBattery>> = Mutex::new(BTreeMap::new());
+// Charge thresholds read from sysfs, for UPower versions that don't report them
+static SYSFS_THRESHOLDS: Mutex<Option<charge_limit::Thresholds>> = Mutex::new(None);
+
+/// Charge thresholds from sysfs, scanning `power_supply` only the first time.
+fn sysfs_thresholds() -> charge_limit::Thresholds {
+    let mut cached = SYSFS_THRESHOLDS.lock().unwrap();
+    cached
+        .get_or_insert_with(charge_limit::sysfs_thresholds)
+        .clone()
+}
+
+/// Drops the cached sysfs thresholds after writing new ones, so the next
+/// reading sees them.
+pub(crate) fn forget_sysfs_thresholds() {
+    SYSFS_THRESHOLDS.lock().unwrap().take();
+}
+
+/// UPower object path of the system battery, once it has been found.
+pub(crate) fn battery_device_path() -> Option<String> {
+    BATTERY_DEVICE_PATH.lock().ok()?.clone()
+}
 
 #[async_trait]
 impl Applet for BatteryApplet {
@@ -26,6 +55,10 @@ impl Applet for BatteryApplet {
     }
 
     async fn start(&self, app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+        // Mice, headsets and the like, whether or not this machine has a
+        // battery of its own.
+        tokio::spawn(monitor_peripherals(app_handle.clone()));
+
         // Check SysFS availability first (cheap, no D-Bus)
         let sysfs_battery = "/sys/class/power_supply/BAT0";
         let has_sysfs_battery = Path::new(sysfs_battery).exists();
@@ -40,7 +73,7 @@ impl Applet for BatteryApplet {
 
                 // Emit initial state
                 if let Some(info) = get_battery_info_with_conn(&conn).await {
-                    let _ = app_handle.emit("battery-update", &info);
+                    publish(&app_handle, &info);
                 }
 
                 self.run_dbus_loop(app_handle, conn, path).await;
@@ -52,7 +85,7 @@ impl Applet for BatteryApplet {
         if has_sysfs {
             // Emit initial state from SysFS
             if let Some(info) = read_sysfs_battery_info() {
-                let _ = app_handle.emit("battery-update", &info);
+                publish(&app_handle, &info);
             }
 
             self.run_sysfs_loop(app_handle).await;
@@ -62,7 +95,7 @@ impl Applet for BatteryApplet {
                 tokio::time::sleep(Duration::from_secs(60)).await;
 
                 if let Some(info) = read_sysfs_battery_info() {
-                    let _ = app_handle.emit("battery-update", &info);
+                    publish(&app_handle, &info);
                 }
             }
         }
@@ -78,12 +111,14 @@ impl BatteryApplet {
             tokio::time::sleep(Duration::from_secs(2)).await;
 
             if let Some(current_info) = read_sysfs_battery_info() {
+                observe(&app_handle, &current_info);
                 let should_emit = match &last_info {
                     None => true,
                     Some(last) => {
                         (last.percentage - current_info.percentage).abs() > 0.1
                             || last.is_charging != current_info.is_charging
                             || last.state != current_info.state
+                            || last.charge_end_threshold != current_info.charge_end_threshold
                     }
                 };
 
@@ -270,6 +305,11 @@ impl BatteryApplet {
                             energy: None,
                             energy_full: None,
                             energy_full_design: None,
+                            energy_rate: None,
+                            charge_threshold_supported: false,
+                            charge_threshold_enabled: false,
+                            charge_start_threshold: None,
+                            charge_end_threshold: None,
                             voltage: None,
                             temperature: None,
                             serial: None,
@@ -347,6 +387,10 @@ impl BatteryApplet {
         last_info: &mut Option<BatteryInfo>,
         current: &Option<BatteryInfo>,
     ) {
+        if let Some(info) = current {
+            observe(app_handle, info);
+        }
+
         let should_emit = match (last_info.as_ref(), current) {
             (_, None) => false,
             (None, Some(_)) => true,
@@ -354,6 +398,8 @@ impl BatteryApplet {
                 (last.percentage - cur.percentage).abs() > 0.5
                     || last.is_charging != cur.is_charging
                     || last.state != cur.state
+                    || last.charge_threshold_enabled != cur.charge_threshold_enabled
+                    || last.charge_end_threshold != cur.charge_end_threshold
             }
         };
 
@@ -364,6 +410,21 @@ impl BatteryApplet {
     }
 }
 
+/// Reacts to a reading: history, low-battery warnings, caffeine's "until
+/// unplugged" and the automatic power saver.
+fn observe(app_handle: &AppHandle, info: &BatteryInfo) {
+    battery_history::record(info);
+    battery_warnings::observe(app_handle, info);
+    caffeine::observe_battery(info);
+    power_profiles::observe_battery(app_handle, info);
+}
+
+/// Sends a fresh reading to the frontend and to the low-battery warnings.
+fn publish(app_handle: &AppHandle, info: &BatteryInfo) {
+    observe(app_handle, info);
+    let _ = app_handle.emit("battery-update", info);
+}
+
 /// Read battery info from SysFS without any D-Bus dependency.
 /// Used as fallback when UPower or system bus is unavailable.
 fn read_sysfs_battery_info() -> Option<BatteryInfo> {
@@ -419,6 +480,18 @@ fn read_sysfs_battery_info() -> Option<BatteryInfo> {
         .and_then(|s| s.parse::<f64>().ok())
         .map(|v| v / 1_000_000.0);
 
+    // Power (sysfs: µW → W); some batteries only report current (µA)
+    let energy_rate = read_str("power_now")
+        .and_then(|s| s.parse::<f64>().ok())
+        .map(|v| v / 1_000_000.0)
+        .or_else(|| {
+            let current = read_str("current_now")?.parse::<f64>().ok()?;
+            Some(current / 1_000_000.0 * voltage?)
+        })
+        .map(f64::abs);
+
+    let thresholds = charge_limit::read_sysfs(bat_path);
+
     // Temperature (sysfs: tenths of °C → °C)
     let temperature = read_str("temp")
         .and_then(|s| s.parse::<f64>().ok())
@@ -438,6 +511,11 @@ fn read_sysfs_battery_info() -> Option<BatteryInfo> {
         energy,
         energy_full,
         energy_full_design,
+        energy_rate,
+        charge_threshold_supported: thresholds.supported,
+        charge_threshold_enabled: thresholds.enabled,
+        charge_start_threshold: thresholds.start,
+        charge_end_threshold: thresholds.end,
         voltage,
         temperature,
         serial,
@@ -527,7 +605,7 @@ async fn get_battery_info_with_conn(conn: &Connection) -> Option<BatteryInfo> {
                 }
             };
 
-            let info = parse_battery_info_from_props(&props);
+            let info = parse_battery_info_from_props(&props, &sysfs_thresholds());
             // Update cache
             *BATTERY_CACHE.lock().unwrap() = Some(info.clone());
             Some(info)
@@ -558,6 +636,11 @@ async fn get_battery_info_with_conn(conn: &Connection) -> Option<BatteryInfo> {
                 energy: None,
                 energy_full: None,
                 energy_full_design: None,
+                energy_rate: None,
+                charge_threshold_supported: false,
+                charge_threshold_enabled: false,
+                charge_start_threshold: None,
+                charge_end_threshold: None,
                 voltage: None,
                 temperature: None,
                 serial: None,
@@ -567,7 +650,11 @@ async fn get_battery_info_with_conn(conn: &Connection) -> Option<BatteryInfo> {
 }
 
 /// Parse BatteryInfo from a HashMap of properties returned by GetAll.
-fn parse_battery_info_from_props(props: &HashMap<String, OwnedValue>) -> BatteryInfo {
+/// `sysfs` fills in the charge thresholds when UPower doesn't report them.
+fn parse_battery_info_from_props(
+    props: &HashMap<String, OwnedValue>,
+    sysfs: &charge_limit::Thresholds,
+) -> BatteryInfo {
     let is_present = prop_bool(props, "IsPresent").unwrap_or(false);
     let percentage = prop_f64(props, "Percentage").unwrap_or(0.0);
     let state_num = prop_u32(props, "State").unwrap_or(0);
@@ -594,6 +681,17 @@ fn parse_battery_info_from_props(props: &HashMap<String, OwnedValue>) -> Battery
         _ => "Unknown".to_string(),
     });
 
+    // UPower < 1.90 doesn't know about charge thresholds: use the sysfs ones.
+    let thresholds = match prop_bool(props, "ChargeThresholdSupported") {
+        Some(supported) => charge_limit::Thresholds {
+            supported,
+            enabled: prop_bool(props, "ChargeThresholdEnabled").unwrap_or(false),
+            start: prop_u32(props, "ChargeStartThreshold"),
+            end: prop_u32(props, "ChargeEndThreshold"),
+        },
+        None => sysfs.clone(),
+    };
+
     BatteryInfo {
         has_battery: is_present,
         percentage,
@@ -608,6 +706,11 @@ fn parse_battery_info_from_props(props: &HashMap<String, OwnedValue>) -> Battery
         energy: prop_f64(props, "Energy"),
         energy_full: prop_f64(props, "EnergyFull"),
         energy_full_design: prop_f64(props, "EnergyFullDesign"),
+        energy_rate: prop_f64(props, "EnergyRate"),
+        charge_threshold_supported: thresholds.supported,
+        charge_threshold_enabled: thresholds.enabled,
+        charge_start_threshold: thresholds.start,
+        charge_end_threshold: thresholds.end,
         voltage: prop_f64(props, "Voltage"),
         temperature: prop_f64(props, "Temperature"),
         serial: prop_string(props, "Serial"),
@@ -680,6 +783,26 @@ fn merge_properties_into_cache(cached: &mut BatteryInfo, changed: &HashMap<Strin
         cached.energy_full_design = Some(val);
     }
 
+    if let Some(val) = prop_f64(changed, "EnergyRate") {
+        cached.energy_rate = Some(val);
+    }
+
+    if let Some(val) = prop_bool(changed, "ChargeThresholdSupported") {
+        cached.charge_threshold_supported = val;
+    }
+
+    if let Some(val) = prop_bool(changed, "ChargeThresholdEnabled") {
+        cached.charge_threshold_enabled = val;
+    }
+
+    if let Some(val) = prop_u32(changed, "ChargeStartThreshold") {
+        cached.charge_start_threshold = Some(val);
+    }
+
+    if let Some(val) = prop_u32(changed, "ChargeEndThreshold") {
+        cached.charge_end_threshold = Some(val);
+    }
+
     if let Some(val) = prop_f64(changed, "Voltage") {
         cached.voltage = Some(val);
     }
@@ -774,3 +897,418 @@ async fn find_battery_path(conn: &Connection) -> Option<String> {
     }
     None
 }
+
+// ---- Peripheral batteries ----
+
+/// A peripheral below this gets a single low-battery notification...
+const PERIPHERAL_LOW_PERCENT: f64 = 15.0;
+/// ...and another one only after it has charged past this.
+const PERIPHERAL_RECOVERED_PERCENT: f64 = 25.0;
+
+/// UPower `WarningLevel` values that mean "low" or worse.
+const WARNING_LEVEL_LOW: u32 = 3;
+
+const UPOWER_DEVICES_PATH: &str = "/org/freedesktop/UPower/devices";
+
+/// Current peripheral batteries, for the command.
+pub fn peripheral_batteries() -> Vec<PeripheralBattery> {
+    PERIPHERALS.lock().unwrap().values().cloned().collect()
+}
+
+pub(crate) fn state_name(state: u32) -> &'static str {
+    match state {
+        1 => "Charging",
+        2 => "Discharging",
+        3 => "Empty",
+        4 => "FullyCharged",
+        5 => "PendingCharge",
+        6 => "PendingDischarge",
+        _ => "Unknown",
+    }
+}
+
+/// UPower `Type` as a name for the frontend. `None` for the machine's own
+/// power: the AC adapter and the battery that powers the system.
+fn peripheral_kind(device_type: u32, power_supply: bool) -> Option<&'static str> {
+    Some(match device_type {
+        0 | 1 => return None,
+        2 if power_supply => return None,
+        2 => "battery",
+        3 => "ups",
+        4 => "monitor",
+        5 => "mouse",
+        6 => "keyboard",
+        7 => "pda",
+        8 => "phone",
+        9 => "media-player",
+        10 => "tablet",
+        11 => "computer",
+        12 => "gaming-input",
+        13 => "pen",
+        14 => "touchpad",
+        15 => "modem",
+        16 => "network",
+        17 => "headset",
+        18 => "speakers",
+        19 => "headphones",
+        20 => "video",
+        21 => "other-audio",
+        22 => "remote-control",
+        23 => "printer",
+        24 => "scanner",
+        25 => "camera",
+        26 => "wearable",
+        27 => "toy",
+        _ => "bluetooth-generic",
+    })
+}
+
+fn parse_peripheral(path: &str, props: &HashMap<String, OwnedValue>) -> Option<PeripheralBattery> {
+    let kind = peripheral_kind(
+        prop_u32(props, "Type")?,
+        prop_bool(props, "PowerSupply").unwrap_or(false),
+    )?;
+
+    // Paired but switched off: UPower keeps the device around with no charge.
+    if prop_bool(props, "IsPresent") == Some(false) {
+        return None;
+    }
+
+    Some(PeripheralBattery {
+        id: path.to_string(),
+        kind: kind.to_string(),
+        model: prop_string(props, "Model"),
+        percentage: prop_f64(props, "Percentage").unwrap_or(0.0),
+        state: state_name(prop_u32(props, "State").unwrap_or(0)).to_string(),
+        icon: prop_string(props, "IconName"),
+        warning_level: prop_u32(props, "WarningLevel").unwrap_or(0),
+    })
+}
+
+fn merge_peripheral(device: &mut PeripheralBattery, changed: &HashMap<String, OwnedValue>) {
+    if let Some(val) = prop_f64(changed, "Percentage") {
+        device.percentage = val;
+    }
+    if let Some(val) = prop_u32(changed, "State") {
+        device.state = state_name(val).to_string();
+    }
+    if changed.contains_key("Model") {
+        device.model = prop_string(changed, "Model");
+    }
+    if changed.contains_key("IconName") {
+        device.icon = prop_string(changed, "IconName");
+    }
+    if let Some(val) = prop_u32(changed, "WarningLevel") {
+        device.warning_level = val;
+    }
+}
+
+fn is_low(device: &PeripheralBattery) -> bool {
+    device.state != "Charging"
+        && (device.percentage <= PERIPHERAL_LOW_PERCENT || device.warning_level >= WARNING_LEVEL_LOW)
+}
+
+fn has_recovered(device: &PeripheralBattery) -> bool {
+    device.state == "Charging"
+        || (device.percentage >= PERIPHERAL_RECOVERED_PERCENT && device.warning_level < WARNING_LEVEL_LOW)
+}
+
+async fn fetch_device_props(conn: &Connection, path: &str) -> Option<HashMap<String, OwnedValue>> {
+    let props_proxy = zbus::Proxy::new(
+        conn,
+        "org.freedesktop.UPower",
+        path,
+        "org.freedesktop.DBus.Properties",
+    )
+    .await
+    .ok()?;
+
+    tokio::time::timeout(
+        Duration::from_millis(300),
+        props_proxy.call_method("GetAll", &("org.freedesktop.UPower.Device",)),
+    )
+    .await
+    .ok()?
+    .ok()?
+    .body()
+    .deserialize()
+    .ok()
+}
+
+/// Reads one device from UPower and stores it, or forgets it if it is not a
+/// peripheral (or went away).
+async fn refresh_peripheral(conn: &Connection, path: &str) {
+    let device = fetch_device_props(conn, path)
+        .await
+        .and_then(|props| parse_peripheral(path, &props));
+
+    let mut peripherals = PERIPHERALS.lock().unwrap();
+    match device {
+        Some(device) => {
+            peripherals.insert(path.to_string(), device);
+        }
+        None => {
+            peripherals.remove(path);
+        }
+    }
+}
+
+async fn reload_peripherals(conn: &Connection) -> Result<(), String> {
+    let upower_proxy = zbus::Proxy::new(
+        conn,
+        "org.freedesktop.UPower",
+        "/org/freedesktop/UPower",
+        "org.freedesktop.UPower",
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let devices: Vec<zbus::zvariant::OwnedObjectPath> = tokio::time::timeout(
+        Duration::from_millis(500),
+        upower_proxy.call_method("EnumerateDevices", &()),
+    )
+    .await
+    .map_err(|_| "EnumerateDevices timed out".to_string())?
+    .map_err(|e| e.to_string())?
+    .body()
+    .deserialize()
+    .map_err(|e| e.to_string())?;
+
+    PERIPHERALS.lock().unwrap().clear();
+    for path in devices {
+        refresh_peripheral(conn, path.as_str()).await;
+    }
+    Ok(())
+}
+
+/// Emits the list when it changed and notifies about peripherals that just
+/// ran low.
+async fn publish_peripherals(
+    app: &AppHandle,
+    last: &mut Option<Vec<PeripheralBattery>>,
+    warned: &mut HashSet<String>,
+) {
+    let current = peripheral_batteries();
+    if last.as_ref() == Some(&current) {
+        return;
+    }
+
+    let _ = app.emit("peripheral-batteries-changed", &current);
+
+    warned.retain(|id| {
+        current
+            .iter()
+            .any(|device| &device.id == id && !has_recovered(device))
+    });
+    for device in current.iter().filter(|device| is_low(device)) {
+        if !warned.insert(device.id.clone()) {
+            continue;
+        }
+
+        let name = device.model.clone().unwrap_or_else(|| device.kind.clone());
+        log::info!("[battery] {} low: {:.0}%", name, device.percentage);
+        let body = format!("{}: {:.0}%", name, device.percentage);
+        if let Err(e) = send_system_notification(
+            localized(app, "battery.peripheralLow"),
+            Some(body),
+            Some("normal".to_string()),
+        )
+        .await
+        {
+            log::warn!("[battery] Could not notify low peripheral battery: {}", e);
+        }
+    }
+
+    *last = Some(current);
+}
+
+async fn monitor_peripherals(app: AppHandle) {
+    let mut last = None;
+    let mut warned = HashSet::new();
+
+    loop {
+        if let Some(conn) = get_system_connection(&app).await {
+            if let Err(e) = watch_peripherals(&app, &conn, &mut last, &mut warned).await {
+                log::warn!("[battery] Peripheral monitor stopped: {}", e);
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    }
+}
+
+/// Same approach as the system battery: a match rule per signal, so the bus
+/// actually routes them to us. Devices under UPower's namespace report their
+/// changes with PropertiesChanged; pairing or unplugging one comes as
+/// DeviceAdded/DeviceRemoved on the manager.
+async fn watch_peripherals(
+    app: &AppHandle,
+    conn: &Connection,
+    last: &mut Option<Vec<PeripheralBattery>>,
+    warned: &mut HashSet<String>,
+) -> Result<(), String> {
+    let bad_rule = |e: zbus::Error| format!("bad match rule: {e}");
+
+    let changes_rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.DBus.Properties")
+        .map_err(bad_rule)?
+        .member("PropertiesChanged")
+        .map_err(bad_rule)?
+        .path_namespace(UPOWER_DEVICES_PATH)
+        .map_err(bad_rule)?
+        .arg(0, "org.freedesktop.UPower.Device")
+        .map_err(bad_rule)?
+        .build();
+    let devices_rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.UPower")
+        .map_err(bad_rule)?
+        .path("/org/freedesktop/UPower")
+        .map_err(bad_rule)?
+        .build();
+
+    // Subscribe before listing, so a change in between is not lost.
+    let mut changes = MessageStream::for_match_rule(changes_rule, conn, Some(32))
+        .await
+        .map_err(|e| format!("could not subscribe to UPower devices: {e}"))?;
+    let mut devices = MessageStream::for_match_rule(devices_rule, conn, Some(16))
+        .await
+        .map_err(|e| format!("could not subscribe to UPower: {e}"))?;
+
+    reload_peripherals(conn).await?;
+    publish_peripherals(app, last, warned).await;
+
+    loop {
+        tokio::select! {
+            msg = changes.next() => {
+                let msg = match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => return Err(format!("D-Bus stream error: {e}")),
+                    None => return Err("D-Bus connection closed".to_string()),
+                };
+                let Some(path) = msg.header().path().map(|p| p.to_string()) else {
+                    continue;
+                };
+                // The system battery has its own monitor.
+                if BATTERY_DEVICE_PATH.lock().unwrap().as_deref() == Some(path.as_str()) {
+                    continue;
+                }
+
+                let merged = msg
+                    .body()
+                    .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                    .ok()
+                    .filter(|(_, _, invalidated)| invalidated.is_empty())
+                    .and_then(|(_, changed, _)| {
+                        let mut peripherals = PERIPHERALS.lock().unwrap();
+                        let device = peripherals.get_mut(&path)?;
+                        merge_peripheral(device, &changed);
+                        Some(())
+                    });
+                if merged.is_none() {
+                    refresh_peripheral(conn, &path).await;
+                }
+                publish_peripherals(app, last, warned).await;
+            }
+
+            msg = devices.next() => {
+                let msg = match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => return Err(format!("D-Bus stream error: {e}")),
+                    None => return Err("D-Bus connection closed".to_string()),
+                };
+                let member = msg.header().member().map(|m| m.to_string());
+                let Ok(path) = msg.body().deserialize::<zbus::zvariant::OwnedObjectPath>() else {
+                    continue;
+                };
+
+                match member.as_deref() {
+                    Some("DeviceAdded") => refresh_peripheral(conn, path.as_str()).await,
+                    Some("DeviceRemoved") => {
+                        PERIPHERALS.lock().unwrap().remove(path.as_str());
+                    }
+                    _ => continue,
+                }
+                publish_peripherals(app, last, warned).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn props(entries: &[(&str, Value<'_>)]) -> HashMap<String, OwnedValue> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), OwnedValue::try_from(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn the_system_battery_and_ac_are_not_peripherals() {
+        let laptop = props(&[("Type", Value::U32(2)), ("PowerSupply", Value::Bool(true))]);
+        let ac = props(&[("Type", Value::U32(1)), ("PowerSupply", Value::Bool(true))]);
+        assert!(parse_peripheral("/bat", &laptop).is_none());
+        assert!(parse_peripheral("/ac", &ac).is_none());
+    }
+
+    #[test]
+    fn a_mouse_is_parsed_and_updated() {
+        let path = "/org/freedesktop/UPower/devices/mouse_hidpp_battery_0";
+        let mut mouse = parse_peripheral(
+            path,
+            &props(&[
+                ("Type", Value::U32(5)),
+                ("PowerSupply", Value::Bool(false)),
+                ("Model", Value::from("MX Master 3")),
+                ("Percentage", Value::F64(40.0)),
+                ("State", Value::U32(2)),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(mouse.kind, "mouse");
+        assert_eq!(mouse.model.as_deref(), Some("MX Master 3"));
+        assert!(!is_low(&mouse));
+
+        merge_peripheral(&mut mouse, &props(&[("Percentage", Value::F64(10.0))]));
+        assert!(is_low(&mouse));
+        assert!(!has_recovered(&mouse));
+
+        merge_peripheral(&mut mouse, &props(&[("State", Value::U32(1))]));
+        assert!(!is_low(&mouse));
+        assert!(has_recovered(&mouse));
+    }
+
+    #[test]
+    fn thresholds_come_from_upower_when_it_reports_them() {
+        let sysfs = charge_limit::Thresholds {
+            supported: true,
+            enabled: true,
+            start: Some(75),
+            end: Some(80),
+        };
+
+        let old_upower = props(&[
+            ("IsPresent", Value::Bool(true)),
+            ("Percentage", Value::F64(55.0)),
+        ]);
+        let info = parse_battery_info_from_props(&old_upower, &sysfs);
+        assert!(info.charge_threshold_enabled);
+        assert_eq!(info.charge_end_threshold, Some(80));
+
+        let new_upower = props(&[
+            ("IsPresent", Value::Bool(true)),
+            ("ChargeThresholdSupported", Value::Bool(true)),
+            ("ChargeThresholdEnabled", Value::Bool(false)),
+            ("ChargeEndThreshold", Value::U32(90)),
+        ]);
+        let info = parse_battery_info_from_props(&new_upower, &sysfs);
+        assert!(info.charge_threshold_supported);
+        assert!(!info.charge_threshold_enabled);
+        assert_eq!(info.charge_start_threshold, None);
+        assert_eq!(info.charge_end_threshold, Some(90));
+    }
+}
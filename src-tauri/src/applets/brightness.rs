@@ -4,7 +4,9 @@ use tauri::{AppHandle, Emitter};
 use std::error::Error;
 use std::path::PathBuf;
 
+use crate::brightness::{claim_shell_write, list_devices, percent_of, BacklightDevice};
 use crate::commands::osd::show_osd_internal;
+use crate::structs::BrightnessInfo;
 
 pub struct BrightnessApplet;
 
@@ -21,31 +23,46 @@ impl Applet for BrightnessApplet {
     }
 }
 
+/// One monitor per device: every display backlight and keyboard backlight
+/// reports its own changes.
 fn monitor_brightness(app: AppHandle) {
-    tokio::spawn(async move {
-        let device_path = match find_backlight_device() {
-            Some(p) => p,
-            None => {
-                log::warn!("No backlight device found under /sys/class/backlight. Brightness monitoring disabled.");
-                return;
-            }
-        };
+    let devices = list_devices();
+    if devices.is_empty() {
+        log::warn!("No backlight device found under /sys/class. Brightness monitoring disabled.");
+        return;
+    }
 
-        let brightness_path = device_path.join("actual_brightness");
-        let max_path = device_path.join("max_brightness");
+    for device in devices {
+        let app = app.clone();
+        tokio::spawn(async move {
+            // Displays expose what the hardware actually shows in
+            // actual_brightness; LEDs only have brightness.
+            let actual = device.path.join("actual_brightness");
+            let brightness_path = if actual.exists() {
+                actual
+            } else {
+                device.brightness_path()
+            };
+            let max_path = device.max_brightness_path();
 
-        // Try inotify first; if it fails, fall back to adaptive polling
-        if !try_inotify_monitor(app.clone(), brightness_path.clone(), max_path.clone()).await {
-            log::warn!("inotify monitoring failed, falling back to adaptive polling for brightness");
-            adaptive_poll_monitor(app, brightness_path, max_path).await;
-        }
-    });
+            // Try inotify first; if it fails, fall back to adaptive polling
+            if !try_inotify_monitor(app.clone(), &device, brightness_path.clone(), max_path.clone()).await {
+                log::warn!("inotify monitoring failed for {}, falling back to adaptive polling", device.id());
+                adaptive_poll_monitor(app, &device, brightness_path, max_path).await;
+            }
+        });
+    }
 }
 
 /// Attempts to set up inotify-based monitoring on the brightness sysfs file.
 /// Returns `true` if inotify is running (the function will continue running until error),
 /// or `false` if inotify setup failed and we should fall back to polling.
-async fn try_inotify_monitor(app: AppHandle, brightness_path: PathBuf, max_path: PathBuf) -> bool {
+async fn try_inotify_monitor(
+    app: AppHandle,
+    device: &BacklightDevice,
+    brightness_path: PathBuf,
+    max_path: PathBuf,
+) -> bool {
     use inotify::{Inotify, WatchMask};
     use futures_util::StreamExt;
 
@@ -88,9 +105,9 @@ async fn try_inotify_monitor(app: AppHandle, brightness_path: PathBuf, max_path:
         }
     };
 
-    // Emit initial brightness
+    // Emit initial brightness, without an OSD: nothing changed yet
     if let Ok(max) = read_int_file(&max_path).await {
-        emit_brightness(&app, initial_value, max).await;
+        emit_brightness(&app, device, initial_value, max, false).await;
     }
 
     // Validate inotify works: retry until a real inotify event confirms the driver
@@ -105,7 +122,7 @@ async fn try_inotify_monitor(app: AppHandle, brightness_path: PathBuf, max_path:
                 if let Ok(current) = read_int_file(&brightness_path).await {
                     last_value = current;
                     if let Ok(max) = read_int_file(&max_path).await {
-                        emit_brightness(&app, current, max).await;
+                        emit_brightness(&app, device, current, max, true).await;
                     }
                 }
                 break;
@@ -149,7 +166,7 @@ async fn try_inotify_monitor(app: AppHandle, brightness_path: PathBuf, max_path:
                         if current != last_value {
                             last_value = current;
                             if let Ok(max) = read_int_file(&max_path).await {
-                                emit_brightness(&app, current, max).await;
+                                emit_brightness(&app, device, current, max, true).await;
                             }
                         }
                     }
@@ -172,13 +189,18 @@ async fn try_inotify_monitor(app: AppHandle, brightness_path: PathBuf, max_path:
 
     // inotify failed mid-operation, fall back to polling
     log::warn!("inotify monitor stopped, switching to adaptive polling fallback");
-    adaptive_poll_monitor(app, brightness_path, max_path).await;
+    adaptive_poll_monitor(app, device, brightness_path, max_path).await;
     true // We handled everything (including fallback)
 }
 
 /// Adaptive polling fallback: 2000ms slow, 200ms fast when changes detected,
 /// 5000ms on read failure until recovery.
-async fn adaptive_poll_monitor(app: AppHandle, brightness_path: PathBuf, max_path: PathBuf) {
+async fn adaptive_poll_monitor(
+    app: AppHandle,
+    device: &BacklightDevice,
+    brightness_path: PathBuf,
+    max_path: PathBuf,
+) {
     let mut interval_ms: u64 = 2000;
     let mut no_change_count: u32 = 0;
     let mut last_val: i32 = -1;
@@ -191,12 +213,14 @@ async fn adaptive_poll_monitor(app: AppHandle, brightness_path: PathBuf, max_pat
 
         if let (Ok(current), Ok(max)) = (current_res, max_res) {
             if current != last_val {
-                // Value changed - switch to fast polling
+                // Value changed - switch to fast polling. The first reading
+                // is the starting point, not a change worth an OSD.
+                let announce = last_val >= 0;
                 last_val = current;
                 interval_ms = 200;
                 no_change_count = 0;
 
-                emit_brightness(&app, current, max).await;
+                emit_brightness(&app, device, current, max, announce).await;
             } else {
                 // No change
                 no_change_count += 1;
@@ -213,44 +237,29 @@ async fn adaptive_poll_monitor(app: AppHandle, brightness_path: PathBuf, max_pat
     }
 }
 
-/// Emit a brightness-changed event to the frontend.
-async fn emit_brightness(app: &AppHandle, current: i32, max: i32) {
-    let percentage = if max > 0 {
-        (current as f64 / max as f64 * 100.0).round() as u8
-    } else {
-        0
-    };
+/// Emit the device's change event (`brightness-changed` for displays,
+/// `keyboard-brightness-changed` for keyboards) and, if `announce`, its OSD.
+///
+/// Changes the shell made itself never get the OSD here: a key press already
+/// showed one, and auto-brightness steps and slider drags should not.
+async fn emit_brightness(app: &AppHandle, device: &BacklightDevice, current: i32, max: i32, announce: bool) {
+    let announce = announce && !claim_shell_write(device, current.max(0) as u32);
+    let percentage = percent_of(device, current.max(0) as u32, max.max(0) as u32);
 
     let _ = app.emit(
-        "brightness-changed",
-        serde_json::json!({
-            "current": percentage,
-            "max": 100,
-            "min": 0
-        }),
+        device.kind.changed_event(),
+        BrightnessInfo {
+            current: percentage,
+            max: 100,
+            min: 0,
+            device: device.id(),
+        },
     );
 
-    let _ = show_osd_internal(
-        "display-brightness",
-        percentage as f64,
-        100.0,
-        "osd.brightness",
-        app,
-    ).await;
-}
-
-fn find_backlight_device() -> Option<PathBuf> {
-    let base = std::path::Path::new("/sys/class/backlight");
-    if !base.exists() {
-        return None;
-    }
-
-    if let Ok(entries) = std::fs::read_dir(base) {
-        if let Some(entry) = entries.flatten().next() {
-            return Some(entry.path());
-        }
+    if announce {
+        let (icon, label) = device.kind.osd();
+        let _ = show_osd_internal(icon, percentage as f64, 100.0, label, app).await;
     }
-    None
 }
 
 async fn read_int_file(path: &std::path::Path) -> Result<i32, std::io::Error> {
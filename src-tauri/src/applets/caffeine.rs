@@ -0,0 +1,145 @@
+use super::Applet;
+use async_trait::async_trait;
+use std::error::Error;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::caffeine::{changed, on_battery, with_caffeine, Locks};
+use crate::dbus_pool::DbusPool;
+use crate::session_actions::system_bus;
+use crate::settings;
+use crate::window_manager::wayfire_ipc::get_wayfire_client;
+
+pub struct CaffeineApplet;
+
+/// How often the countdown is re-emitted and the timer checked.
+const TICK: Duration = Duration::from_secs(1);
+
+/// How often fullscreen windows and players are looked up while an
+/// automatic trigger is enabled.
+const DETECT_EVERY: Duration = Duration::from_secs(3);
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[async_trait]
+impl Applet for CaffeineApplet {
+    fn name(&self) -> &'static str {
+        "caffeine"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        tokio::spawn(run(app));
+        Ok(())
+    }
+}
+
+async fn run(app: AppHandle) {
+    let mut locks: Option<Locks> = None;
+    let mut last_state = None;
+    let mut last_detect: Option<Instant> = None;
+
+    loop {
+        let settings = settings::get().caffeine;
+        let now = Instant::now();
+
+        if last_detect.is_none_or(|at| now.duration_since(at) >= DETECT_EVERY) {
+            last_detect = Some(now);
+            let fullscreen = settings.auto_fullscreen && fullscreen_focused().await;
+            let playing = settings.auto_media && media_playing(&app).await;
+            let _ = with_caffeine(|caffeine| {
+                caffeine.fullscreen = fullscreen;
+                caffeine.playing = playing;
+            });
+        }
+
+        let Ok((reason, state)) = with_caffeine(|caffeine| {
+            if caffeine.expire(now, on_battery()) {
+                log::info!("Caffeine: timer ended");
+            }
+            (caffeine.reason(&settings), caffeine.state(&settings, now))
+        }) else {
+            return;
+        };
+
+        match (reason, locks.is_some()) {
+            (Some(reason), false) => {
+                log::info!("Caffeine: keeping the system awake ({:?})", reason);
+                let system = system_bus(&app).await.ok();
+                locks = Some(Locks::acquire(system.as_ref()).await);
+            }
+            (None, true) => {
+                log::info!("Caffeine: released");
+                locks = None;
+            }
+            _ => {}
+        }
+
+        if last_state.as_ref() != Some(&state) {
+            let _ = app.emit("caffeine-changed", &state);
+            last_state = Some(state);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(TICK) => {}
+            _ = changed().notified() => {
+                // A manual change may come with new settings: look again.
+                last_detect = None;
+            }
+        }
+    }
+}
+
+async fn fullscreen_focused() -> bool {
+    let Some(client) = get_wayfire_client().await else {
+        return false;
+    };
+    match client.list_views_typed().await {
+        Ok(views) => views
+            .iter()
+            .any(|view| view.activated && view.fullscreen == Some(true)),
+        Err(e) => {
+            log::debug!("Caffeine: could not list views: {}", e);
+            false
+        }
+    }
+}
+
+async fn media_playing(app: &AppHandle) -> bool {
+    let Some(connection) = session_bus(app).await else {
+        return false;
+    };
+    let Ok(dbus) = zbus::fdo::DBusProxy::new(&connection).await else {
+        return false;
+    };
+    let Ok(names) = dbus.list_names().await else {
+        return false;
+    };
+
+    for name in names.iter().filter(|name| name.starts_with(MPRIS_PREFIX)) {
+        let status = tokio::time::timeout(
+            Duration::from_millis(300),
+            connection.call_method(
+                Some(name.as_str()),
+                "/org/mpris/MediaPlayer2",
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &("org.mpris.MediaPlayer2.Player", "PlaybackStatus"),
+            ),
+        )
+        .await;
+        let playing = status
+            .ok()
+            .and_then(|reply| reply.ok())
+            .and_then(|reply| reply.body().deserialize::<zbus::zvariant::OwnedValue>().ok())
+            .and_then(|value| String::try_from(value).ok())
+            .is_some_and(|status| status == "Playing");
+        if playing {
+            return true;
+        }
+    }
+    false
+}
+
+async fn session_bus(app: &AppHandle) -> Option<zbus::Connection> {
+    app.try_state::<DbusPool>()?.session().await
+}
@@ -8,6 +8,7 @@ use std::{fs, io};
 use std::os::unix::io::AsRawFd;
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::structs::VolumeInfo;
 use crate::windows_apps::create_osd_window;
 
 // ─── e v d e v   C a p s   L o c k   m o n i t o r ───────────────────────────
@@ -134,22 +135,21 @@ fn show_osd_sync(icon: &str, value: f64, maximum: f64, label: &str, app: &AppHan
     }
 }
 
-// ─── M i c   m u t e   v i a   P u l s e A u d i o ───────────────────────────
+// ─── M i c   m u t e ──────────────────────────────────────────────────────────
 
-/// Reads the current microphone mute state.
-async fn read_mic_muted() -> Option<bool> {
-    let output = tokio::process::Command::new("pactl")
-        .args(["get-source-mute", "@DEFAULT_SOURCE@"])
-        .output()
+/// Reads the current microphone volume and mute state.
+///
+/// Goes through the audio backend like the mic commands: PipeWire's native
+/// graph when the monitor runs, otherwise `pw-dump` or `pactl`. Those last two
+/// block on a child process, so it runs on the blocking pool.
+async fn read_mic_state() -> Option<VolumeInfo> {
+    tokio::task::spawn_blocking(crate::audio::get_mic_volume)
         .await
-        .ok()?;
-
-    String::from_utf8(output.stdout)
+        .ok()?
         .ok()
-        .map(|text| text.trim().contains("yes"))
 }
 
-async fn announce_mic_state(app: &AppHandle, muted: bool) {
+pub(crate) async fn announce_mic_state(app: &AppHandle, muted: bool) {
     let (label, icon) = if muted {
         ("osd.micMuted", "microphone-sensitivity-muted")
     } else {
@@ -167,7 +167,7 @@ async fn announce_mic_state(app: &AppHandle, muted: bool) {
     .await;
 }
 
-/// Follows the microphone mute state through `pactl subscribe`.
+/// Follows the microphone volume and mute state through `pactl subscribe`.
 ///
 /// This used to fork a `pactl get-source-mute` process **every second** for the
 /// whole session — around 86,000 processes a day — to notice a change that
@@ -180,7 +180,7 @@ fn spawn_mic_monitor(app: AppHandle, running: Arc<AtomicBool>) {
         use tokio::process::Command;
         use std::process::Stdio;
 
-        let mut last_muted: Option<bool> = read_mic_muted().await;
+        let mut last_state: Option<VolumeInfo> = read_mic_state().await;
         let mut backoff = std::time::Duration::from_secs(1);
 
         while running.load(Ordering::Relaxed) {
@@ -213,21 +213,32 @@ fn spawn_mic_monitor(app: AppHandle, running: Arc<AtomicBool>) {
             while running.load(Ordering::Relaxed) {
                 match lines.next_line().await {
                     Ok(Some(line)) => {
-                        // Only source events can change the microphone.
-                        if !line.contains("source") {
+                        // Only source events can change the microphone;
+                        // "source-output" is an application recording.
+                        if !line.contains(" on source #") {
                             continue;
                         }
 
-                        let Some(muted) = read_mic_muted().await else {
+                        // Plugging a microphone in or out is AudioPolicyApplet's
+                        // business; here only the level and mute matter.
+                        let Some(state) = read_mic_state().await else {
                             continue;
                         };
 
-                        if last_muted == Some(muted) {
+                        if last_state.as_ref() == Some(&state) {
                             continue;
                         }
-                        last_muted = Some(muted);
+                        let mute_changed = last_state
+                            .as_ref()
+                            .map(|last| last.is_muted != state.is_muted)
+                            .unwrap_or(true);
+                        last_state = Some(state.clone());
 
-                        announce_mic_state(&app, muted).await;
+                        let _ = app.emit("mic-volume-changed", &state);
+
+                        if mute_changed {
+                            announce_mic_state(&app, state.is_muted).await;
+                        }
                     }
                     // PulseAudio went away; reconnect rather than going deaf.
                     Ok(None) | Err(_) => break,
@@ -0,0 +1,137 @@
+use super::Applet;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use std::error::Error;
+use std::os::fd::OwnedFd;
+use std::time::Duration;
+use tauri::AppHandle;
+use zbus::{Connection, MatchRule, Message, MessageStream};
+
+use crate::lock;
+use crate::session_actions::system_bus;
+use crate::settings;
+
+pub struct LockApplet;
+
+/// How long to wait before trying logind again after losing it.
+const RETRY: Duration = Duration::from_secs(30);
+
+#[async_trait]
+impl Applet for LockApplet {
+    fn name(&self) -> &'static str {
+        "lock"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        tokio::spawn(run(app));
+        Ok(())
+    }
+}
+
+async fn run(app: AppHandle) {
+    loop {
+        let Ok(connection) = system_bus(&app).await else {
+            log::warn!("Lock: no system bus connection");
+            tokio::time::sleep(RETRY).await;
+            continue;
+        };
+        if let Err(e) = listen(&app, &connection).await {
+            log::warn!("Lock: lost logind: {}", e);
+        }
+        tokio::time::sleep(RETRY).await;
+    }
+}
+
+/// Handles `Lock`/`Unlock` for our session (`loginctl lock-session`, the
+/// idle manager) and locks before the machine goes to sleep.
+async fn listen(app: &AppHandle, connection: &Connection) -> zbus::Result<()> {
+    let session = lock::session_path(connection)
+        .await
+        .map_err(|e| zbus::Error::Failure(e.to_string()))?;
+    log::info!("Lock: listening on {}", session.as_str());
+
+    let session_rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.login1.Session")?
+        .path(session.as_str())?
+        .build();
+    let sleep_rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.login1.Manager")?
+        .member("PrepareForSleep")?
+        .path("/org/freedesktop/login1")?
+        .build();
+    let mut session_signals = MessageStream::for_match_rule(session_rule, connection, Some(8)).await?;
+    let mut sleep_signals = MessageStream::for_match_rule(sleep_rule, connection, Some(8)).await?;
+
+    let mut delay = take_delay(connection).await;
+
+    loop {
+        tokio::select! {
+            msg = session_signals.next() => {
+                let msg = msg.ok_or_else(|| zbus::Error::Failure("stream closed".into()))??;
+                match member(&msg).as_deref() {
+                    Some("Lock") => {
+                        if let Err(e) = lock::lock(app).await {
+                            log::error!("Lock: could not lock: {}", e);
+                        }
+                    }
+                    Some("Unlock") => lock::unlock(),
+                    _ => {}
+                }
+            }
+            msg = sleep_signals.next() => {
+                let msg = msg.ok_or_else(|| zbus::Error::Failure("stream closed".into()))??;
+                let Ok(going_to_sleep) = msg.body().deserialize::<bool>() else {
+                    continue;
+                };
+                if going_to_sleep {
+                    if delay.is_some() && settings::get().lock.lock_before_sleep {
+                        log::info!("Lock: locking before sleep");
+                        match lock::lock(app).await {
+                            Ok(()) => tokio::time::sleep(lock::SETTLE).await,
+                            Err(e) => log::error!("Lock: could not lock before sleep: {}", e),
+                        }
+                    }
+                    // Dropping the descriptor lets logind carry on.
+                    delay = None;
+                } else {
+                    delay = take_delay(connection).await;
+                }
+            }
+        }
+    }
+}
+
+fn member(msg: &Message) -> Option<String> {
+    msg.header().member().map(|member| member.to_string())
+}
+
+/// A delay inhibitor holds suspend back (up to logind's `InhibitDelayMaxSec`)
+/// until it is released, which leaves time to lock first.
+async fn take_delay(connection: &Connection) -> Option<OwnedFd> {
+    if !settings::get().lock.lock_before_sleep {
+        return None;
+    }
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1",
+            Some("org.freedesktop.login1.Manager"),
+            "Inhibit",
+            &(
+                "sleep",
+                "Vasak",
+                "Bloquear la pantalla antes de suspender",
+                "delay",
+            ),
+        )
+        .await;
+    match reply.and_then(|reply| reply.body().deserialize::<zbus::zvariant::OwnedFd>()) {
+        Ok(fd) => Some(fd.into()),
+        Err(e) => {
+            log::warn!("Lock: no sleep delay inhibitor: {}", e);
+            None
+        }
+    }
+}
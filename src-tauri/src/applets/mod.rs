@@ -6,14 +6,20 @@ pub mod manager;
 
 // Modules for specific applets
 pub mod audio;
+pub mod audio_policy;
+pub mod auto_brightness;
 pub mod battery;
 pub mod bluetooth;
 pub mod brightness;
+pub mod caffeine;
 pub mod connect;
 pub mod keyboard_leds;
+pub mod lock;
 pub mod music;
 pub mod network;
+pub mod night_light;
 pub mod notifications;
+pub mod power_profiles;
 pub mod tray;
 
 #[async_trait]
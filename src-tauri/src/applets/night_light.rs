@@ -0,0 +1,77 @@
+use super::Applet;
+use async_trait::async_trait;
+use chrono::Local;
+use std::error::Error;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::night_light_state;
+use crate::gamma;
+use crate::night_light::{changed, set_current_temperature, target_temperature, DAYLIGHT};
+use crate::settings;
+use crate::structs::NightLightState;
+
+/// How often the schedule is checked. Scheduled transitions last tens of
+/// minutes, so each check only moves the temperature a little.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Turning it on or off fades over about a second instead of snapping.
+const FADE_TICK: Duration = Duration::from_millis(50);
+const FADE_STEP: u32 = 150;
+
+pub struct NightLightApplet;
+
+#[async_trait]
+impl Applet for NightLightApplet {
+    fn name(&self) -> &'static str {
+        "night_light"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        tokio::spawn(run(app));
+        Ok(())
+    }
+}
+
+fn step_toward(from: u32, to: u32) -> u32 {
+    if from < to {
+        (from + FADE_STEP).min(to)
+    } else {
+        from.saturating_sub(FADE_STEP).max(to)
+    }
+}
+
+async fn run(app: AppHandle) {
+    let Ok(Some(gamma)) = tokio::task::spawn_blocking(gamma::service).await else {
+        log::warn!("Night light unavailable: no gamma control on this compositor");
+        return;
+    };
+
+    let mut shown = DAYLIGHT;
+    let mut announced: Option<NightLightState> = None;
+
+    loop {
+        let target = target_temperature(&settings::get().night_light, &Local::now());
+
+        if shown != target {
+            shown = step_toward(shown, target);
+            if let Err(e) = gamma.set_temperature(shown) {
+                log::warn!("Night light: could not set {}K: {}", shown, e);
+            }
+            set_current_temperature(shown);
+            tokio::time::sleep(FADE_TICK).await;
+            continue;
+        }
+
+        let state = night_light_state();
+        if announced.as_ref() != Some(&state) {
+            let _ = app.emit("night-light-changed", &state);
+            announced = Some(state);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = changed().notified() => {}
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::Applet;
+use async_trait::async_trait;
+use std::error::Error;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::power_profiles::{self, connect, read_state, watch, PowerProfilesProxy};
+use crate::session_actions::system_bus;
+
+pub struct PowerProfilesApplet;
+
+/// How long to wait before looking for power-profiles-daemon again.
+const RETRY: Duration = Duration::from_secs(60);
+
+#[async_trait]
+impl Applet for PowerProfilesApplet {
+    fn name(&self) -> &'static str {
+        "power_profiles"
+    }
+
+    async fn start(&self, app: AppHandle) -> Result<(), Box<dyn Error>> {
+        tokio::spawn(run(app));
+        Ok(())
+    }
+}
+
+async fn run(app: AppHandle) {
+    loop {
+        let Ok(connection) = system_bus(&app).await else {
+            log::warn!("Power profiles: no system bus connection");
+            tokio::time::sleep(RETRY).await;
+            continue;
+        };
+
+        let proxy = match connect(&connection).await {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                log::info!("Power profiles: daemon not available: {}", e);
+                tokio::time::sleep(RETRY).await;
+                continue;
+            }
+        };
+
+        emit_state(&app, &proxy).await;
+        watch(&proxy, || {
+            let app = app.clone();
+            let proxy = proxy.clone();
+            tokio::spawn(async move { emit_state(&app, &proxy).await });
+        })
+        .await;
+
+        log::warn!("Power profiles: daemon went away");
+        let _ = app.emit(
+            "power-profiles-changed",
+            power_profiles::state(&connection).await,
+        );
+        tokio::time::sleep(RETRY).await;
+    }
+}
+
+async fn emit_state(app: &AppHandle, proxy: &PowerProfilesProxy<'_>) {
+    match read_state(proxy).await {
+        Ok(state) => {
+            let _ = app.emit("power-profiles-changed", state);
+        }
+        Err(e) => log::warn!("Power profiles: could not read state: {}", e),
+    }
+}
@@ -0,0 +1,124 @@
+//! Backends de control de audio.
+//!
+//! Todo lo que lee o cambia el estado del servidor de audio pasa por el trait
+//! [`AudioBackend`]. Antes `audio.rs` recorría la salida legible de
+//! `pactl list sinks`, que cambia con el idioma de la sesión (`Volumen:` en vez
+//! de `Volume:`) y con cada versión de pactl. Los dos backends de acá leen
+//! formatos estructurados:
+//!
+//! 1. `PipeWireBackend` - lee el grafo de PipeWire con `pw-dump` (el mismo JSON
+//!    que sigue `audio_native::PwDumpMonitor`) y escribe con `wpctl`. Con el
+//!    monitor nativo corriendo usa su grafo y sus proxies en lugar de eso.
+//! 2. `PactlBackend` - `pactl --format=json`, para sesiones con PulseAudio o
+//!    sin WirePlumber.
+
+pub mod pactl;
+pub mod pipewire;
+
+use crate::error::{Result, VasakError};
+use crate::logger::log_info;
+use crate::structs::{AudioCard, AudioDevice, VolumeInfo};
+use std::sync::OnceLock;
+
+/// Lado del servidor de audio sobre el que se opera.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// Salidas (parlantes, auriculares, HDMI)
+    Sink,
+    /// Entradas (micrófonos), sin los monitores de las salidas
+    Source,
+}
+
+impl DeviceKind {
+    /// Sustantivo que usa pactl en sus subcomandos (`set-sink-volume`, ...).
+    pub fn noun(self) -> &'static str {
+        match self {
+            DeviceKind::Sink => "sink",
+            DeviceKind::Source => "source",
+        }
+    }
+
+    /// Evento que recibe el frontend cuando cambia la lista de dispositivos.
+    pub fn devices_event(self) -> &'static str {
+        match self {
+            DeviceKind::Sink => "audio-devices-changed",
+            DeviceKind::Source => "audio-sources-changed",
+        }
+    }
+}
+
+pub trait AudioBackend: Send + Sync {
+    /// Nombre corto para los logs.
+    fn name(&self) -> &'static str;
+
+    /// Dispositivos de `kind`, con el que está por defecto marcado.
+    fn list_devices(&self, kind: DeviceKind) -> Result<Vec<AudioDevice>>;
+
+    /// Establece el volumen (en porcentaje) del dispositivo por defecto.
+    fn set_volume(&self, kind: DeviceKind, volume: i64) -> Result<()>;
+
+    /// Silencia o des-silencia el dispositivo por defecto.
+    fn set_mute(&self, kind: DeviceKind, muted: bool) -> Result<()>;
+
+    /// Elige el dispositivo por defecto por su `AudioDevice::id`.
+    fn set_default(&self, kind: DeviceKind, device_id: &str) -> Result<()>;
+
+    /// Tarjetas de audio con sus perfiles.
+    fn list_cards(&self) -> Result<Vec<AudioCard>>;
+
+    /// Activa un puerto (`AudioPort::name`) de un dispositivo.
+    fn set_port(&self, kind: DeviceKind, device_id: &str, port: &str) -> Result<()>;
+
+    /// Activa un perfil (`AudioProfile::name`) de una tarjeta.
+    fn set_card_profile(&self, card_id: &str, profile: &str) -> Result<()>;
+
+    /// Volumen y silencio del dispositivo por defecto.
+    fn get_volume(&self, kind: DeviceKind) -> Result<VolumeInfo> {
+        let devices = self.list_devices(kind)?;
+
+        // Si ninguno figura como por defecto (el servidor todavía no eligió),
+        // el primero de la lista es mejor respuesta que un error.
+        let device = devices
+            .iter()
+            .find(|device| device.is_default)
+            .or_else(|| devices.first())
+            .ok_or_else(|| VasakError::NotFound(format!("No hay ningún {}", kind.noun())))?;
+
+        Ok(VolumeInfo {
+            current: (device.volume * 100.0).round() as i64,
+            min: 0,
+            max: 100,
+            is_muted: device.is_muted,
+        })
+    }
+
+    /// Alterna el silencio y devuelve el estado nuevo.
+    ///
+    /// Leer y después escribir no es atómico: dos pedidos seguidos pueden ver
+    /// el mismo estado y silenciar dos veces. Los backends que pueden pedirle
+    /// al servidor que lo alterne él mismo lo sobreescriben.
+    fn toggle_mute(&self, kind: DeviceKind) -> Result<bool> {
+        let muted = !self.get_volume(kind)?.is_muted;
+        self.set_mute(kind, muted)?;
+        Ok(muted)
+    }
+}
+
+/// Backend elegido la primera vez que se usa
+static BACKEND: OnceLock<Box<dyn AudioBackend>> = OnceLock::new();
+
+/// El backend de audio activo, detectándolo solo la primera vez.
+pub fn backend() -> &'static dyn AudioBackend {
+    BACKEND.get_or_init(detect_backend).as_ref()
+}
+
+fn detect_backend() -> Box<dyn AudioBackend> {
+    let backend: Box<dyn AudioBackend> = if pipewire::PipeWireBackend::is_available() {
+        Box::new(pipewire::PipeWireBackend)
+    } else {
+        Box::new(pactl::PactlBackend)
+    };
+
+    log_info(&format!("Usando {} para control de audio", backend.name()));
+    backend
+}
@@ -0,0 +1,433 @@
+//! Backend `pactl --format=json`.
+//!
+//! Funciona con PulseAudio y con pipewire-pulse. El JSON no se traduce con el
+//! idioma de la sesión, a diferencia de `pactl list`, que es lo que se leía
+//! antes.
+
+use super::{AudioBackend, DeviceKind};
+use crate::constants::CMD_PACTL;
+use crate::error::{Result, VasakError};
+use crate::structs::{AudioCard, AudioDevice, AudioPort, AudioProfile};
+use crate::utils::CommandExecutor;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub struct PactlBackend;
+
+/// Lo que interesa de `pactl --format=json info`.
+#[derive(Debug, Deserialize)]
+struct PactlInfo {
+    default_sink_name: Option<String>,
+    default_source_name: Option<String>,
+}
+
+/// Un elemento de `pactl --format=json list sinks|sources`.
+#[derive(Debug, Deserialize)]
+struct PactlDevice {
+    index: u32,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    mute: bool,
+    /// Un canal por clave: `{"front-left": {"value_percent": "75%", ...}}`
+    #[serde(default)]
+    volume: HashMap<String, Value>,
+    /// Solo en sources: el sink del que es monitor, si lo es.
+    #[serde(default)]
+    monitor_of_sink: Option<String>,
+    #[serde(default)]
+    ports: Vec<PactlPort>,
+    #[serde(default)]
+    active_port: Option<String>,
+    /// Índice de la tarjeta; según la versión llega como número o como texto.
+    #[serde(default)]
+    card: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PactlPort {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    priority: u32,
+    /// "available", "not available" o "availability unknown"
+    #[serde(default)]
+    availability: String,
+}
+
+impl PactlPort {
+    fn into_audio_port(self) -> AudioPort {
+        let available = match self.availability.as_str() {
+            "available" => Some(true),
+            "not available" => Some(false),
+            _ => None,
+        };
+
+        AudioPort {
+            description: if self.description.is_empty() {
+                self.name.clone()
+            } else {
+                self.description
+            },
+            name: self.name,
+            priority: self.priority,
+            available,
+        }
+    }
+}
+
+/// Un elemento de `pactl --format=json list cards`.
+#[derive(Debug, Deserialize)]
+struct PactlCard {
+    index: u32,
+    name: String,
+    #[serde(default)]
+    properties: HashMap<String, Value>,
+    /// Perfil por nombre: `{"a2dp-sink": {"description": ..., "available": true}}`
+    #[serde(default)]
+    profiles: HashMap<String, PactlProfile>,
+    #[serde(default)]
+    active_profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PactlProfile {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    priority: u32,
+    #[serde(default = "default_true")]
+    available: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl PactlCard {
+    fn into_audio_card(self) -> AudioCard {
+        let description = self
+            .properties
+            .get("device.description")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.name)
+            .to_string();
+
+        let mut profiles: Vec<AudioProfile> = self
+            .profiles
+            .into_iter()
+            .map(|(name, profile)| AudioProfile {
+                description: if profile.description.is_empty() {
+                    name.clone()
+                } else {
+                    profile.description
+                },
+                name,
+                priority: profile.priority,
+                available: profile.available,
+            })
+            .collect();
+        // Como los muestra pavucontrol: primero los preferidos por el servidor.
+        profiles.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        AudioCard {
+            id: self.index.to_string(),
+            name: self.name,
+            description,
+            profiles,
+            active_profile: self.active_profile,
+        }
+    }
+}
+
+impl PactlDevice {
+    /// Los sources monitor son la salida de un sink vista como entrada:
+    /// sirven para grabar lo que suena, no son un micrófono.
+    fn is_monitor(&self) -> bool {
+        self.name.ends_with(".monitor")
+            || self
+                .monitor_of_sink
+                .as_deref()
+                .is_some_and(|sink| !sink.is_empty() && sink != "n/a")
+    }
+
+    /// El canal más alto, que es lo que muestran los mezcladores.
+    fn volume_percent(&self) -> Option<f64> {
+        self.volume
+            .values()
+            .filter_map(|channel| channel.get("value_percent")?.as_str())
+            .filter_map(|percent| percent.trim().trim_end_matches('%').parse::<f64>().ok())
+            .reduce(f64::max)
+    }
+
+    fn into_audio_device(self, default_name: Option<&str>) -> AudioDevice {
+        let volume = self.volume_percent().map(|pct| pct / 100.0).unwrap_or(0.5);
+        let is_default = default_name == Some(self.name.as_str());
+        let name = if self.description.is_empty() {
+            self.name.clone()
+        } else {
+            self.description
+        };
+
+        let card_id = match self.card {
+            Some(Value::Number(index)) => Some(index.to_string()),
+            Some(Value::String(index)) if !index.is_empty() && index != "n/a" => Some(index),
+            _ => None,
+        };
+
+        AudioDevice {
+            id: self.index.to_string(),
+            name,
+            description: self.name,
+            is_default,
+            volume,
+            is_muted: self.mute,
+            ports: self
+                .ports
+                .into_iter()
+                .map(PactlPort::into_audio_port)
+                .collect(),
+            active_port: self.active_port,
+            card_id,
+        }
+    }
+}
+
+fn parse_default_name(info_json: &str, kind: DeviceKind) -> Result<String> {
+    let info: PactlInfo = serde_json::from_str(info_json)
+        .map_err(|e| VasakError::Parse(format!("pactl info: {}", e)))?;
+
+    let name = match kind {
+        DeviceKind::Sink => info.default_sink_name,
+        DeviceKind::Source => info.default_source_name,
+    };
+
+    name.filter(|name| !name.is_empty())
+        .ok_or_else(|| VasakError::NotFound(format!("No hay {} por defecto", kind.noun())))
+}
+
+fn parse_devices(
+    list_json: &str,
+    kind: DeviceKind,
+    default_name: Option<&str>,
+) -> Result<Vec<AudioDevice>> {
+    let devices: Vec<PactlDevice> = serde_json::from_str(list_json)
+        .map_err(|e| VasakError::Parse(format!("pactl list {}s: {}", kind.noun(), e)))?;
+
+    Ok(devices
+        .into_iter()
+        .filter(|device| kind == DeviceKind::Sink || !device.is_monitor())
+        .map(|device| device.into_audio_device(default_name))
+        .collect())
+}
+
+fn parse_cards(list_json: &str) -> Result<Vec<AudioCard>> {
+    let cards: Vec<PactlCard> = serde_json::from_str(list_json)
+        .map_err(|e| VasakError::Parse(format!("pactl list cards: {}", e)))?;
+
+    Ok(cards.into_iter().map(PactlCard::into_audio_card).collect())
+}
+
+/// Nombre especial con el que pactl resuelve el dispositivo por defecto en el
+/// momento de aplicar el cambio, sin consultarlo antes.
+fn default_target(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Sink => "@DEFAULT_SINK@",
+        DeviceKind::Source => "@DEFAULT_SOURCE@",
+    }
+}
+
+fn get_default_name(kind: DeviceKind) -> Result<String> {
+    let output = CommandExecutor::run(CMD_PACTL, &["--format=json", "info"])?;
+    parse_default_name(&output, kind)
+}
+
+impl AudioBackend for PactlBackend {
+    fn name(&self) -> &'static str {
+        "pactl --format=json"
+    }
+
+    fn list_devices(&self, kind: DeviceKind) -> Result<Vec<AudioDevice>> {
+        let default_name = get_default_name(kind).ok();
+        let list_arg = format!("{}s", kind.noun());
+        let output = CommandExecutor::run(CMD_PACTL, &["--format=json", "list", &list_arg])?;
+        parse_devices(&output, kind, default_name.as_deref())
+    }
+
+    fn set_volume(&self, kind: DeviceKind, volume: i64) -> Result<()> {
+        let command = format!("set-{}-volume", kind.noun());
+        CommandExecutor::run(
+            CMD_PACTL,
+            &[&command, default_target(kind), &format!("{}%", volume)],
+        )?;
+        Ok(())
+    }
+
+    fn set_mute(&self, kind: DeviceKind, muted: bool) -> Result<()> {
+        let command = format!("set-{}-mute", kind.noun());
+        CommandExecutor::run(
+            CMD_PACTL,
+            &[&command, default_target(kind), if muted { "1" } else { "0" }],
+        )?;
+        Ok(())
+    }
+
+    fn toggle_mute(&self, kind: DeviceKind) -> Result<bool> {
+        let command = format!("set-{}-mute", kind.noun());
+        CommandExecutor::run(CMD_PACTL, &[&command, default_target(kind), "toggle"])?;
+        Ok(self.get_volume(kind)?.is_muted)
+    }
+
+    fn set_default(&self, kind: DeviceKind, device_id: &str) -> Result<()> {
+        let command = format!("set-default-{}", kind.noun());
+        CommandExecutor::run(CMD_PACTL, &[&command, device_id])?;
+        Ok(())
+    }
+
+    fn list_cards(&self) -> Result<Vec<AudioCard>> {
+        let output = CommandExecutor::run(CMD_PACTL, &["--format=json", "list", "cards"])?;
+        parse_cards(&output)
+    }
+
+    fn set_port(&self, kind: DeviceKind, device_id: &str, port: &str) -> Result<()> {
+        let command = format!("set-{}-port", kind.noun());
+        CommandExecutor::run(CMD_PACTL, &[&command, device_id, port])?;
+        Ok(())
+    }
+
+    fn set_card_profile(&self, card_id: &str, profile: &str) -> Result<()> {
+        CommandExecutor::run(CMD_PACTL, &["set-card-profile", card_id, profile])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = include_str!("fixtures/pactl-info.json");
+    const SINKS: &str = include_str!("fixtures/pactl-sinks.json");
+    const SOURCES: &str = include_str!("fixtures/pactl-sources.json");
+    const CARDS: &str = include_str!("fixtures/pactl-cards.json");
+
+    #[test]
+    fn default_names_come_from_info() {
+        assert_eq!(
+            parse_default_name(INFO, DeviceKind::Sink).unwrap(),
+            "alsa_output.pci-0000_00_1f.3.analog-stereo"
+        );
+        assert_eq!(
+            parse_default_name(INFO, DeviceKind::Source).unwrap(),
+            "alsa_input.pci-0000_00_1f.3.analog-stereo"
+        );
+    }
+
+    #[test]
+    fn sinks_are_parsed_with_the_default_marked() {
+        let default = parse_default_name(INFO, DeviceKind::Sink).unwrap();
+        let sinks = parse_devices(SINKS, DeviceKind::Sink, Some(&default)).unwrap();
+
+        assert_eq!(sinks.len(), 2);
+
+        let builtin = &sinks[0];
+        assert_eq!(builtin.id, "55");
+        assert_eq!(builtin.name, "Built-in Audio Analog Stereo");
+        assert_eq!(
+            builtin.description,
+            "alsa_output.pci-0000_00_1f.3.analog-stereo"
+        );
+        assert!(builtin.is_default);
+        assert!(!builtin.is_muted);
+        assert!((builtin.volume - 0.75).abs() < f64::EPSILON);
+
+        let hdmi = &sinks[1];
+        assert!(!hdmi.is_default);
+        assert!(hdmi.is_muted);
+    }
+
+    /// Con el balance corrido, el canal más alto es el que cuenta.
+    #[test]
+    fn unbalanced_channels_report_the_loudest() {
+        let sinks = parse_devices(SINKS, DeviceKind::Sink, None).unwrap();
+        assert!((sinks[1].volume - 0.40).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn monitor_sources_are_hidden() {
+        let default = parse_default_name(INFO, DeviceKind::Source).unwrap();
+        let sources = parse_devices(SOURCES, DeviceKind::Source, Some(&default)).unwrap();
+
+        assert_eq!(sources.len(), 1);
+        assert_eq!(
+            sources[0].description,
+            "alsa_input.pci-0000_00_1f.3.analog-stereo"
+        );
+        assert!(sources[0].is_default);
+    }
+
+    #[test]
+    fn ports_report_availability_and_the_active_one() {
+        let sinks = parse_devices(SINKS, DeviceKind::Sink, None).unwrap();
+        let builtin = &sinks[0];
+
+        assert_eq!(
+            builtin.active_port.as_deref(),
+            Some("analog-output-speaker")
+        );
+        assert_eq!(builtin.card_id.as_deref(), Some("47"));
+        assert_eq!(
+            builtin.ports,
+            vec![
+                AudioPort {
+                    name: "analog-output-speaker".to_string(),
+                    description: "Speakers".to_string(),
+                    priority: 10000,
+                    available: None,
+                },
+                AudioPort {
+                    name: "analog-output-headphones".to_string(),
+                    description: "Headphones".to_string(),
+                    priority: 9900,
+                    available: Some(false),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cards_list_profiles_by_priority() {
+        let cards = parse_cards(CARDS).unwrap();
+        assert_eq!(cards.len(), 2);
+
+        let headset = &cards[1];
+        assert_eq!(headset.id, "82");
+        assert_eq!(headset.description, "WH-1000XM4");
+        assert_eq!(headset.active_profile.as_deref(), Some("headset-head-unit"));
+
+        let names: Vec<&str> = headset.profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["a2dp-sink", "headset-head-unit", "off"]);
+        assert!(headset.profiles.iter().all(|p| p.available));
+
+        let builtin = &cards[0];
+        let hdmi = builtin
+            .profiles
+            .iter()
+            .find(|p| p.name == "output:hdmi-stereo")
+            .expect("hdmi profile");
+        assert!(!hdmi.available);
+    }
+
+    #[test]
+    fn missing_default_is_an_error_not_an_empty_name() {
+        let info = r#"{"default_sink_name":"","default_source_name":null}"#;
+        assert!(parse_default_name(info, DeviceKind::Sink).is_err());
+        assert!(parse_default_name(info, DeviceKind::Source).is_err());
+    }
+}
@@ -0,0 +1,884 @@
+//! Backend PipeWire: lee con `pw-dump`, escribe con `wpctl`.
+//!
+//! `pw-dump` vuelca el grafo entero como JSON —los mismos objetos que
+//! `audio_native::PwDumpMonitor` sigue en modo `--monitor`—, así que una sola
+//! lectura trae los nodos, sus volúmenes y la metadata `default` que dice cuál
+//! es el dispositivo por defecto. `wpctl` es la herramienta de WirePlumber, el
+//! gestor de sesión que aplica y recuerda esos cambios.
+//!
+//! Con la feature `pipewire-native` y el monitor nativo corriendo, ni eso: las
+//! lecturas salen del grafo que el monitor mantiene al día y las escrituras van
+//! por los proxies que ya tiene enlazados (ver `audio_native::live_graph`).
+//! `pw-dump` y `wpctl` quedan para cuando el monitor no está.
+
+use super::{AudioBackend, DeviceKind};
+#[cfg(feature = "pipewire-native")]
+use crate::audio_native;
+use crate::audio_native::GraphWrite;
+use crate::constants::{CMD_PW_CLI, CMD_PW_DUMP, CMD_WPCTL};
+use crate::error::{Result, VasakError};
+use crate::structs::{AudioCard, AudioDevice, AudioPort, AudioProfile, VolumeInfo};
+use crate::utils::CommandExecutor;
+use serde_json::Value;
+
+pub struct PipeWireBackend;
+
+impl PipeWireBackend {
+    /// `wpctl status` solo funciona con WirePlumber corriendo, que es lo que
+    /// hace falta para que los cambios se apliquen.
+    pub fn is_available() -> bool {
+        CommandExecutor::run_silent(CMD_PW_DUMP, &["--version"])
+            && CommandExecutor::run_silent(CMD_WPCTL, &["status"])
+    }
+
+    /// Alias de `wpctl` para el dispositivo por defecto de `kind`.
+    fn default_target(kind: DeviceKind) -> &'static str {
+        match kind {
+            DeviceKind::Sink => "@DEFAULT_AUDIO_SINK@",
+            DeviceKind::Source => "@DEFAULT_AUDIO_SOURCE@",
+        }
+    }
+}
+
+/// Convierte un volumen de PipeWire (lineal, 1.0 = 100 %) al porcentaje que ve
+/// el usuario.
+///
+/// PipeWire guarda el volumen lineal y las herramientas lo muestran en escala
+/// cúbica, que es la que se corresponde con lo que se oye.
+fn linear_to_percent(linear: f64) -> f64 {
+    linear.max(0.0).cbrt() * 100.0
+}
+
+fn media_class_matches(media_class: &str, kind: DeviceKind) -> bool {
+    match kind {
+        DeviceKind::Sink => media_class == "Audio/Sink",
+        // Incluye "Audio/Source/Virtual" (cancelación de eco, filtros).
+        DeviceKind::Source => media_class.starts_with("Audio/Source"),
+    }
+}
+
+/// Nombre del nodo por defecto según la metadata `default` de WirePlumber.
+fn default_node_name(objects: &[Value], kind: DeviceKind) -> Option<String> {
+    let key = match kind {
+        DeviceKind::Sink => "default.audio.sink",
+        DeviceKind::Source => "default.audio.source",
+    };
+
+    objects
+        .iter()
+        .filter(|obj| {
+            obj.get("type").and_then(Value::as_str) == Some("PipeWire:Interface:Metadata")
+        })
+        .filter(|obj| {
+            obj.pointer("/props/metadata.name").and_then(Value::as_str) == Some("default")
+        })
+        .filter_map(|obj| obj.get("metadata")?.as_array())
+        .flatten()
+        .find(|entry| entry.get("key").and_then(Value::as_str) == Some(key))
+        .and_then(|entry| {
+            // El valor es JSON ({"name": "..."}); según la versión llega como
+            // objeto o como texto con ese JSON adentro.
+            let value = entry.get("value")?;
+            match value {
+                Value::String(raw) => {
+                    let parsed: Value = serde_json::from_str(raw).ok()?;
+                    parsed.get("name")?.as_str().map(str::to_string)
+                }
+                other => other.get("name")?.as_str().map(str::to_string),
+            }
+        })
+}
+
+/// Volumen (el canal más alto) y silencio de un nodo, desde su param `Props`.
+fn node_volume(info: &Value) -> (Option<f64>, bool) {
+    let Some(props) = info.pointer("/params/Props").and_then(Value::as_array) else {
+        return (None, false);
+    };
+
+    let mut volume = None;
+    let mut muted = false;
+
+    for entry in props {
+        if let Some(mute) = entry.get("mute").and_then(Value::as_bool) {
+            muted = mute;
+        }
+        if let Some(channels) = entry.get("channelVolumes").and_then(Value::as_array) {
+            volume = channels.iter().filter_map(Value::as_f64).reduce(f64::max);
+        }
+    }
+
+    (volume.map(linear_to_percent), muted)
+}
+
+/// Volumen y silencio del dispositivo por defecto de `kind`.
+fn default_volume(objects: &[Value], kind: DeviceKind) -> Option<VolumeInfo> {
+    let name = default_node_name(objects, kind)?;
+    let info = objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Node"))
+        .filter_map(|obj| obj.get("info"))
+        .find(|info| info.pointer("/props/node.name").and_then(Value::as_str) == Some(&name))?;
+
+    let (volume_pct, is_muted) = node_volume(info);
+    Some(VolumeInfo {
+        current: volume_pct?.round() as i64,
+        min: 0,
+        max: 100,
+        is_muted,
+    })
+}
+
+/// El grafo tal como lo va contando `pw-dump --monitor`: primero todos los
+/// objetos y después, completos, solo los que cambian. Los que desaparecen
+/// llegan como `{"id": N, "info": null}`.
+#[derive(Debug, Default)]
+pub(crate) struct DumpGraph {
+    objects: Vec<Value>,
+}
+
+impl DumpGraph {
+    /// Incorpora un fragmento de la salida de `pw-dump --monitor`.
+    pub(crate) fn apply(&mut self, chunk_json: &str) -> Result<()> {
+        for obj in parse_dump(chunk_json)? {
+            let Some(id) = obj.get("id").and_then(Value::as_u64) else {
+                continue;
+            };
+            if obj.get("info").is_some_and(Value::is_null) {
+                self.remove(id);
+            } else {
+                self.insert(obj);
+            }
+        }
+        Ok(())
+    }
+
+    /// Volumen del dispositivo por defecto, si ya se conoce.
+    pub(crate) fn default_volume(&self, kind: DeviceKind) -> Option<VolumeInfo> {
+        default_volume(self.objects(), kind)
+    }
+
+    pub(crate) fn objects(&self) -> &[Value] {
+        &self.objects
+    }
+
+    /// El objeto `id`, si está. El monitor nativo arma los suyos de a partes
+    /// (props, después cada param) y los completa acá.
+    pub(crate) fn object_mut(&mut self, id: u64) -> Option<&mut Value> {
+        self.objects
+            .iter_mut()
+            .find(|o| o.get("id").and_then(Value::as_u64) == Some(id))
+    }
+
+    pub(crate) fn insert(&mut self, obj: Value) {
+        let Some(id) = obj.get("id").and_then(Value::as_u64) else {
+            return;
+        };
+        match self.object_mut(id) {
+            Some(known) => *known = obj,
+            None => self.objects.push(obj),
+        }
+    }
+
+    /// Alterna el silencio de `node` en el grafo y devuelve cómo quedó, o
+    /// `None` si todavía no se conoce. El monitor nativo lo hace antes de
+    /// escribir, así un segundo pedido ve el primero aunque PipeWire todavía
+    /// no haya contestado.
+    #[cfg_attr(not(feature = "pipewire-native"), allow(dead_code))]
+    pub(crate) fn flip_mute(&mut self, node: u64) -> Option<bool> {
+        let props = self
+            .object_mut(node)?
+            .pointer_mut("/info/params/Props")?
+            .as_array_mut()?;
+        let muted = !props
+            .iter()
+            .rev()
+            .find_map(|entry| entry.get("mute")?.as_bool())?;
+        for entry in props.iter_mut().filter(|entry| entry.get("mute").is_some()) {
+            entry["mute"] = Value::Bool(muted);
+        }
+        Some(muted)
+    }
+
+    pub(crate) fn remove(&mut self, id: u64) {
+        self.objects
+            .retain(|o| o.get("id").and_then(Value::as_u64) != Some(id));
+    }
+}
+
+fn parse_dump(dump_json: &str) -> Result<Vec<Value>> {
+    let dump: Value = serde_json::from_str(dump_json)
+        .map_err(|e| VasakError::Parse(format!("pw-dump: {}", e)))?;
+
+    match dump {
+        Value::Array(objects) => Ok(objects),
+        _ => Err(VasakError::Parse(
+            "pw-dump: se esperaba un arreglo".to_string(),
+        )),
+    }
+}
+
+fn is_type(obj: &Value, type_name: &str) -> bool {
+    obj.get("type").and_then(Value::as_str) == Some(type_name)
+}
+
+/// Los ids en las props a veces llegan como número y a veces como texto.
+fn prop_u64(props: &Value, key: &str) -> Option<u64> {
+    match props.get(key)? {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn find_device(objects: &[Value], device_id: u64) -> Option<&Value> {
+    objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Device"))
+        .find(|obj| obj.get("id").and_then(Value::as_u64) == Some(device_id))
+}
+
+/// Entradas de un param (`EnumRoute`, `Route`, `EnumProfile`, ...) de un objeto.
+fn params<'a>(obj: &'a Value, name: &str) -> impl Iterator<Item = &'a Value> {
+    obj.pointer(&format!("/info/params/{}", name))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// `available` de rutas y perfiles: "yes", "no" o "unknown".
+fn availability(entry: &Value) -> Option<bool> {
+    match entry.get("available").and_then(Value::as_str) {
+        Some("yes") => Some(true),
+        Some("no") => Some(false),
+        _ => None,
+    }
+}
+
+fn route_direction(kind: DeviceKind) -> &'static str {
+    match kind {
+        DeviceKind::Sink => "Output",
+        DeviceKind::Source => "Input",
+    }
+}
+
+/// Rutas de `device` que sirven al sub-dispositivo `profile_device`
+/// (`card.profile.device` del nodo). Cada ruta es un puerto.
+fn node_routes(
+    device: &Value,
+    kind: DeviceKind,
+    profile_device: u64,
+) -> impl Iterator<Item = &Value> {
+    params(device, "EnumRoute").filter(move |route| {
+        route.get("direction").and_then(Value::as_str) == Some(route_direction(kind))
+            && route
+                .get("devices")
+                .and_then(Value::as_array)
+                .is_some_and(|devices| devices.iter().any(|d| d.as_u64() == Some(profile_device)))
+    })
+}
+
+/// Puertos del nodo y el activo, desde los params del `Device` al que pertenece.
+fn node_ports(
+    objects: &[Value],
+    props: &Value,
+    kind: DeviceKind,
+) -> (Vec<AudioPort>, Option<String>) {
+    let (Some(device_id), Some(profile_device)) = (
+        prop_u64(props, "device.id"),
+        prop_u64(props, "card.profile.device"),
+    ) else {
+        return (Vec::new(), None);
+    };
+    let Some(device) = find_device(objects, device_id) else {
+        return (Vec::new(), None);
+    };
+
+    let ports = node_routes(device, kind, profile_device)
+        .filter_map(|route| {
+            let name = route.get("name")?.as_str()?.to_string();
+            Some(AudioPort {
+                description: route
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or(&name)
+                    .to_string(),
+                name,
+                priority: route.get("priority").and_then(Value::as_u64).unwrap_or(0) as u32,
+                available: availability(route),
+            })
+        })
+        .collect();
+
+    let active_port = params(device, "Route")
+        .find(|route| route.get("device").and_then(Value::as_u64) == Some(profile_device))
+        .and_then(|route| route.get("name")?.as_str())
+        .map(str::to_string);
+
+    (ports, active_port)
+}
+
+fn devices(objects: &[Value], kind: DeviceKind) -> Vec<AudioDevice> {
+    let default_name = default_node_name(objects, kind);
+
+    objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Node"))
+        .filter_map(|obj| {
+            let id = obj.get("id")?.as_u64()?;
+            let info = obj.get("info")?;
+            let props = info.get("props")?;
+
+            let media_class = props.get("media.class")?.as_str()?;
+            if !media_class_matches(media_class, kind) {
+                return None;
+            }
+
+            let node_name = props.get("node.name")?.as_str()?.to_string();
+            let description = props
+                .get("node.description")
+                .or_else(|| props.get("node.nick"))
+                .and_then(Value::as_str)
+                .unwrap_or(&node_name)
+                .to_string();
+            let (volume_pct, is_muted) = node_volume(info);
+            let (ports, active_port) = node_ports(objects, props, kind);
+
+            Some(AudioDevice {
+                id: id.to_string(),
+                name: description,
+                is_default: default_name.as_deref() == Some(node_name.as_str()),
+                description: node_name,
+                volume: volume_pct.map(|pct| pct / 100.0).unwrap_or(0.5),
+                is_muted,
+                ports,
+                active_port,
+                card_id: prop_u64(props, "device.id").map(|id| id.to_string()),
+            })
+        })
+        .collect()
+}
+
+fn profile_from(entry: &Value) -> Option<AudioProfile> {
+    let name = entry.get("name")?.as_str()?.to_string();
+    Some(AudioProfile {
+        description: entry
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or(&name)
+            .to_string(),
+        name,
+        priority: entry.get("priority").and_then(Value::as_u64).unwrap_or(0) as u32,
+        // "unknown" no impide elegirlo
+        available: availability(entry) != Some(false),
+    })
+}
+
+fn cards(objects: &[Value]) -> Vec<AudioCard> {
+    objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Device"))
+        .filter(|obj| {
+            obj.pointer("/info/props/media.class")
+                .and_then(Value::as_str)
+                == Some("Audio/Device")
+        })
+        .filter_map(|obj| {
+            let id = obj.get("id")?.as_u64()?;
+            let props = obj.pointer("/info/props")?;
+            let name = props.get("device.name")?.as_str()?.to_string();
+
+            let mut profiles: Vec<AudioProfile> = params(obj, "EnumProfile")
+                .filter_map(profile_from)
+                .collect();
+            profiles.sort_by(|a, b| {
+                b.priority
+                    .cmp(&a.priority)
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+
+            let active_profile = params(obj, "Profile")
+                .next()
+                .and_then(|profile| profile.get("name")?.as_str())
+                .map(str::to_string);
+
+            Some(AudioCard {
+                id: id.to_string(),
+                description: props
+                    .get("device.description")
+                    .and_then(Value::as_str)
+                    .unwrap_or(&name)
+                    .to_string(),
+                name,
+                profiles,
+                active_profile,
+            })
+        })
+        .collect()
+}
+
+fn find_node(objects: &[Value], node_id: u64) -> Option<&Value> {
+    objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Node"))
+        .find(|obj| obj.get("id").and_then(Value::as_u64) == Some(node_id))
+}
+
+fn node_name(node: &Value) -> Option<&str> {
+    node.pointer("/info/props/node.name")?.as_str()
+}
+
+/// Id del nodo por defecto de `kind`, para escribirle sin pasar por el alias de
+/// `wpctl`.
+fn default_node_id(objects: &[Value], kind: DeviceKind) -> Result<u64> {
+    let name = default_node_name(objects, kind)
+        .ok_or_else(|| VasakError::NotFound(format!("No hay {} por defecto", kind.noun())))?;
+    objects
+        .iter()
+        .filter(|obj| is_type(obj, "PipeWire:Interface:Node"))
+        .find(|obj| node_name(obj) == Some(name.as_str()))
+        .and_then(|obj| obj.get("id")?.as_u64())
+        .ok_or_else(|| VasakError::NotFound(format!("Nodo {} no encontrado", name)))
+}
+
+/// `node.name` del nodo `device_id`, que es lo que guarda la metadata `default`.
+fn default_name_for(objects: &[Value], device_id: &str) -> Result<String> {
+    device_id
+        .parse()
+        .ok()
+        .and_then(|id| find_node(objects, id))
+        .and_then(node_name)
+        .map(str::to_string)
+        .ok_or_else(|| VasakError::NotFound(format!("Nodo {} no encontrado", device_id)))
+}
+
+/// Índice de `EnumProfile` del perfil `profile` en la tarjeta `card_id`.
+fn profile_index(objects: &[Value], card_id: u64, profile: &str) -> Result<u64> {
+    let device = find_device(objects, card_id)
+        .ok_or_else(|| VasakError::NotFound(format!("Tarjeta {} no encontrada", card_id)))?;
+
+    params(device, "EnumProfile")
+        .find(|entry| entry.get("name").and_then(Value::as_str) == Some(profile))
+        .and_then(|entry| entry.get("index")?.as_u64())
+        .ok_or_else(|| VasakError::NotFound(format!("Perfil {} no encontrado", profile)))
+}
+
+/// Lo que pide `pw-cli set-param <device> Route`: el `Device` dueño del nodo,
+/// el índice de la ruta y el sub-dispositivo al que se aplica.
+fn route_target(
+    objects: &[Value],
+    kind: DeviceKind,
+    node_id: &str,
+    port: &str,
+) -> Result<(u64, u64, u64)> {
+    let props = node_id
+        .parse()
+        .ok()
+        .and_then(|id| find_node(objects, id))
+        .and_then(|obj| obj.pointer("/info/props"))
+        .ok_or_else(|| VasakError::NotFound(format!("Nodo {} no encontrado", node_id)))?;
+
+    let (Some(device_id), Some(profile_device)) = (
+        prop_u64(props, "device.id"),
+        prop_u64(props, "card.profile.device"),
+    ) else {
+        return Err(VasakError::InvalidState(format!(
+            "El nodo {} no tiene puertos",
+            node_id
+        )));
+    };
+
+    let route_index = find_device(objects, device_id)
+        .into_iter()
+        .flat_map(|device| node_routes(device, kind, profile_device))
+        .find(|route| route.get("name").and_then(Value::as_str) == Some(port))
+        .and_then(|route| route.get("index")?.as_u64())
+        .ok_or_else(|| VasakError::NotFound(format!("Puerto {} no encontrado", port)))?;
+
+    Ok((device_id, route_index, profile_device))
+}
+
+/// Lee del grafo del monitor nativo o, si no corre, de un `pw-dump`.
+fn read_graph<T>(read: impl FnOnce(&[Value]) -> Result<T>) -> Result<T> {
+    #[cfg(feature = "pipewire-native")]
+    if let Some(live) = audio_native::live_graph() {
+        return live.read(read)?;
+    }
+
+    let output = CommandExecutor::run(CMD_PW_DUMP, &["--no-colors"])?;
+    read(&parse_dump(&output)?)
+}
+
+/// Manda la escritura que arma `write` por los proxies del monitor nativo.
+/// Devuelve `false` si no hay monitor, y entonces toca `wpctl`.
+#[cfg(feature = "pipewire-native")]
+fn write_native(write: impl FnOnce(&[Value]) -> Result<GraphWrite>) -> Result<bool> {
+    let Some(live) = audio_native::live_graph() else {
+        return Ok(false);
+    };
+    let write = live.read(write)??;
+    Ok(live.write(write))
+}
+
+#[cfg(not(feature = "pipewire-native"))]
+fn write_native(_write: impl FnOnce(&[Value]) -> Result<GraphWrite>) -> Result<bool> {
+    Ok(false)
+}
+
+impl AudioBackend for PipeWireBackend {
+    fn name(&self) -> &'static str {
+        "PipeWire (pw-dump + wpctl)"
+    }
+
+    fn list_devices(&self, kind: DeviceKind) -> Result<Vec<AudioDevice>> {
+        read_graph(|objects| Ok(devices(objects, kind)))
+    }
+
+    fn set_volume(&self, kind: DeviceKind, volume: i64) -> Result<()> {
+        let percent = volume.max(0);
+        if write_native(|objects| {
+            Ok(GraphWrite::Volume {
+                node: default_node_id(objects, kind)?,
+                percent,
+            })
+        })? {
+            return Ok(());
+        }
+
+        let volume_str = format!("{}%", percent);
+        CommandExecutor::run(
+            CMD_WPCTL,
+            &["set-volume", Self::default_target(kind), &volume_str],
+        )?;
+        Ok(())
+    }
+
+    fn set_mute(&self, kind: DeviceKind, muted: bool) -> Result<()> {
+        if write_native(|objects| {
+            Ok(GraphWrite::Mute {
+                node: default_node_id(objects, kind)?,
+                muted,
+            })
+        })? {
+            return Ok(());
+        }
+
+        let state = if muted { "1" } else { "0" };
+        CommandExecutor::run(CMD_WPCTL, &["set-mute", Self::default_target(kind), state])?;
+        Ok(())
+    }
+
+    fn toggle_mute(&self, kind: DeviceKind) -> Result<bool> {
+        #[cfg(feature = "pipewire-native")]
+        if let Some(live) = audio_native::live_graph() {
+            // Leer, alternar y encolar con el grafo tomado: dos pedidos
+            // seguidos no pueden ver el mismo estado.
+            let (node, muted) = live.update(|graph| -> Result<(u64, bool)> {
+                let node = default_node_id(graph.objects(), kind)?;
+                let muted = graph.flip_mute(node).ok_or_else(|| {
+                    VasakError::InvalidState(format!("El nodo {} no informó su silencio", node))
+                })?;
+                Ok((node, muted))
+            })??;
+            if live.write(GraphWrite::Mute { node, muted }) {
+                return Ok(muted);
+            }
+        }
+
+        CommandExecutor::run(
+            CMD_WPCTL,
+            &["set-mute", Self::default_target(kind), "toggle"],
+        )?;
+        Ok(self.get_volume(kind)?.is_muted)
+    }
+
+    fn set_default(&self, kind: DeviceKind, device_id: &str) -> Result<()> {
+        if write_native(|objects| {
+            Ok(GraphWrite::Default {
+                kind,
+                name: default_name_for(objects, device_id)?,
+            })
+        })? {
+            return Ok(());
+        }
+
+        // El id de nodo ya dice si es salida o entrada.
+        CommandExecutor::run(CMD_WPCTL, &["set-default", device_id])?;
+        Ok(())
+    }
+
+    fn list_cards(&self) -> Result<Vec<AudioCard>> {
+        read_graph(|objects| Ok(cards(objects)))
+    }
+
+    fn set_port(&self, kind: DeviceKind, device_id: &str, port: &str) -> Result<()> {
+        let (card, route, profile_device) =
+            read_graph(|objects| route_target(objects, kind, device_id, port))?;
+        if write_native(|_| {
+            Ok(GraphWrite::Route {
+                device: card,
+                index: route,
+                profile_device,
+            })
+        })? {
+            return Ok(());
+        }
+
+        // wpctl no tiene un subcomando para rutas; `save` hace que WirePlumber
+        // la recuerde como hace con el perfil.
+        let param = format!(
+            "{{ index: {}, device: {}, save: true }}",
+            route, profile_device
+        );
+        CommandExecutor::run(
+            CMD_PW_CLI,
+            &["set-param", &card.to_string(), "Route", &param],
+        )?;
+        Ok(())
+    }
+
+    fn set_card_profile(&self, card_id: &str, profile: &str) -> Result<()> {
+        let card: u64 = card_id
+            .parse()
+            .map_err(|_| VasakError::Parse(format!("Id de tarjeta inválido: {}", card_id)))?;
+        let index = read_graph(|objects| profile_index(objects, card, profile))?;
+        if write_native(|_| Ok(GraphWrite::Profile { device: card, index }))? {
+            return Ok(());
+        }
+
+        CommandExecutor::run(CMD_WPCTL, &["set-profile", card_id, &index.to_string()])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = include_str!("fixtures/pw-dump.json");
+
+    fn parse_devices(dump_json: &str, kind: DeviceKind) -> Result<Vec<AudioDevice>> {
+        Ok(devices(&parse_dump(dump_json)?, kind))
+    }
+
+    fn parse_cards(dump_json: &str) -> Result<Vec<AudioCard>> {
+        Ok(cards(&parse_dump(dump_json)?))
+    }
+
+    #[test]
+    fn sinks_are_parsed_with_the_default_marked() {
+        let sinks = parse_devices(DUMP, DeviceKind::Sink).unwrap();
+
+        assert_eq!(sinks.len(), 2);
+
+        let builtin = sinks.iter().find(|d| d.id == "55").expect("built-in sink");
+        assert_eq!(builtin.name, "Built-in Audio Analog Stereo");
+        assert_eq!(
+            builtin.description,
+            "alsa_output.pci-0000_00_1f.3.analog-stereo"
+        );
+        assert!(builtin.is_default);
+        assert!(!builtin.is_muted);
+        // 0.421875 lineal es 75 % en la escala cúbica
+        assert!((builtin.volume - 0.75).abs() < 1e-9);
+
+        let bluetooth = sinks.iter().find(|d| d.id == "78").expect("bluetooth sink");
+        assert!(!bluetooth.is_default);
+        assert!(bluetooth.is_muted);
+    }
+
+    #[test]
+    fn sources_include_virtual_ones_and_skip_streams() {
+        let sources = parse_devices(DUMP, DeviceKind::Source).unwrap();
+        let names: Vec<&str> = sources.iter().map(|d| d.description.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "alsa_input.pci-0000_00_1f.3.analog-stereo",
+                "echo-cancel-source"
+            ]
+        );
+        assert!(sources[0].is_default);
+    }
+
+    /// WirePlumber viejo guarda el valor de la metadata como texto JSON.
+    #[test]
+    fn metadata_value_as_string_is_understood() {
+        let dump = r#"[
+            {"id":40,"type":"PipeWire:Interface:Metadata","props":{"metadata.name":"default"},
+             "metadata":[{"subject":0,"key":"default.audio.sink","type":"Spa:String:JSON","value":"{\"name\":\"speakers\"}"}]},
+            {"id":50,"type":"PipeWire:Interface:Node","info":{"props":{"media.class":"Audio/Sink","node.name":"speakers"},"params":{}}}
+        ]"#;
+
+        let sinks = parse_devices(dump, DeviceKind::Sink).unwrap();
+        assert!(sinks[0].is_default);
+        assert!(
+            (sinks[0].volume - 0.5).abs() < f64::EPSILON,
+            "without Props the volume is unknown"
+        );
+    }
+
+    #[test]
+    fn ports_come_from_the_routes_of_the_owning_device() {
+        let sinks = parse_devices(DUMP, DeviceKind::Sink).unwrap();
+        let builtin = sinks.iter().find(|d| d.id == "55").unwrap();
+
+        let ports: Vec<(&str, Option<bool>)> = builtin
+            .ports
+            .iter()
+            .map(|p| (p.name.as_str(), p.available))
+            .collect();
+        // La salida HDMI es de otro sub-dispositivo y no aparece acá.
+        assert_eq!(
+            ports,
+            vec![
+                ("analog-output-speaker", None),
+                ("analog-output-headphones", Some(false))
+            ]
+        );
+        assert_eq!(
+            builtin.active_port.as_deref(),
+            Some("analog-output-speaker")
+        );
+        assert_eq!(builtin.card_id.as_deref(), Some("48"));
+
+        let sources = parse_devices(DUMP, DeviceKind::Source).unwrap();
+        assert_eq!(sources[0].ports.len(), 2);
+        assert_eq!(
+            sources[0].active_port.as_deref(),
+            Some("analog-input-internal-mic")
+        );
+
+        // Sin Device (bluetooth en este volcado) no hay puertos que elegir.
+        let bluetooth = sinks.iter().find(|d| d.id == "78").unwrap();
+        assert!(bluetooth.ports.is_empty());
+        assert_eq!(bluetooth.card_id, None);
+    }
+
+    #[test]
+    fn cards_list_profiles_with_the_active_one() {
+        let cards = parse_cards(DUMP).unwrap();
+        assert_eq!(cards.len(), 1);
+
+        let card = &cards[0];
+        assert_eq!(card.id, "48");
+        assert_eq!(card.description, "Built-in Audio");
+        assert_eq!(
+            card.active_profile.as_deref(),
+            Some("output:analog-stereo+input:analog-stereo")
+        );
+        assert_eq!(
+            card.profiles.first().map(|p| p.name.as_str()),
+            Some("output:analog-stereo+input:analog-stereo")
+        );
+        assert!(
+            !card
+                .profiles
+                .iter()
+                .find(|p| p.name == "output:hdmi-stereo")
+                .unwrap()
+                .available
+        );
+    }
+
+    #[test]
+    fn writes_resolve_names_to_pipewire_indexes() {
+        let objects = parse_dump(DUMP).unwrap();
+
+        assert_eq!(
+            profile_index(&objects, 48, "output:hdmi-stereo").unwrap(),
+            3
+        );
+        assert!(profile_index(&objects, 48, "nope").is_err());
+        assert!(profile_index(&objects, 99, "off").is_err());
+
+        assert_eq!(
+            route_target(&objects, DeviceKind::Sink, "55", "analog-output-headphones").unwrap(),
+            (48, 3, 4)
+        );
+        // Un puerto de entrada no se puede poner en una salida.
+        assert!(
+            route_target(&objects, DeviceKind::Sink, "55", "analog-input-headset-mic").is_err()
+        );
+        assert!(route_target(&objects, DeviceKind::Sink, "78", "anything").is_err());
+    }
+
+    #[test]
+    fn native_writes_resolve_the_default_node() {
+        let objects = parse_dump(DUMP).unwrap();
+
+        assert_eq!(default_node_id(&objects, DeviceKind::Sink).unwrap(), 55);
+        assert_eq!(
+            default_name_for(&objects, "78").unwrap(),
+            "bluez_output.00_1B_66_AA_BB_CC.1"
+        );
+        assert!(default_name_for(&objects, "999").is_err());
+        assert!(default_name_for(&objects, "speakers").is_err());
+    }
+
+    #[test]
+    fn monitor_updates_follow_the_default_sink() {
+        let mut graph = DumpGraph::default();
+        assert_eq!(graph.default_volume(DeviceKind::Sink), None);
+
+        graph.apply(DUMP).unwrap();
+        let info = graph.default_volume(DeviceKind::Sink).unwrap();
+        assert_eq!((info.current, info.is_muted), (75, false));
+
+        // Cambia el volumen de otra salida: el de la por defecto sigue igual.
+        graph
+            .apply(r#"[{"id":78,"type":"PipeWire:Interface:Node","info":{"props":{"media.class":"Audio/Sink","node.name":"bluez_output.00_1B_66_AA_BB_CC.1"},"params":{"Props":[{"mute":false,"channelVolumes":[1.0]}]}}}]"#)
+            .unwrap();
+        assert_eq!(graph.default_volume(DeviceKind::Sink).unwrap().current, 75);
+
+        // Pasa a ser la por defecto.
+        graph
+            .apply(r#"[{"id":40,"type":"PipeWire:Interface:Metadata","props":{"metadata.name":"default"},"metadata":[{"subject":0,"key":"default.audio.sink","value":{"name":"bluez_output.00_1B_66_AA_BB_CC.1"}}]}]"#)
+            .unwrap();
+        assert_eq!(graph.default_volume(DeviceKind::Sink).unwrap().current, 100);
+
+        // Se desconecta: hasta que WirePlumber elija otra no hay volumen.
+        graph.apply(r#"[{"id":78,"info":null}]"#).unwrap();
+        assert_eq!(graph.default_volume(DeviceKind::Sink), None);
+        assert!(default_node_id(graph.objects(), DeviceKind::Sink).is_err());
+    }
+
+    #[test]
+    fn mute_flips_in_the_graph() {
+        let mut graph = DumpGraph::default();
+        graph.apply(DUMP).unwrap();
+
+        assert_eq!(graph.flip_mute(55), Some(true));
+        assert!(graph.default_volume(DeviceKind::Sink).unwrap().is_muted);
+        assert_eq!(graph.flip_mute(55), Some(false));
+        // Sin Props no hay nada que alternar.
+        assert_eq!(graph.flip_mute(48), None);
+        assert_eq!(graph.flip_mute(999), None);
+    }
+
+    /// El monitor nativo completa los objetos de a un param por vez.
+    #[test]
+    fn native_graph_is_filled_in_place() {
+        let mut graph = DumpGraph::default();
+        graph.apply(DUMP).unwrap();
+
+        let sink = graph.object_mut(55).unwrap();
+        sink["info"]["params"]["Props"] = serde_json::json!([{"mute": true, "channelVolumes": [1.0, 1.0]}]);
+        let info = graph.default_volume(DeviceKind::Sink).unwrap();
+        assert_eq!((info.current, info.is_muted), (100, true));
+
+        graph.remove(48);
+        let builtin = devices(graph.objects(), DeviceKind::Sink)
+            .into_iter()
+            .find(|d| d.id == "55")
+            .unwrap();
+        assert!(builtin.ports.is_empty(), "the routes went away with the card");
+        assert!(cards(graph.objects()).is_empty());
+    }
+
+    #[test]
+    fn garbage_is_a_parse_error() {
+        assert!(parse_devices("pw-dump: command not found", DeviceKind::Sink).is_err());
+        assert!(parse_devices("{}", DeviceKind::Sink).is_err());
+    }
+}
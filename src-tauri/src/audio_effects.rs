@@ -0,0 +1,426 @@
+//! Efectos de audio: supresión de ruido y cancelación de eco.
+//!
+//! Activarla crea una entrada virtual que limpia la del micrófono y la deja
+//! como entrada por defecto, así las videollamadas la usan sin configurar nada.
+//! Hay dos formas de crearla:
+//!
+//! 1. Con PipeWire, una config de `libpipewire-module-echo-cancel` (motor
+//!    WebRTC) escrita en `~/.config/pipewire/` y cargada en su propio proceso
+//!    `pipewire -c`. Desactivar es terminar ese proceso: no hace falta
+//!    reiniciar el servidor de audio, ni se corta el sonido.
+//! 2. Sin PipeWire (o sin el binario `pipewire`), `pactl load-module
+//!    module-echo-cancel`, que entienden PulseAudio y pipewire-pulse.
+//!
+//! Lo que el usuario eligió se guarda en las preferencias y se restaura al
+//! iniciar la sesión.
+//!
+//! El proceso `pipewire -c` propio queda guardado para esperarlo: si termina
+//! solo, `try_wait` lo recoge y se puede volver a lanzar. El de una instancia
+//! anterior del shell se reconoce por su línea de comandos, no solo por el
+//! pid, que para entonces puede tenerlo otro proceso.
+
+use crate::audio_backend::{backend, pipewire::PipeWireBackend, DeviceKind};
+use crate::audio_policy;
+use crate::constants::{CMD_PACTL, CMD_PIPEWIRE};
+use crate::error::{Result, VasakError};
+use crate::logger::{log_info, log_warning};
+use crate::notifications::localized;
+use crate::settings;
+use crate::utils::CommandExecutor;
+use serde::Deserialize;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Nodo de la entrada virtual en cada método.
+const PIPEWIRE_SOURCE: &str = "vasak_echo_cancel.source";
+const PACTL_SOURCE: &str = "vasak_echo_cancel_source";
+const PACTL_SINK: &str = "vasak_echo_cancel_sink";
+
+/// Lo que tarda como mucho la entrada virtual en aparecer.
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Method {
+    PipeWire,
+    Pactl,
+}
+
+fn method() -> Method {
+    static METHOD: OnceLock<Method> = OnceLock::new();
+
+    *METHOD.get_or_init(|| {
+        if PipeWireBackend::is_available()
+            && CommandExecutor::run_silent(CMD_PIPEWIRE, &["--version"])
+        {
+            Method::PipeWire
+        } else {
+            Method::Pactl
+        }
+    })
+}
+
+fn virtual_source(method: Method) -> &'static str {
+    match method {
+        Method::PipeWire => PIPEWIRE_SOURCE,
+        Method::Pactl => PACTL_SOURCE,
+    }
+}
+
+/// Nombres que ve el usuario en la lista de dispositivos.
+struct Descriptions {
+    source: String,
+    sink: String,
+}
+
+impl Descriptions {
+    fn localized(app: &AppHandle) -> Self {
+        Self {
+            source: localized(app, "audio.noiseSuppression"),
+            sink: localized(app, "audio.echoCancellation"),
+        }
+    }
+}
+
+/// Config para `pipewire -c`: un cliente mínimo que solo carga el módulo.
+fn filter_config(descriptions: &Descriptions) -> String {
+    format!(
+        r#"# Generado por vasak-desktop: supresión de ruido y cancelación de eco.
+# Se regenera cada vez que se activa; los cambios a mano se pierden.
+context.properties = {{
+    log.level = 0
+}}
+
+context.spa-libs = {{
+    audio.convert.* = audioconvert/libspa-audioconvert
+    support.*       = support/libspa-support
+}}
+
+context.modules = [
+    {{ name = libpipewire-module-rt flags = [ ifexists nofail ] }}
+    {{ name = libpipewire-module-protocol-native }}
+    {{ name = libpipewire-module-client-node }}
+    {{ name = libpipewire-module-adapter }}
+    {{ name = libpipewire-module-echo-cancel
+        args = {{
+            library.name = aec/libspa-aec-webrtc
+            aec.args = {{
+                webrtc.noise_suppression = true
+                webrtc.extended_filter = true
+                webrtc.gain_control = false
+            }}
+            capture.props = {{
+                node.name = "vasak_echo_cancel.capture"
+                node.passive = true
+            }}
+            source.props = {{
+                node.name = "{source}"
+                node.description = "{source_description}"
+            }}
+            sink.props = {{
+                node.name = "vasak_echo_cancel.sink"
+                node.description = "{sink_description}"
+            }}
+            playback.props = {{
+                node.name = "vasak_echo_cancel.playback"
+                node.passive = true
+            }}
+        }}
+    }}
+]
+"#,
+        source = PIPEWIRE_SOURCE,
+        source_description = descriptions.source,
+        sink_description = descriptions.sink,
+    )
+}
+
+fn config_path() -> Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("pipewire").join("vasak-echo-cancel.conf"))
+        .ok_or_else(|| VasakError::NotFound("directorio de configuración".to_string()))
+}
+
+/// El proceso `pipewire -c` sobrevive a un reinicio del shell; el pid queda
+/// acá para poder terminarlo desde la instancia siguiente.
+fn pid_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("vasak-echo-cancel.pid")
+}
+
+/// El filtro que lanzó esta instancia.
+static FILTER: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Si `cmdline` (el de `/proc/<pid>/cmdline`, separado por NUL) es el de un
+/// filtro cargado con `config`.
+fn runs_config(cmdline: &[u8], config: &Path) -> bool {
+    cmdline
+        .split(|byte| *byte == 0)
+        .any(|arg| arg == config.as_os_str().as_bytes())
+}
+
+/// El filtro de una instancia anterior del shell, si sigue corriendo. Un
+/// zombi tiene la línea de comandos vacía, así que tampoco cuenta.
+fn previous_pid(config: &Path) -> Option<i32> {
+    let pid: i32 = std::fs::read_to_string(pid_path()).ok()?.trim().parse().ok()?;
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    (pid > 0 && runs_config(&cmdline, config)).then_some(pid)
+}
+
+fn filter_running(config: &Path) -> bool {
+    if let Ok(mut filter) = FILTER.lock() {
+        if let Some(child) = filter.as_mut() {
+            if matches!(child.try_wait(), Ok(None)) {
+                return true;
+            }
+            // Terminó solo (por ejemplo, se cayó el servidor de audio) y
+            // `try_wait` ya lo recogió.
+            *filter = None;
+        }
+    }
+    previous_pid(config).is_some()
+}
+
+fn start_filter_process(descriptions: &Descriptions) -> Result<()> {
+    let path = config_path()?;
+    if filter_running(&path) {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, filter_config(descriptions))?;
+
+    let child = std::process::Command::new(CMD_PIPEWIRE)
+        .arg("-c")
+        .arg(&path)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| VasakError::Command(format!("pipewire -c: {}", e)))?;
+
+    std::fs::write(pid_path(), child.id().to_string())?;
+    *FILTER
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("audio_effects"))? = Some(child);
+    Ok(())
+}
+
+fn stop_filter_process() -> Result<()> {
+    let own = FILTER
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("audio_effects"))?
+        .take();
+
+    match own {
+        // Hasta que se lo espera el pid sigue siendo suyo: la señal no puede
+        // ir a parar a otro proceso.
+        Some(mut child) => {
+            unsafe {
+                libc::kill(child.id() as i32, libc::SIGTERM);
+            }
+            let _ = child.wait();
+        }
+        None => {
+            if let Some(pid) = previous_pid(&config_path()?) {
+                unsafe {
+                    libc::kill(pid, libc::SIGTERM);
+                }
+            }
+        }
+    }
+    let _ = std::fs::remove_file(pid_path());
+    Ok(())
+}
+
+/// Un elemento de `pactl --format=json list modules`.
+#[derive(Debug, Deserialize)]
+struct PactlModule {
+    index: u32,
+    name: String,
+    #[serde(default)]
+    argument: Option<String>,
+}
+
+/// Índice del `module-echo-cancel` que cargamos nosotros, si está cargado.
+fn find_module_index(modules_json: &str) -> Result<Option<u32>> {
+    let modules: Vec<PactlModule> = serde_json::from_str(modules_json)
+        .map_err(|e| VasakError::Parse(format!("pactl list modules: {}", e)))?;
+
+    let ours = format!("source_name={}", PACTL_SOURCE);
+    Ok(modules
+        .into_iter()
+        .find(|module| {
+            module.name == "module-echo-cancel"
+                && module
+                    .argument
+                    .as_deref()
+                    .is_some_and(|args| args.split_whitespace().any(|arg| arg == ours))
+        })
+        .map(|module| module.index))
+}
+
+fn loaded_module() -> Result<Option<u32>> {
+    let output = CommandExecutor::run(CMD_PACTL, &["--format=json", "list", "modules"])?;
+    find_module_index(&output)
+}
+
+fn load_module(descriptions: &Descriptions) -> Result<()> {
+    if loaded_module()?.is_some() {
+        return Ok(());
+    }
+
+    let source = format!("source_name={}", PACTL_SOURCE);
+    let sink = format!("sink_name={}", PACTL_SINK);
+    let source_properties = format!(
+        "source_properties=device.description=\"{}\"",
+        descriptions.source
+    );
+    let sink_properties = format!(
+        "sink_properties=device.description=\"{}\"",
+        descriptions.sink
+    );
+    CommandExecutor::run(
+        CMD_PACTL,
+        &[
+            "load-module",
+            "module-echo-cancel",
+            &source,
+            &sink,
+            "aec_method=webrtc",
+            "aec_args=\"noise_suppression=1 extended_filter=1\"",
+            &source_properties,
+            &sink_properties,
+        ],
+    )?;
+    Ok(())
+}
+
+fn unload_module() -> Result<()> {
+    if let Some(index) = loaded_module()? {
+        CommandExecutor::run(CMD_PACTL, &["unload-module", &index.to_string()])?;
+    }
+    Ok(())
+}
+
+/// Espera a que aparezca la entrada virtual y la deja por defecto.
+fn make_default(node_name: &str) -> Result<()> {
+    let started = Instant::now();
+
+    loop {
+        let sources = backend().list_devices(DeviceKind::Source)?;
+        if let Some(source) = sources.iter().find(|s| s.description == node_name) {
+            backend().set_default(DeviceKind::Source, &source.id)?;
+            // Primera en la lista: si se desactiva, la política vuelve sola a
+            // la que seguía.
+            audio_policy::remember_preferred(DeviceKind::Source, node_name)?;
+            return Ok(());
+        }
+
+        if started.elapsed() > SOURCE_TIMEOUT {
+            return Err(VasakError::NotFound(format!(
+                "La entrada {} no apareció",
+                node_name
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// Si la entrada con supresión de ruido existe ahora mismo.
+pub fn is_noise_suppression_enabled() -> Result<bool> {
+    let name = virtual_source(method());
+    Ok(backend()
+        .list_devices(DeviceKind::Source)?
+        .iter()
+        .any(|source| source.description == name))
+}
+
+/// Activa o desactiva la supresión de ruido y lo recuerda.
+pub fn set_noise_suppression(app: &AppHandle, enabled: bool) -> Result<()> {
+    let method = method();
+    log_info(&format!(
+        "{} supresión de ruido ({:?})",
+        if enabled { "Activando" } else { "Desactivando" },
+        method
+    ));
+
+    match (method, enabled) {
+        (Method::PipeWire, true) => start_filter_process(&Descriptions::localized(app))?,
+        (Method::PipeWire, false) => stop_filter_process()?,
+        (Method::Pactl, true) => load_module(&Descriptions::localized(app))?,
+        (Method::Pactl, false) => unload_module()?,
+    }
+
+    if enabled {
+        make_default(virtual_source(method))?;
+    }
+
+    settings::update(|s| s.audio.noise_suppression = enabled)?;
+    Ok(())
+}
+
+/// Vuelve a crear la entrada virtual si quedó activada en la sesión anterior.
+pub fn restore(app: &AppHandle) {
+    if !settings::get().audio.noise_suppression {
+        return;
+    }
+
+    if let Err(e) = set_noise_suppression(app, true) {
+        log_warning(&format!("No se pudo restaurar la supresión de ruido: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn our_echo_cancel_module_is_found_among_others() {
+        let modules = r#"[
+            {"index":7,"name":"module-always-sink","argument":"","properties":{}},
+            {"index":24,"name":"module-echo-cancel","argument":"source_name=someone_else","properties":{}},
+            {"index":31,"name":"module-echo-cancel","argument":"source_name=vasak_echo_cancel_source sink_name=vasak_echo_cancel_sink aec_method=webrtc","properties":{}}
+        ]"#;
+
+        assert_eq!(find_module_index(modules).unwrap(), Some(31));
+    }
+
+    #[test]
+    fn nothing_loaded_is_none() {
+        let modules = r#"[{"index":7,"name":"module-always-sink","argument":null}]"#;
+        assert_eq!(find_module_index(modules).unwrap(), None);
+    }
+
+    #[test]
+    fn filter_config_names_the_virtual_source() {
+        let config = filter_config(&Descriptions {
+            source: "Noise suppression".to_string(),
+            sink: "Echo cancellation".to_string(),
+        });
+        assert!(config.contains(r#"node.name = "vasak_echo_cancel.source""#));
+        assert!(config.contains(r#"node.description = "Noise suppression""#));
+        assert!(config.contains("libpipewire-module-echo-cancel"));
+        assert!(!config.contains("{{"), "format! braces must be unescaped");
+    }
+
+    /// Después de un reinicio del shell el pid guardado puede ser de otro.
+    #[test]
+    fn only_our_filter_matches_the_pid_file() {
+        let config = Path::new("/home/ana/.config/pipewire/vasak-echo-cancel.conf");
+
+        assert!(runs_config(
+            b"pipewire\0-c\0/home/ana/.config/pipewire/vasak-echo-cancel.conf\0",
+            config
+        ));
+        assert!(!runs_config(b"firefox\0--new-window\0", config));
+        assert!(!runs_config(b"", config), "zombies have no command line");
+    }
+}
@@ -6,19 +6,35 @@
 //!    to volume/mute changes on the default audio sink via PipeWire's native event loop.
 //!    Because libpipewire requires its own main loop thread, the PipeWire event loop runs
 //!    on a dedicated `std::thread` and bridges updates to tokio via a `watch` channel.
+//!    It mirrors the whole audio graph, which the PipeWire backend then reads and
+//!    writes through [`live_graph`] instead of spawning `pw-dump` and `wpctl`.
 //!
 //! 2. `PwDumpMonitor` (default) - Uses `pw-dump --monitor` as a streaming process that
 //!    emits JSON events on stdout when PipeWire objects change. This is significantly
 //!    better than pactl polling because it's event-driven without spawning new processes.
 //!
-//! 3. `PactlFallback` - Wraps the existing `audio::get_volume()` function in a simple
-//!    tokio interval loop at 2000ms, used when PipeWire tools are also unavailable.
+//! 3. `PactlFallback` - Asks the audio backend for the volume in a simple tokio
+//!    interval loop at 2000ms, used when PipeWire tools are also unavailable.
+//!
+//! Whichever monitor the audio applet adopts is published with
+//! [`AudioMonitor::publish_state`], and [`cached_volume`] reads it. Commands
+//! answer from there instead of spawning `pactl` on every request.
 
-use crate::audio;
+use crate::audio_backend::pipewire::DumpGraph;
+use crate::audio_backend::{backend, DeviceKind};
+#[cfg(feature = "pipewire-native")]
+use crate::error::VasakError;
 use crate::logger::{log_debug, log_error, log_info};
 use crate::structs::VolumeInfo;
-use tokio::sync::{oneshot, watch};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::{oneshot, watch, Notify};
 
+#[cfg(feature = "pipewire-native")]
+use std::cell::RefCell;
+#[cfg(feature = "pipewire-native")]
+use std::collections::HashMap;
+#[cfg(feature = "pipewire-native")]
+use std::rc::Rc;
 #[cfg(feature = "pipewire-native")]
 use std::sync::Arc;
 
@@ -36,14 +52,123 @@ pub enum PipeWireError {
     PwDumpNotAvailable(String),
 }
 
+/// Receiver of the monitor the audio applet is running.
+static ACTIVE_STATE: Mutex<Option<watch::Receiver<VolumeInfo>>> = Mutex::new(None);
+
+/// Latest volume of the default sink as seen by the running monitor, or
+/// `None` before the audio applet has started one.
+pub fn cached_volume() -> Option<VolumeInfo> {
+    let active = ACTIVE_STATE.lock().ok()?;
+    active.as_ref().map(|rx| rx.borrow().clone())
+}
+
+/// Wakes the polling fallback so a write shows up in the cache right away.
+/// Event-driven monitors see writes on their own, so for them this is a no-op.
+pub fn refresh() {
+    poll_now().notify_one();
+}
+
+fn poll_now() -> &'static Notify {
+    static POLL_NOW: OnceLock<Notify> = OnceLock::new();
+    POLL_NOW.get_or_init(Notify::new)
+}
+
+/// One synchronous read to seed a monitor's channel, so the cache never
+/// serves a made-up 0% while the monitor waits for its first event.
+fn initial_volume() -> VolumeInfo {
+    backend()
+        .get_volume(DeviceKind::Sink)
+        .unwrap_or_else(|_| unknown_volume())
+}
+
+fn unknown_volume() -> VolumeInfo {
+    VolumeInfo {
+        current: 0,
+        min: 0,
+        max: 100,
+        is_muted: false,
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // PipeWireMonitor - native pipewire-rs (requires feature "pipewire-native")
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Monitors audio volume/mute state via PipeWire's native event API.
+/// A write for the native loop, already resolved against the graph by the
+/// audio backend. The loop owns the proxies, so everyone else has to ask it.
+///
+/// Without the feature the backend still builds these (and then falls back to
+/// `wpctl`), so nothing reads the fields.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "pipewire-native"), allow(dead_code))]
+pub(crate) enum GraphWrite {
+    /// Volume in percent, on the same cubic scale as wpctl
+    Volume { node: u64, percent: i64 },
+    Mute { node: u64, muted: bool },
+    /// Makes the node called `name` the configured default
+    Default { kind: DeviceKind, name: String },
+    Route { device: u64, index: u64, profile_device: u64 },
+    Profile { device: u64, index: u64 },
+}
+
+/// The graph the native monitor keeps up to date, in `pw-dump` form, and the
+/// way into its loop. The audio backend reads and writes through this instead
+/// of spawning `pw-dump` and `wpctl`.
+#[cfg(feature = "pipewire-native")]
+#[derive(Clone)]
+pub(crate) struct LiveGraph {
+    graph: Arc<Mutex<DumpGraph>>,
+    writes: pipewire::channel::Sender<GraphWrite>,
+}
+
+#[cfg(feature = "pipewire-native")]
+impl LiveGraph {
+    pub(crate) fn read<T>(
+        &self,
+        read: impl FnOnce(&[serde_json::Value]) -> T,
+    ) -> crate::error::Result<T> {
+        let graph = self
+            .graph
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("audio graph"))?;
+        Ok(read(graph.objects()))
+    }
+
+    /// Like [`read`](Self::read), for changes the backend makes ahead of
+    /// PipeWire so the next request already sees them.
+    pub(crate) fn update<T>(
+        &self,
+        update: impl FnOnce(&mut DumpGraph) -> T,
+    ) -> crate::error::Result<T> {
+        let mut graph = self
+            .graph
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("audio graph"))?;
+        Ok(update(&mut graph))
+    }
+
+    /// Queues `write` on the PipeWire thread. `false` once that thread is gone.
+    pub(crate) fn write(&self, write: GraphWrite) -> bool {
+        self.writes.send(write).is_ok()
+    }
+}
+
+/// Graph of the native monitor the audio applet adopted.
+#[cfg(feature = "pipewire-native")]
+static LIVE_GRAPH: Mutex<Option<LiveGraph>> = Mutex::new(None);
+
+/// The live graph, when the adopted monitor is the native one.
+#[cfg(feature = "pipewire-native")]
+pub(crate) fn live_graph() -> Option<LiveGraph> {
+    LIVE_GRAPH.lock().ok()?.clone()
+}
+
+/// Monitors the audio graph via PipeWire's native event API.
 ///
 /// Spawns a dedicated OS thread running the PipeWire main loop and bridges
-/// state changes to the async world via a `tokio::sync::watch` channel.
+/// state changes to the async world via a `tokio::sync::watch` channel. The
+/// thread binds every sink, source and card, so besides the default sink's
+/// volume it keeps the whole graph for [`live_graph`].
 ///
 /// Only available when compiled with the `pipewire-native` feature and
 /// libpipewire-0.3 development headers are installed.
@@ -51,34 +176,311 @@ pub enum PipeWireError {
 pub struct PipeWireMonitor {
     /// Receives the latest VolumeInfo whenever the default sink changes.
     pub state_rx: watch::Receiver<VolumeInfo>,
+    live: LiveGraph,
     /// Handle to signal the PipeWire thread to stop.
     _shutdown: Arc<std::sync::atomic::AtomicBool>,
 }
 
+/// Proxies bound by the native loop, by global id. They live on the PipeWire
+/// thread; dropping one also drops its listener.
+#[cfg(feature = "pipewire-native")]
+#[derive(Default)]
+struct Bound {
+    nodes: HashMap<u32, (pipewire::node::Node, pipewire::node::NodeListener)>,
+    devices: HashMap<u32, (pipewire::device::Device, pipewire::device::DeviceListener)>,
+    metadata: Option<(
+        pipewire::metadata::Metadata,
+        pipewire::metadata::MetadataListener,
+    )>,
+}
+
+/// Global properties as the string map `pw-dump` prints.
+#[cfg(feature = "pipewire-native")]
+fn dict_json(dict: &pipewire::spa::utils::dict::DictRef) -> serde_json::Value {
+    dict.iter()
+        .map(|(key, value)| (key.to_string(), serde_json::Value::from(value)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// `pw-dump` key for a property of a Props, Route or Profile param.
+#[cfg(feature = "pipewire-native")]
+fn param_key(object_type: u32, key: u32) -> Option<&'static str> {
+    use pipewire::spa::sys;
+
+    Some(match (object_type, key) {
+        (sys::SPA_TYPE_OBJECT_Props, sys::SPA_PROP_mute) => "mute",
+        (sys::SPA_TYPE_OBJECT_Props, sys::SPA_PROP_channelVolumes) => "channelVolumes",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_index) => "index",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_direction) => "direction",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_device) => "device",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_name) => "name",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_description) => "description",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_priority) => "priority",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_available) => "available",
+        (sys::SPA_TYPE_OBJECT_ParamRoute, sys::SPA_PARAM_ROUTE_devices) => "devices",
+        (sys::SPA_TYPE_OBJECT_ParamProfile, sys::SPA_PARAM_PROFILE_index) => "index",
+        (sys::SPA_TYPE_OBJECT_ParamProfile, sys::SPA_PARAM_PROFILE_name) => "name",
+        (sys::SPA_TYPE_OBJECT_ParamProfile, sys::SPA_PARAM_PROFILE_description) => "description",
+        (sys::SPA_TYPE_OBJECT_ParamProfile, sys::SPA_PARAM_PROFILE_priority) => "priority",
+        (sys::SPA_TYPE_OBJECT_ParamProfile, sys::SPA_PARAM_PROFILE_available) => "available",
+        _ => return None,
+    })
+}
+
+/// A param pod as the entry `pw-dump` would print for it, keeping only the
+/// keys the audio backend reads.
+#[cfg(feature = "pipewire-native")]
+fn param_json(object: &pipewire::spa::pod::Object) -> serde_json::Value {
+    use pipewire::spa::pod::{Value, ValueArray};
+    use pipewire::spa::sys;
+    use pipewire::spa::utils::Id;
+    use serde_json::json;
+
+    let mut entry = serde_json::Map::new();
+    for prop in &object.properties {
+        let Some(key) = param_key(object.type_, prop.key) else {
+            continue;
+        };
+        let value = match (&prop.value, key) {
+            (Value::Id(Id(direction)), "direction") => {
+                json!(if *direction == sys::SPA_DIRECTION_INPUT { "Input" } else { "Output" })
+            }
+            (Value::Id(Id(available)), "available") => json!(match *available {
+                sys::SPA_PARAM_AVAILABILITY_yes => "yes",
+                sys::SPA_PARAM_AVAILABILITY_no => "no",
+                _ => "unknown",
+            }),
+            (Value::Bool(b), _) => json!(b),
+            (Value::Int(n), _) => json!(n),
+            (Value::Long(n), _) => json!(n),
+            (Value::Float(f), _) => json!(f),
+            (Value::String(s), _) => json!(s),
+            (Value::ValueArray(ValueArray::Float(values)), _) => json!(values),
+            (Value::ValueArray(ValueArray::Int(values)), _) => json!(values),
+            _ => continue,
+        };
+        entry.insert(key.to_string(), value);
+    }
+    entry.into()
+}
+
+/// Stores one entry of a param under `info.params`. A fresh enumeration
+/// starts at index 0, so that is when the old entries go.
+#[cfg(feature = "pipewire-native")]
+fn store_param(
+    graph: &Mutex<DumpGraph>,
+    id: u32,
+    param: pipewire::spa::param::ParamType,
+    index: u32,
+    pod: Option<&pipewire::spa::pod::Pod>,
+) {
+    use pipewire::spa::param::ParamType;
+    use pipewire::spa::pod::{deserialize::PodDeserializer, Value};
+
+    let name = match param {
+        ParamType::Props => "Props",
+        ParamType::EnumRoute => "EnumRoute",
+        ParamType::Route => "Route",
+        ParamType::EnumProfile => "EnumProfile",
+        ParamType::Profile => "Profile",
+        _ => return,
+    };
+    let Some(pod) = pod else {
+        return;
+    };
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(pod.as_bytes())
+    else {
+        return;
+    };
+
+    let Ok(mut graph) = graph.lock() else {
+        return;
+    };
+    let Some(obj) = graph.object_mut(id.into()) else {
+        return;
+    };
+    let entries = &mut obj["info"]["params"][name];
+    if index == 0 || !entries.is_array() {
+        *entries = serde_json::Value::Array(Vec::new());
+    }
+    if let Some(entries) = entries.as_array_mut() {
+        entries.push(param_json(&object));
+    }
+}
+
+/// Sends the default sink's volume if it changed.
+#[cfg(feature = "pipewire-native")]
+fn publish(graph: &Mutex<DumpGraph>, state_tx: &watch::Sender<VolumeInfo>) {
+    let Some(info) = graph
+        .lock()
+        .ok()
+        .and_then(|graph| graph.default_volume(DeviceKind::Sink))
+    else {
+        return;
+    };
+    state_tx.send_if_modified(|current| {
+        let changed = *current != info;
+        *current = info;
+        changed
+    });
+}
+
+/// Serializes a param object to hand it to `set_param`.
+#[cfg(feature = "pipewire-native")]
+fn param_pod(
+    type_: u32,
+    id: u32,
+    properties: Vec<(u32, pipewire::spa::pod::Value)>,
+) -> Option<Vec<u8>> {
+    use pipewire::spa::pod::{serialize::PodSerializer, Object, Property, PropertyFlags, Value};
+
+    let object = Value::Object(Object {
+        type_,
+        id,
+        properties: properties
+            .into_iter()
+            .map(|(key, value)| Property {
+                key,
+                flags: PropertyFlags::empty(),
+                value,
+            })
+            .collect(),
+    });
+    PodSerializer::serialize(std::io::Cursor::new(Vec::new()), &object)
+        .ok()
+        .map(|(cursor, _)| cursor.into_inner())
+}
+
+/// How many channels `node` has, so a volume write sets all of them.
+#[cfg(feature = "pipewire-native")]
+fn channel_count(graph: &Mutex<DumpGraph>, node: u64) -> usize {
+    graph
+        .lock()
+        .ok()
+        .and_then(|graph| {
+            let obj = graph.objects().iter().find(|obj| {
+                obj.get("id").and_then(serde_json::Value::as_u64) == Some(node)
+            })?;
+            let channels = obj.pointer("/info/params/Props/0/channelVolumes")?;
+            Some(channels.as_array()?.len())
+        })
+        .filter(|count| *count > 0)
+        .unwrap_or(2)
+}
+
+/// Carries out a [`GraphWrite`] on the PipeWire thread.
+#[cfg(feature = "pipewire-native")]
+fn apply_write(bound: &Bound, graph: &Mutex<DumpGraph>, write: GraphWrite) {
+    use pipewire::spa::param::ParamType;
+    use pipewire::spa::pod::{Pod, Value, ValueArray};
+    use pipewire::spa::sys;
+
+    let missing = |what: &str, id: u64| {
+        log_error(&format!("PipeWireMonitor: no bound {} with id={}", what, id));
+    };
+    let props = |properties| param_pod(sys::SPA_TYPE_OBJECT_Props, sys::SPA_PARAM_Props, properties);
+
+    match write {
+        GraphWrite::Volume { node, percent } => {
+            let Some((proxy, _)) = bound.nodes.get(&(node as u32)) else {
+                return missing("node", node);
+            };
+            // wpctl's cubic scale, back to PipeWire's linear volume
+            let linear = (percent as f32 / 100.0).powi(3);
+            let channels = vec![linear; channel_count(graph, node)];
+            let bytes = props(vec![(
+                sys::SPA_PROP_channelVolumes,
+                Value::ValueArray(ValueArray::Float(channels)),
+            )]);
+            if let Some(pod) = bytes.as_deref().and_then(Pod::from_bytes) {
+                proxy.set_param(ParamType::Props, 0, pod);
+            }
+        }
+        GraphWrite::Mute { node, muted } => {
+            let Some((proxy, _)) = bound.nodes.get(&(node as u32)) else {
+                return missing("node", node);
+            };
+            let bytes = props(vec![(sys::SPA_PROP_mute, Value::Bool(muted))]);
+            if let Some(pod) = bytes.as_deref().and_then(Pod::from_bytes) {
+                proxy.set_param(ParamType::Props, 0, pod);
+            }
+        }
+        GraphWrite::Default { kind, name } => {
+            let Some((metadata, _)) = &bound.metadata else {
+                return log_error("PipeWireMonitor: no default metadata to write to");
+            };
+            // What `wpctl set-default` writes; WirePlumber then moves
+            // `default.audio.*` and remembers the choice.
+            let key = match kind {
+                DeviceKind::Sink => "default.configured.audio.sink",
+                DeviceKind::Source => "default.configured.audio.source",
+            };
+            let value = serde_json::json!({ "name": name }).to_string();
+            metadata.set_property(0, key, Some("Spa:String:JSON"), Some(&value));
+        }
+        GraphWrite::Route {
+            device,
+            index,
+            profile_device,
+        } => {
+            let Some((proxy, _)) = bound.devices.get(&(device as u32)) else {
+                return missing("device", device);
+            };
+            let bytes = param_pod(
+                sys::SPA_TYPE_OBJECT_ParamRoute,
+                sys::SPA_PARAM_Route,
+                vec![
+                    (sys::SPA_PARAM_ROUTE_index, Value::Int(index as i32)),
+                    (sys::SPA_PARAM_ROUTE_device, Value::Int(profile_device as i32)),
+                    // Remembered by WirePlumber, like `pw-cli ... save: true`
+                    (sys::SPA_PARAM_ROUTE_save, Value::Bool(true)),
+                ],
+            );
+            if let Some(pod) = bytes.as_deref().and_then(Pod::from_bytes) {
+                proxy.set_param(ParamType::Route, 0, pod);
+            }
+        }
+        GraphWrite::Profile { device, index } => {
+            let Some((proxy, _)) = bound.devices.get(&(device as u32)) else {
+                return missing("device", device);
+            };
+            let bytes = param_pod(
+                sys::SPA_TYPE_OBJECT_ParamProfile,
+                sys::SPA_PARAM_Profile,
+                vec![
+                    (sys::SPA_PARAM_PROFILE_index, Value::Int(index as i32)),
+                    (sys::SPA_PARAM_PROFILE_save, Value::Bool(true)),
+                ],
+            );
+            if let Some(pod) = bytes.as_deref().and_then(Pod::from_bytes) {
+                proxy.set_param(ParamType::Profile, 0, pod);
+            }
+        }
+    }
+}
+
 #[cfg(feature = "pipewire-native")]
 impl PipeWireMonitor {
-    /// Attempts to connect to PipeWire and start monitoring the default sink.
+    /// Attempts to connect to PipeWire and start monitoring the graph.
     ///
     /// On success, returns a `PipeWireMonitor` whose `state_rx` will receive
     /// `VolumeInfo` updates whenever the default sink volume or mute state changes.
-    pub fn connect() -> Result<Self, PipeWireError> {
-        let initial_volume = VolumeInfo {
-            current: 0,
-            min: 0,
-            max: 100,
-            is_muted: false,
-        };
+    pub fn connect(initial_volume: VolumeInfo) -> Result<Self, PipeWireError> {
 
         let (state_tx, state_rx) = watch::channel(initial_volume);
+        let (writes_tx, writes_rx) = pipewire::channel::channel();
+        let graph = Arc::new(Mutex::new(DumpGraph::default()));
         let shutdown = Arc::new(std::sync::atomic::AtomicBool::new(false));
         let shutdown_clone = shutdown.clone();
+        let graph_clone = graph.clone();
 
         // Spawn a dedicated OS thread for PipeWire's main loop.
         // PipeWire's C library cannot run inside tokio's async runtime.
         let builder = std::thread::Builder::new().name("pipewire-monitor".into());
         let handle = builder
             .spawn(move || {
-                Self::run_loop(state_tx, shutdown_clone);
+                Self::run_loop(state_tx, graph_clone, writes_rx, shutdown_clone);
             })
             .map_err(|e| PipeWireError::InitFailed(format!("Failed to spawn thread: {}", e)))?;
 
@@ -92,23 +494,33 @@ impl PipeWireMonitor {
             ));
         }
 
-        log_info("PipeWireMonitor: connected successfully, monitoring default sink");
+        log_info("PipeWireMonitor: connected successfully, monitoring the audio graph");
 
         Ok(Self {
             state_rx,
+            live: LiveGraph {
+                graph,
+                writes: writes_tx,
+            },
             _shutdown: shutdown,
         })
     }
 
     /// Runs the PipeWire main loop on a dedicated thread.
     ///
-    /// This function subscribes to the PipeWire registry, finds the default
-    /// audio sink, and listens for volume/mute param changes.
+    /// This function subscribes to the PipeWire registry, binds the sinks,
+    /// sources, cards and the "default" metadata, mirrors them into `graph`
+    /// and carries out the writes that arrive on `writes`.
     fn run_loop(
         state_tx: watch::Sender<VolumeInfo>,
+        graph: Arc<Mutex<DumpGraph>>,
+        writes: pipewire::channel::Receiver<GraphWrite>,
         shutdown: Arc<std::sync::atomic::AtomicBool>,
     ) {
         use pipewire::prelude::*;
+        use pipewire::spa::param::ParamType;
+        use pipewire::types::ObjectType;
+        use serde_json::json;
 
         // Initialize PipeWire (must be called on the thread that runs the loop)
         pipewire::init();
@@ -148,10 +560,16 @@ impl PipeWireMonitor {
 
         let registry = core.get_registry().expect("PipeWire: failed to get registry");
 
-        let state_tx_clone = state_tx.clone();
+        // Proxies and their listeners stay alive here until their global goes.
+        let bound = Rc::new(RefCell::new(Bound::default()));
+        let bound_on_remove = bound.clone();
+        let graph_on_remove = graph.clone();
 
-        // Store bound node proxies and param listeners so they stay alive.
-        let bound_nodes = std::sync::Mutex::new(Vec::new());
+        let _writes = writes.attach(mainloop.loop_(), {
+            let bound = bound.clone();
+            let graph = graph.clone();
+            move |write| apply_write(&bound.borrow(), &graph, write)
+        });
 
         // Raw pointer for registry access inside the 'static closure.
         // SAFETY: registry lives on run_loop's stack and is dropped after the
@@ -159,89 +577,174 @@ impl PipeWireMonitor {
         // which is called inside run_loop().
         let registry_ptr: *const pipewire::registry::Registry = &registry;
 
-        // Listen for registry global events to find and bind audio sinks
+        let state_on_remove = state_tx.clone();
+
         let _registry_listener = registry
             .add_listener_local()
             .global(move |global| {
-                if let Some(props) = global.props {
-                    let media_class = props.get("media.class").unwrap_or("");
+                let Some(props) = global.props else {
+                    return;
+                };
 
-                    if media_class == "Audio/Sink" {
-                        log_debug(&format!(
-                            "PipeWireMonitor: found and binding sink node id={}",
-                            global.id
-                        ));
+                // SAFETY: registry lives on run_loop's stack, outlives this closure.
+                let registry = unsafe { &*registry_ptr };
+                let id = global.id;
+
+                if global.type_ == ObjectType::Metadata {
+                    if props.get("metadata.name") != Some("default") {
+                        return;
+                    }
+
+                    let Ok(metadata) = registry.bind::<pipewire::metadata::Metadata>(global) else {
+                        log_error("PipeWireMonitor: failed to bind default metadata");
+                        return;
+                    };
+                    if let Ok(mut graph) = graph.lock() {
+                        graph.insert(json!({
+                            "id": id,
+                            "type": "PipeWire:Interface:Metadata",
+                            "props": {"metadata.name": "default"},
+                            "metadata": [],
+                        }));
+                    }
 
-                        // SAFETY: registry lives on run_loop's stack, outlives this closure.
-                        let registry = unsafe { &*registry_ptr };
-
-                        if let Ok(node) = registry.bind::<pipewire::node::Node>(global) {
-                            use pipewire::spa::{param::ParamType, pod::Value, pod::deserialize::PodDeserializer, sys};
-
-                            let tx = state_tx_clone.clone();
-
-                            // Subscribe to Props parameter updates on this node
-                            node.subscribe_params(&[ParamType::Props]);
-
-                            // Listen for Props param events containing volume/mute
-                            let listener = node
-                                .add_listener_local()
-                                .param(move |_seq, id, _index, _next, param| {
-                                    if id == ParamType::Props {
-                                        if let Some(pod) = param {
-                                            if let Ok((_, Value::Object(obj))) =
-                                                PodDeserializer::deserialize_any_from(
-                                                    pod.as_bytes(),
-                                                )
-                                            {
-                                                let mut volume: Option<f64> = None;
-                                                let mut muted: Option<bool> = None;
-
-                                                for prop in &obj.properties {
-                                                    match prop.key {
-                                                        sys::SPA_PROP_volume => {
-                                                            if let Value::Float(vol) =
-                                                                prop.value
-                                                            {
-                                                                volume = Some(vol as f64);
-                                                            }
-                                                        }
-                                                        sys::SPA_PROP_mute => {
-                                                            if let Value::Bool(m) = prop.value {
-                                                                muted = Some(m);
-                                                            }
-                                                        }
-                                                        _ => {}
-                                                    }
-                                                }
-
-                                                if let Some(vol) = volume {
-                                                    let pct = (vol * 100.0).round() as i64;
-                                                    let info = VolumeInfo {
-                                                        current: pct.clamp(0, 100),
-                                                        min: 0,
-                                                        max: 100,
-                                                        is_muted: muted.unwrap_or(false),
-                                                    };
-                                                    let _ = tx.send(info);
-                                                }
+                    let tx = state_tx.clone();
+                    let graph = graph.clone();
+                    let listener = metadata
+                        .add_listener_local()
+                        .property(move |subject, key, type_, value| {
+                            if let Ok(mut graph) = graph.lock() {
+                                if let Some(entries) = graph
+                                    .object_mut(id.into())
+                                    .and_then(|obj| obj["metadata"].as_array_mut())
+                                {
+                                    // No key means the whole metadata was cleared.
+                                    entries.retain(|entry| {
+                                        key.is_some() && entry["key"].as_str() != key
+                                    });
+                                    if let (Some(key), Some(value)) = (key, value) {
+                                        let value = match type_ {
+                                            Some("Spa:String:JSON") => {
+                                                serde_json::from_str(value).unwrap_or_default()
                                             }
-                                        }
+                                            _ => json!(value),
+                                        };
+                                        entries.push(json!({
+                                            "subject": subject,
+                                            "key": key,
+                                            "value": value,
+                                        }));
                                     }
-                                })
-                                .register();
-
-                            // Keep node and listener alive for the duration of the loop
-                            bound_nodes.lock().unwrap().push((node, listener));
-                        } else {
-                            log_error(&format!(
-                                "PipeWireMonitor: failed to bind sink node id={}",
-                                global.id
-                            ));
-                        }
+                                }
+                            }
+                            publish(&graph, &tx);
+                            0
+                        })
+                        .register();
+
+                    bound.borrow_mut().metadata = Some((metadata, listener));
+                    return;
+                }
+
+                let media_class = props.get("media.class").unwrap_or_default();
+
+                if global.type_ == ObjectType::Node
+                    && (media_class == "Audio/Sink" || media_class.starts_with("Audio/Source"))
+                {
+                    log_debug(&format!("PipeWireMonitor: binding {} node id={}", media_class, id));
+
+                    let Ok(node) = registry.bind::<pipewire::node::Node>(global) else {
+                        log_error(&format!("PipeWireMonitor: failed to bind node id={}", id));
+                        return;
+                    };
+                    if let Ok(mut graph) = graph.lock() {
+                        graph.insert(json!({
+                            "id": id,
+                            "type": "PipeWire:Interface:Node",
+                            "info": {"props": dict_json(props), "params": {}},
+                        }));
                     }
+
+                    node.subscribe_params(&[ParamType::Props]);
+
+                    let graph_on_info = graph.clone();
+                    let graph = graph.clone();
+                    let tx = state_tx.clone();
+                    let listener = node
+                        .add_listener_local()
+                        // The global only carries a few props; the info has
+                        // all of them, `card.profile.device` included.
+                        .info(move |info| {
+                            let Some(props) = info.props() else {
+                                return;
+                            };
+                            if let Ok(mut graph) = graph_on_info.lock() {
+                                if let Some(obj) = graph.object_mut(id.into()) {
+                                    obj["info"]["props"] = dict_json(props);
+                                }
+                            }
+                        })
+                        .param(move |_seq, param, index, _next, pod| {
+                            store_param(&graph, id, param, index, pod);
+                            publish(&graph, &tx);
+                        })
+                        .register();
+
+                    bound.borrow_mut().nodes.insert(id, (node, listener));
+                    return;
+                }
+
+                if global.type_ == ObjectType::Device && media_class == "Audio/Device" {
+                    let Ok(device) = registry.bind::<pipewire::device::Device>(global) else {
+                        log_error(&format!("PipeWireMonitor: failed to bind device id={}", id));
+                        return;
+                    };
+                    if let Ok(mut graph) = graph.lock() {
+                        graph.insert(json!({
+                            "id": id,
+                            "type": "PipeWire:Interface:Device",
+                            "info": {"props": dict_json(props), "params": {}},
+                        }));
+                    }
+
+                    device.subscribe_params(&[
+                        ParamType::EnumRoute,
+                        ParamType::Route,
+                        ParamType::EnumProfile,
+                        ParamType::Profile,
+                    ]);
+
+                    let graph_on_info = graph.clone();
+                    let graph = graph.clone();
+                    let listener = device
+                        .add_listener_local()
+                        .info(move |info| {
+                            let Some(props) = info.props() else {
+                                return;
+                            };
+                            if let Ok(mut graph) = graph_on_info.lock() {
+                                if let Some(obj) = graph.object_mut(id.into()) {
+                                    obj["info"]["props"] = dict_json(props);
+                                }
+                            }
+                        })
+                        .param(move |_seq, param, index, _next, pod| {
+                            store_param(&graph, id, param, index, pod);
+                        })
+                        .register();
+
+                    bound.borrow_mut().devices.insert(id, (device, listener));
                 }
             })
+            .global_remove(move |id| {
+                let mut bound = bound_on_remove.borrow_mut();
+                bound.nodes.remove(&id);
+                bound.devices.remove(&id);
+                if let Ok(mut graph) = graph_on_remove.lock() {
+                    graph.remove(id.into());
+                }
+                publish(&graph_on_remove, &state_on_remove);
+            })
             .register();
 
         // Run the PipeWire main loop (blocks until quit)
@@ -285,7 +788,7 @@ impl PwDumpMonitor {
     ///
     /// Returns an error if pw-dump is not found or fails to start.
     /// Only returns `Ok` after the first pw-dump process has spawned successfully.
-    pub async fn connect() -> Result<Self, PipeWireError> {
+    pub async fn connect(initial_volume: VolumeInfo) -> Result<Self, PipeWireError> {
         // First, check if pw-dump is available
         let check = tokio::process::Command::new("which")
             .arg("pw-dump")
@@ -299,13 +802,6 @@ impl PwDumpMonitor {
             ));
         }
 
-        let initial_volume = VolumeInfo {
-            current: 0,
-            min: 0,
-            max: 100,
-            is_muted: false,
-        };
-
         let (state_tx, state_rx) = watch::channel(initial_volume);
         let (ready_tx, ready_rx) = oneshot::channel();
 
@@ -385,6 +881,8 @@ impl PwDumpMonitor {
             let mut lines = reader.lines();
             let mut json_buffer = String::new();
             let mut bracket_depth: i32 = 0;
+            // A fresh pw-dump starts over with a full dump of the graph.
+            let mut graph = DumpGraph::default();
 
             // Read JSON objects from pw-dump output
             while let Ok(Some(line)) = lines.next_line().await {
@@ -403,8 +901,17 @@ impl PwDumpMonitor {
 
                 // When brackets are balanced, we have a complete JSON chunk
                 if bracket_depth == 0 && !json_buffer.trim().is_empty() {
-                    if let Some(volume_info) = Self::parse_volume_from_json(&json_buffer) {
-                        let _ = state_tx.send(volume_info);
+                    match graph.apply(&json_buffer) {
+                        Ok(()) => {
+                            if let Some(volume_info) = graph.default_volume(DeviceKind::Sink) {
+                                state_tx.send_if_modified(|current| {
+                                    let changed = *current != volume_info;
+                                    *current = volume_info;
+                                    changed
+                                });
+                            }
+                        }
+                        Err(e) => log_debug(&format!("PwDumpMonitor: skipping chunk: {}", e)),
                     }
                     json_buffer.clear();
                 }
@@ -416,95 +923,6 @@ impl PwDumpMonitor {
             tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         }
     }
-
-    /// Parses pw-dump JSON output to extract volume/mute for the default sink.
-    ///
-    /// pw-dump outputs arrays of PipeWire objects. We look for objects with
-    /// type "PipeWire:Interface:Node" and media.class "Audio/Sink" that have
-    /// volume parameters.
-    fn parse_volume_from_json(json_str: &str) -> Option<VolumeInfo> {
-        // Parse as a JSON value
-        let value: serde_json::Value = serde_json::from_str(json_str).ok()?;
-
-        // pw-dump outputs an array of objects
-        let objects = value.as_array()?;
-
-        for obj in objects {
-            // Check if this is a Node with Audio/Sink media class
-            let obj_type = match obj.get("type").and_then(|v| v.as_str()) {
-                Some(t) => t,
-                None => continue,
-            };
-            if obj_type != "PipeWire:Interface:Node" {
-                continue;
-            }
-
-            let info = match obj.get("info") {
-                Some(i) => i,
-                None => continue,
-            };
-            let props = match info.get("props") {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let media_class = match props.get("media.class").and_then(|v| v.as_str()) {
-                Some(mc) => mc,
-                None => continue,
-            };
-            if media_class != "Audio/Sink" {
-                continue;
-            }
-
-            // Look for volume parameters in the params section
-            let params = match info.get("params") {
-                Some(p) => p,
-                None => continue,
-            };
-
-            // Check Props params for volume info
-            if let Some(props_array) = params.get("Props").and_then(|v| v.as_array()) {
-                for prop_entry in props_array {
-                    // Look for channelVolumes and mute in the props
-                    let mute = prop_entry.get("mute").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                    if let Some(volumes) =
-                        prop_entry.get("channelVolumes").and_then(|v| v.as_array())
-                    {
-                        // PipeWire volumes are cubic: actual_volume = pw_vol^3
-                        // Convert to percentage: pct = cbrt(pw_vol) * 100
-                        // Actually PipeWire uses linear 0.0..1.0 that maps to the
-                        // cubic scale internally. The raw value is the linear volume.
-                        if let Some(first_vol) = volumes.first().and_then(|v| v.as_f64()) {
-                            // Convert PipeWire linear volume (0.0-1.0+) to percentage
-                            // PipeWire uses cubic scale: percentage = cbrt(linear) * 100
-                            let pct = (first_vol.cbrt() * 100.0).round() as i64;
-
-                            return Some(VolumeInfo {
-                                current: pct.clamp(0, 150), // Can go above 100%
-                                min: 0,
-                                max: 100,
-                                is_muted: mute,
-                            });
-                        }
-                    }
-
-                    // Alternative: look for volume as a single float
-                    if let Some(vol) = prop_entry.get("volume").and_then(|v| v.as_f64()) {
-                        let pct = (vol.cbrt() * 100.0).round() as i64;
-                        return Some(VolumeInfo {
-                            current: pct.clamp(0, 150),
-                            min: 0,
-                            max: 100,
-                            is_muted: mute,
-                        });
-                    }
-                }
-            }
-        }
-
-        None
-    }
 }
 
 impl Drop for PwDumpMonitor {
@@ -520,8 +938,8 @@ impl Drop for PwDumpMonitor {
 /// Fallback audio monitor that uses `pactl` commands at a fixed 2000ms interval.
 ///
 /// This is used when PipeWire native monitoring and pw-dump are both unavailable.
-/// It wraps the existing `audio::get_volume()` function and sends updates through
-/// a watch channel.
+/// It asks the audio backend for the default sink's volume and sends updates
+/// through a watch channel. [`refresh`] makes it poll right away.
 pub struct PactlFallback {
     /// Receives the latest VolumeInfo from polling.
     pub state_rx: watch::Receiver<VolumeInfo>,
@@ -537,14 +955,7 @@ impl PactlFallback {
     ///
     /// Returns a `PactlFallback` whose `state_rx` will receive `VolumeInfo`
     /// updates whenever the polled volume differs from the previous reading.
-    pub fn start() -> Self {
-        let initial_volume = VolumeInfo {
-            current: 0,
-            min: 0,
-            max: 100,
-            is_muted: false,
-        };
-
+    pub fn start(initial_volume: VolumeInfo) -> Self {
         let (state_tx, state_rx) = watch::channel(initial_volume);
 
         let task = tokio::spawn(async move {
@@ -566,9 +977,18 @@ impl PactlFallback {
         let mut last_volume: Option<VolumeInfo> = None;
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = poll_now().notified() => {}
+            }
 
-            match audio::get_volume() {
+            let polled =
+                tokio::task::spawn_blocking(|| backend().get_volume(DeviceKind::Sink)).await;
+            let Ok(polled) = polled else {
+                continue;
+            };
+
+            match polled {
                 Ok(current) => {
                     let has_changed = match &last_volume {
                         None => true,
@@ -627,10 +1047,14 @@ impl AudioMonitor {
     ///
     /// Attempts PipeWire native (if feature enabled), then pw-dump, then pactl.
     pub async fn new() -> Self {
+        let initial = tokio::task::spawn_blocking(initial_volume)
+            .await
+            .unwrap_or_else(|_| unknown_volume());
+
         // Try native PipeWire first (if compiled with feature)
         #[cfg(feature = "pipewire-native")]
         {
-            match PipeWireMonitor::connect() {
+            match PipeWireMonitor::connect(initial.clone()) {
                 Ok(pw) => {
                     log_info("AudioMonitor: using PipeWire native monitoring");
                     return AudioMonitor::PipeWire(pw);
@@ -645,7 +1069,7 @@ impl AudioMonitor {
         }
 
         // Try pw-dump process monitor
-        match PwDumpMonitor::connect().await {
+        match PwDumpMonitor::connect(initial.clone()).await {
             Ok(pwd) => {
                 log_info("AudioMonitor: using pw-dump event-driven monitoring");
                 return AudioMonitor::PwDump(pwd);
@@ -660,7 +1084,25 @@ impl AudioMonitor {
 
         // Fall back to pactl polling
         log_info("AudioMonitor: using pactl polling fallback (2000ms)");
-        AudioMonitor::Pactl(PactlFallback::start())
+        AudioMonitor::Pactl(PactlFallback::start(initial))
+    }
+
+    /// Makes this monitor the one [`cached_volume`] reads from.
+    ///
+    /// Called by whoever adopts the monitor, not by `new`: the applet also
+    /// builds throwaway monitors to probe for an event-driven backend.
+    pub fn publish_state(&self) {
+        if let Ok(mut active) = ACTIVE_STATE.lock() {
+            *active = Some(self.state_rx());
+        }
+
+        #[cfg(feature = "pipewire-native")]
+        if let Ok(mut live) = LIVE_GRAPH.lock() {
+            *live = match self {
+                AudioMonitor::PipeWire(pw) => Some(pw.live.clone()),
+                _ => None,
+            };
+        }
     }
 
     /// Returns a clone of the watch receiver for volume state.
@@ -0,0 +1,220 @@
+//! Política de dispositivo por defecto.
+//!
+//! El usuario ordena sus dispositivos de audio por preferencia (implícitamente,
+//! eligiéndolos, o desde la configuración). Cuando uno aparece o desaparece,
+//! `AudioPolicyApplet` vuelve a elegir el primero de esa lista que esté
+//! conectado. Los dispositivos se recuerdan por nombre de nodo, no por id: el
+//! id cambia cada vez que se reconectan.
+
+use crate::audio_backend::DeviceKind;
+use crate::error::Result;
+use crate::settings::{self, AudioSettings};
+use crate::structs::AudioDevice;
+
+/// Cuántos dispositivos se recuerdan de cada lado. Cada auricular Bluetooth,
+/// perfil HDMI o adaptador USB que se conectó alguna vez entra en la lista;
+/// pasado este largo se olvidan los menos preferidos que no están conectados.
+const MAX_REMEMBERED: usize = 20;
+
+fn priority_of(audio: &AudioSettings, kind: DeviceKind) -> &Vec<String> {
+    match kind {
+        DeviceKind::Sink => &audio.sink_priority,
+        DeviceKind::Source => &audio.source_priority,
+    }
+}
+
+fn priority_of_mut(audio: &mut AudioSettings, kind: DeviceKind) -> &mut Vec<String> {
+    match kind {
+        DeviceKind::Sink => &mut audio.sink_priority,
+        DeviceKind::Source => &mut audio.source_priority,
+    }
+}
+
+/// Lista de preferencia guardada para `kind`.
+pub fn priority_list(kind: DeviceKind) -> Vec<String> {
+    priority_of(&settings::get().audio, kind).clone()
+}
+
+/// Pone `name` primero en la lista.
+fn promote(priority: &mut Vec<String>, name: &str) {
+    priority.retain(|known| known != name);
+    priority.insert(0, name.to_string());
+}
+
+/// Agrega al final los nombres que la lista todavía no conoce.
+fn append_unknown<'a>(priority: &mut Vec<String>, names: impl IntoIterator<Item = &'a str>) {
+    for name in names {
+        if !priority.iter().any(|known| known == name) {
+            priority.push(name.to_string());
+        }
+    }
+}
+
+/// Recorta la lista a [`MAX_REMEMBERED`] olvidando desde el final los que no
+/// están en `connected`.
+fn prune(priority: &mut Vec<String>, connected: &[&str]) {
+    let mut excess = priority.len().saturating_sub(MAX_REMEMBERED);
+    let mut index = priority.len();
+    while excess > 0 && index > 0 {
+        index -= 1;
+        if !connected.contains(&priority[index].as_str()) {
+            priority.remove(index);
+            excess -= 1;
+        }
+    }
+}
+
+fn connected_names(devices: &[AudioDevice]) -> Vec<&str> {
+    devices.iter().map(|device| device.description.as_str()).collect()
+}
+
+/// El usuario eligió `name` a mano: pasa a ser el preferido.
+pub fn remember_preferred(kind: DeviceKind, name: &str) -> Result<()> {
+    settings::update(|s| promote(priority_of_mut(&mut s.audio, kind), name))?;
+    Ok(())
+}
+
+/// Un dispositivo que se acaba de conectar y nunca se vio va primero:
+/// enchufarlo es la señal más clara de que se lo quiere usar. `connected` son
+/// todos los conectados, que no se olvidan al recortar la lista.
+pub fn remember_new(kind: DeviceKind, names: &[&str], connected: &[AudioDevice]) -> Result<()> {
+    let connected = connected_names(connected);
+    settings::update(|s| {
+        let priority = priority_of_mut(&mut s.audio, kind);
+        for name in names.iter().rev() {
+            if !priority.iter().any(|known| known == name) {
+                priority.insert(0, name.to_string());
+            }
+        }
+        prune(priority, &connected);
+    })?;
+    Ok(())
+}
+
+/// Los que ya estaban al arrancar van al final, detrás de lo que el usuario
+/// haya elegido antes. El que está por defecto, antes que el resto.
+pub fn remember_present(kind: DeviceKind, devices: &[AudioDevice]) -> Result<()> {
+    let mut ordered: Vec<&AudioDevice> = devices.iter().collect();
+    ordered.sort_by_key(|device| !device.is_default);
+
+    let connected = connected_names(devices);
+    settings::update(|s| {
+        let priority = priority_of_mut(&mut s.audio, kind);
+        append_unknown(priority, ordered.iter().map(|device| device.description.as_str()));
+        prune(priority, &connected);
+    })?;
+    Ok(())
+}
+
+/// El dispositivo conectado que va primero en `priority`.
+pub fn choose_default<'a>(
+    priority: &[String],
+    devices: &'a [AudioDevice],
+) -> Option<&'a AudioDevice> {
+    priority
+        .iter()
+        .find_map(|name| devices.iter().find(|device| &device.description == name))
+}
+
+/// Qué lado cambió según una línea de `pactl subscribe`, si es que se conectó
+/// o desconectó algo. Cambios de volumen y streams de aplicaciones
+/// (`sink-input`, `source-output`) no cuentan.
+pub fn hotplug_kind(line: &str) -> Option<DeviceKind> {
+    if !line.contains("'new'") && !line.contains("'remove'") {
+        return None;
+    }
+
+    if line.contains(" on sink #") {
+        Some(DeviceKind::Sink)
+    } else if line.contains(" on source #") {
+        Some(DeviceKind::Source)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, name: &str, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: name.to_string(),
+            is_default,
+            volume: 0.5,
+            is_muted: false,
+            ports: Vec::new(),
+            active_port: None,
+            card_id: None,
+        }
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn the_first_connected_device_in_the_list_wins() {
+        let priority = names(&["usb-headset", "bluez-speaker", "builtin"]);
+        let devices = vec![device("55", "builtin", true), device("78", "bluez-speaker", false)];
+
+        assert_eq!(choose_default(&priority, &devices).map(|d| d.id.as_str()), Some("78"));
+    }
+
+    #[test]
+    fn nothing_is_chosen_when_no_known_device_is_connected() {
+        let priority = names(&["usb-headset"]);
+        let devices = vec![device("55", "builtin", true)];
+
+        assert!(choose_default(&priority, &devices).is_none());
+    }
+
+    #[test]
+    fn promoting_moves_to_the_front_without_duplicates() {
+        let mut priority = names(&["a", "b", "c"]);
+        promote(&mut priority, "c");
+        assert_eq!(priority, names(&["c", "a", "b"]));
+
+        promote(&mut priority, "new");
+        assert_eq!(priority, names(&["new", "c", "a", "b"]));
+    }
+
+    #[test]
+    fn unknown_devices_are_appended_in_order() {
+        let mut priority = names(&["b"]);
+        append_unknown(&mut priority, ["a", "b", "c"]);
+        assert_eq!(priority, names(&["b", "a", "c"]));
+    }
+
+    #[test]
+    fn long_lists_forget_the_least_preferred_disconnected_devices() {
+        let mut priority: Vec<String> =
+            (0..MAX_REMEMBERED + 3).map(|n| format!("headset-{}", n)).collect();
+        let last = format!("headset-{}", MAX_REMEMBERED + 2);
+
+        prune(&mut priority, &[last.as_str(), "headset-0"]);
+
+        assert_eq!(priority.len(), MAX_REMEMBERED);
+        assert_eq!(priority.first().map(String::as_str), Some("headset-0"));
+        assert_eq!(priority.last(), Some(&last), "connected devices are kept");
+        assert!(!priority.contains(&format!("headset-{}", MAX_REMEMBERED + 1)));
+        assert!(!priority.contains(&format!("headset-{}", MAX_REMEMBERED - 1)));
+
+        let mut short = names(&["a", "b"]);
+        prune(&mut short, &[]);
+        assert_eq!(short, names(&["a", "b"]));
+    }
+
+    #[test]
+    fn only_device_hotplug_lines_count() {
+        assert_eq!(hotplug_kind("Event 'new' on sink #91"), Some(DeviceKind::Sink));
+        assert_eq!(hotplug_kind("Event 'remove' on source #92"), Some(DeviceKind::Source));
+
+        assert_eq!(hotplug_kind("Event 'change' on sink #55"), None);
+        assert_eq!(hotplug_kind("Event 'new' on sink-input #120"), None);
+        assert_eq!(hotplug_kind("Event 'remove' on source-output #121"), None);
+        assert_eq!(hotplug_kind("Event 'new' on card #48"), None);
+    }
+}
@@ -0,0 +1,504 @@
+//! Brillo automático según el sensor de luz ambiente.
+//!
+//! El sensor se lee a través de iio-sensor-proxy (`net.hadess.SensorProxy` en
+//! el bus del sistema): hay que reclamarlo con `ClaimLight` y a partir de ahí
+//! publica `LightLevel` como propiedad.
+//!
+//! Cada lectura se pasa por la curva que eligió el usuario (puntos lux → %,
+//! interpolados en escala logarítmica, que es como el ojo percibe la luz). El
+//! brillo se acerca al valor de la curva de a poco, y solo se empieza a mover
+//! cuando la diferencia supera un umbral, para que una sombra o el ruido del
+//! sensor no hagan parpadear la pantalla.
+//!
+//! Si el usuario cambia el brillo a mano, el modo automático se pausa y respeta
+//! ese valor hasta que la luz ambiente cambie de verdad (por ejemplo, al pasar
+//! de un cuarto a la calle).
+
+use crate::logger::{log_info, log_warning};
+use crate::settings::{self, CurvePoint};
+use futures_util::StreamExt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use zbus::proxy;
+
+/// Cada cuánto se acerca el brillo al objetivo.
+pub(crate) const TICK: Duration = Duration::from_millis(250);
+
+/// Fracción de la distancia al objetivo que se recorre en cada paso.
+const SMOOTHING: f64 = 0.3;
+
+/// Puntos de diferencia con el objetivo a partir de los cuales vale la pena
+/// mover el brillo.
+const HYSTERESIS: f64 = 3.0;
+
+/// Cambio de luz (en log10 de lux) que saca al modo automático de la pausa:
+/// unas cuatro veces más o menos luz que cuando el usuario tocó el brillo.
+const RESUME_DISTANCE: f64 = 0.6;
+
+#[proxy(
+    interface = "net.hadess.SensorProxy",
+    default_service = "net.hadess.SensorProxy",
+    default_path = "/net/hadess/SensorProxy"
+)]
+trait Sensors {
+    /// ClaimLight method
+    fn claim_light(&self) -> zbus::Result<()>;
+
+    /// ReleaseLight method
+    fn release_light(&self) -> zbus::Result<()>;
+
+    /// HasAmbientLight property
+    #[zbus(property)]
+    fn has_ambient_light(&self) -> zbus::Result<bool>;
+
+    /// LightLevel property
+    #[zbus(property)]
+    fn light_level(&self) -> zbus::Result<f64>;
+
+    /// LightLevelUnit property: "lux" o "vendor"
+    #[zbus(property)]
+    fn light_level_unit(&self) -> zbus::Result<String>;
+}
+
+/// log10(lux + 1): 0 en la oscuridad, ~4 a pleno sol.
+fn light_scale(lux: f64) -> f64 {
+    (lux.max(0.0) + 1.0).log10()
+}
+
+/// Brillo que pide la curva para `lux`, interpolando entre los dos puntos
+/// vecinos. Fuera de la curva se usa el extremo más cercano.
+pub(crate) fn curve_percent(curve: &[CurvePoint], lux: f64) -> Option<f64> {
+    let mut points: Vec<(f64, f64)> = curve
+        .iter()
+        .map(|p| (light_scale(p.lux), p.percent.min(100) as f64))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let level = light_scale(lux);
+    let first = points.first()?;
+    let last = points.last()?;
+    if level <= first.0 {
+        return Some(first.1);
+    }
+    if level >= last.0 {
+        return Some(last.1);
+    }
+
+    points.windows(2).find_map(|pair| {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        (level >= x0 && level <= x1).then(|| {
+            if x1 > x0 {
+                y0 + (y1 - y0) * (level - x0) / (x1 - x0)
+            } else {
+                y1
+            }
+        })
+    })
+}
+
+/// Decide qué brillo aplicar a partir de las lecturas del sensor. No toca el
+/// hardware: `tick` devuelve el porcentaje cuando hay que cambiarlo.
+#[derive(Debug)]
+pub(crate) struct Controller {
+    curve: Vec<CurvePoint>,
+    min_percent: u32,
+    /// Última lectura, en lux
+    lux: Option<f64>,
+    /// Brillo que se está mostrando, con decimales para que el acercamiento
+    /// sea suave
+    current: Option<f64>,
+    /// Si se está en medio de un acercamiento al objetivo
+    ramping: bool,
+    paused: bool,
+    /// Luz que había cuando se pausó
+    paused_light: Option<f64>,
+}
+
+impl Controller {
+    pub(crate) fn new(curve: Vec<CurvePoint>, min_percent: u32) -> Self {
+        Self {
+            curve,
+            min_percent: min_percent.min(100),
+            lux: None,
+            current: None,
+            ramping: false,
+            paused: false,
+            paused_light: None,
+        }
+    }
+
+    pub(crate) fn from_settings() -> Self {
+        let brightness = settings::get().brightness;
+        Self::new(brightness.auto_curve, brightness.min_percent)
+    }
+
+    /// Punto de partida: el brillo que tiene la pantalla ahora.
+    pub(crate) fn start_from(&mut self, percent: u32) {
+        self.current = Some(percent as f64);
+        self.ramping = false;
+    }
+
+    pub(crate) fn on_reading(&mut self, lux: f64) {
+        self.lux = Some(lux);
+
+        if self.paused {
+            let level = light_scale(lux);
+            match self.paused_light {
+                None => self.paused_light = Some(level),
+                Some(at) if (level - at).abs() >= RESUME_DISTANCE => {
+                    log_info("Brillo automático: cambió la luz ambiente, se reanuda");
+                    self.paused = false;
+                    self.paused_light = None;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// El usuario puso el brillo en `percent` a mano.
+    pub(crate) fn on_manual_change(&mut self, percent: u32) {
+        self.paused = true;
+        self.paused_light = self.lux.map(light_scale);
+        self.start_from(percent);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub(crate) fn lux(&self) -> Option<f64> {
+        self.lux
+    }
+
+    fn target(&self) -> Option<f64> {
+        let percent = curve_percent(&self.curve, self.lux?)?;
+        Some(percent.clamp(self.min_percent as f64, 100.0))
+    }
+
+    /// Un paso hacia el objetivo. Devuelve el brillo a aplicar si cambió.
+    pub(crate) fn tick(&mut self) -> Option<u32> {
+        if self.paused {
+            return None;
+        }
+        let target = self.target()?;
+
+        let Some(current) = self.current else {
+            // Sin saber qué brillo hay, se va directo al de la curva.
+            self.current = Some(target);
+            return Some(target.round() as u32);
+        };
+
+        let distance = target - current;
+        if !self.ramping {
+            if distance.abs() < HYSTERESIS {
+                return None;
+            }
+            self.ramping = true;
+        }
+
+        let next = if distance.abs() <= 0.5 {
+            self.ramping = false;
+            target
+        } else {
+            current + distance * SMOOTHING
+        };
+        self.current = Some(next);
+
+        let (before, after) = (current.round() as u32, next.round() as u32);
+        (before != after).then_some(after)
+    }
+}
+
+/// El controlador que usa el applet.
+pub(crate) fn controller() -> &'static Mutex<Controller> {
+    static CONTROLLER: OnceLock<Mutex<Controller>> = OnceLock::new();
+    CONTROLLER.get_or_init(|| Mutex::new(Controller::from_settings()))
+}
+
+/// Se avisa cuando cambian las preferencias, para que el applet arranque,
+/// se detenga o tome la curva nueva.
+pub(crate) fn restart_signal() -> &'static Notify {
+    static RESTART: OnceLock<Notify> = OnceLock::new();
+    RESTART.get_or_init(Notify::new)
+}
+
+/// Las preferencias cambiaron: el applet vuelve a arrancar con la curva
+/// nueva, o se detiene si se desactivó.
+pub fn reload() {
+    restart_signal().notify_one();
+}
+
+static SENSOR_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_sensor_available(available: bool) {
+    SENSOR_AVAILABLE.store(available, Ordering::Relaxed);
+}
+
+pub fn is_sensor_available() -> bool {
+    SENSOR_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Para llamar cada vez que el usuario cambia el brillo de la pantalla.
+pub fn note_manual_change(percent: u32) {
+    if !settings::get().brightness.auto {
+        return;
+    }
+    if let Ok(mut controller) = controller().lock() {
+        if !controller.is_paused() {
+            log_info("Brillo automático: pausado por un ajuste manual");
+        }
+        controller.on_manual_change(percent);
+    }
+}
+
+/// Estado para la interfaz: (pausado, última lectura en lux).
+pub fn status() -> (bool, Option<f64>) {
+    controller()
+        .lock()
+        .map(|c| (c.is_paused(), c.lux()))
+        .unwrap_or((false, None))
+}
+
+/// Vuelve a leer la curva de las preferencias y parte de `percent`.
+pub(crate) fn reset(percent: Option<u32>) {
+    if let Ok(mut controller) = controller().lock() {
+        *controller = Controller::from_settings();
+        if let Some(percent) = percent {
+            controller.start_from(percent);
+        }
+    }
+}
+
+/// Sigue al sensor hasta que termine `stop`: reclama la luz, pasa cada
+/// lectura al controlador y llama a `apply` con el brillo de cada paso.
+/// Si el equipo no tiene sensor, espera a que aparezca uno.
+pub(crate) async fn follow_sensor<F>(
+    connection: &zbus::Connection,
+    controller: &Mutex<Controller>,
+    stop: impl Future<Output = ()>,
+    mut apply: F,
+) -> zbus::Result<()>
+where
+    F: FnMut(u32),
+{
+    let sensors = SensorsProxy::new(connection).await?;
+    tokio::pin!(stop);
+
+    if !sensors.has_ambient_light().await.unwrap_or(false) {
+        set_sensor_available(false);
+        log_info("Brillo automático: no hay sensor de luz ambiente, esperando");
+        let mut appeared = sensors.receive_has_ambient_light_changed().await;
+        loop {
+            tokio::select! {
+                _ = &mut stop => return Ok(()),
+                change = appeared.next() => match change {
+                    Some(change) if change.get().await.unwrap_or(false) => break,
+                    Some(_) => {}
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+    set_sensor_available(true);
+
+    sensors.claim_light().await?;
+    if let Ok(unit) = sensors.light_level_unit().await {
+        if unit != "lux" {
+            log_warning(&format!(
+                "Brillo automático: el sensor usa unidades '{}', la curva puede no ajustar bien",
+                unit
+            ));
+        }
+    }
+
+    let mut levels = sensors.receive_light_level_changed().await;
+    if let Ok(lux) = sensors.light_level().await {
+        if let Ok(mut controller) = controller.lock() {
+            controller.on_reading(lux);
+        }
+    }
+
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let result = loop {
+        tokio::select! {
+            _ = &mut stop => break Ok(()),
+            change = levels.next() => match change {
+                Some(change) => {
+                    if let Ok(lux) = change.get().await {
+                        if let Ok(mut controller) = controller.lock() {
+                            controller.on_reading(lux);
+                        }
+                    }
+                }
+                None => break Ok(()),
+            },
+            _ = ticker.tick() => {
+                let step = controller.lock().ok().and_then(|mut c| c.tick());
+                if let Some(percent) = step {
+                    apply(percent);
+                }
+            }
+        }
+    };
+
+    let _ = sensors.release_light().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> Vec<CurvePoint> {
+        vec![
+            CurvePoint { lux: 0.0, percent: 10 },
+            CurvePoint { lux: 100.0, percent: 50 },
+            CurvePoint { lux: 10000.0, percent: 100 },
+        ]
+    }
+
+    #[test]
+    fn curve_interpolates_on_a_log_scale() {
+        let curve = curve();
+        assert_eq!(curve_percent(&curve, 0.0), Some(10.0));
+        assert_eq!(curve_percent(&curve, 100.0), Some(50.0));
+        assert_eq!(curve_percent(&curve, 50000.0), Some(100.0));
+        // ~10 lux está a mitad de camino entre 0 y 100 en escala logarítmica.
+        let ten = curve_percent(&curve, 10.0).unwrap();
+        assert!((28.0..32.0).contains(&ten), "{}", ten);
+        assert_eq!(curve_percent(&[], 10.0), None);
+    }
+
+    #[test]
+    fn small_changes_stay_inside_the_hysteresis() {
+        let mut controller = Controller::new(curve(), 5);
+        controller.start_from(50);
+        controller.on_reading(105.0);
+        assert_eq!(controller.tick(), None);
+    }
+
+    #[test]
+    fn brightness_ramps_gradually_and_settles() {
+        let mut controller = Controller::new(curve(), 5);
+        controller.start_from(10);
+        controller.on_reading(10000.0);
+
+        let steps: Vec<u32> = (0..40).filter_map(|_| controller.tick()).collect();
+        assert!(steps.len() > 3, "should take several steps: {:?}", steps);
+        assert!(steps.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(steps.last(), Some(&100));
+    }
+
+    #[test]
+    fn manual_change_pauses_until_the_light_changes() {
+        let mut controller = Controller::new(curve(), 5);
+        controller.on_reading(100.0);
+        controller.on_manual_change(80);
+
+        controller.on_reading(120.0);
+        assert_eq!(controller.tick(), None);
+
+        controller.on_reading(2.0);
+        assert!(!controller.is_paused());
+        assert!(controller.tick().is_some_and(|p| p < 80));
+    }
+
+    #[test]
+    fn minimum_percent_is_respected() {
+        let mut controller = Controller::new(curve(), 30);
+        controller.on_reading(0.0);
+        assert_eq!(controller.tick(), Some(30));
+    }
+
+    mod fake_sensor {
+        use super::*;
+        use std::os::unix::net::UnixStream;
+        use std::sync::Arc;
+        use zbus::{connection, interface, object_server::SignalContext, Guid};
+
+        struct FakeSensorProxy {
+            level: f64,
+        }
+
+        #[interface(name = "net.hadess.SensorProxy")]
+        impl FakeSensorProxy {
+            fn claim_light(&self) {}
+
+            fn release_light(&self) {}
+
+            #[zbus(property)]
+            fn has_ambient_light(&self) -> bool {
+                true
+            }
+
+            #[zbus(property)]
+            fn light_level(&self) -> f64 {
+                self.level
+            }
+
+            #[zbus(property)]
+            fn light_level_unit(&self) -> String {
+                "lux".to_string()
+            }
+        }
+
+        #[test]
+        fn follows_a_fake_sensor_proxy() {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let (server_side, client_side) = UnixStream::pair().unwrap();
+                    let guid = Guid::generate();
+                    let server = connection::Builder::unix_stream(server_side)
+                        .server(guid)
+                        .unwrap()
+                        .p2p()
+                        .serve_at("/net/hadess/SensorProxy", FakeSensorProxy { level: 0.0 })
+                        .unwrap()
+                        .build();
+                    let client = connection::Builder::unix_stream(client_side).p2p().build();
+                    let (server, client) = futures_util::future::try_join(server, client)
+                        .await
+                        .unwrap();
+
+                    let controller = Mutex::new(Controller::new(curve(), 5));
+                    controller.lock().unwrap().start_from(10);
+                    let applied = Arc::new(Mutex::new(Vec::new()));
+
+                    let stop = {
+                        let server = server.clone();
+                        async move {
+                            // Se enciende una luz fuerte en la habitación.
+                            tokio::time::sleep(TICK * 2).await;
+                            let iface = server
+                                .object_server()
+                                .interface::<_, FakeSensorProxy>("/net/hadess/SensorProxy")
+                                .await
+                                .unwrap();
+                            iface.get_mut().await.level = 10000.0;
+                            let ctx = SignalContext::new(&server, "/net/hadess/SensorProxy").unwrap();
+                            iface.get().await.light_level_changed(&ctx).await.unwrap();
+                            tokio::time::sleep(TICK * 30).await;
+                        }
+                    };
+
+                    let sink = applied.clone();
+                    follow_sensor(&client, &controller, stop, |p| sink.lock().unwrap().push(p))
+                        .await
+                        .unwrap();
+
+                    let applied = applied.lock().unwrap().clone();
+                    assert!(applied.len() > 3, "{:?}", applied);
+                    assert!(applied.windows(2).all(|w| w[0] < w[1]), "{:?}", applied);
+                    assert_eq!(applied.last(), Some(&100));
+                });
+        }
+    }
+}
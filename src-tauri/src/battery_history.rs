@@ -0,0 +1,396 @@
+//! Historial de la batería del equipo.
+//!
+//! Cada pocos minutos se anota el porcentaje, la potencia y el estado en
+//! `~/.local/share/vasak-desktop/battery-history.jsonl`, una muestra por línea,
+//! y se descartan las de más de una semana. Una vez por día se anota también
+//! la capacidad real frente a la de diseño, para ver cómo se desgasta la
+//! batería con los meses; esas no se descartan.
+//!
+//! Si el historial propio todavía no cubre el período pedido (la primera vez
+//! que se usa el shell, por ejemplo), se completa con `GetHistory` de UPower.
+
+use crate::error::{Result, VasakError};
+use crate::logger::log_warning;
+use crate::structs::{BatteryHealthSample, BatteryHistory, BatteryInfo, BatterySample};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zbus::Connection;
+
+/// Segundos entre muestras mientras nada cambia.
+const SAMPLE_INTERVAL: i64 = 120;
+/// Cambio de porcentaje que merece una muestra aunque no haya pasado el
+/// intervalo.
+const PERCENT_STEP: f64 = 1.0;
+const DAY: i64 = 24 * 60 * 60;
+/// Cuánto historial se guarda.
+const KEEP: i64 = 7 * DAY;
+/// Puntos que se le piden a UPower por serie.
+const UPOWER_RESOLUTION: u32 = 200;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistorySpan {
+    Day,
+    Week,
+}
+
+impl HistorySpan {
+    fn seconds(self) -> i64 {
+        match self {
+            HistorySpan::Day => DAY,
+            HistorySpan::Week => KEEP,
+        }
+    }
+}
+
+fn should_record(last: Option<&BatterySample>, next: &BatterySample) -> bool {
+    match last {
+        None => true,
+        Some(last) => {
+            next.time - last.time >= SAMPLE_INTERVAL
+                || last.state != next.state
+                || (last.percentage - next.percentage).abs() >= PERCENT_STEP
+        }
+    }
+}
+
+fn read_lines<T: serde::de::DeserializeOwned>(path: &Path) -> Vec<T> {
+    // Una línea rota (un corte de luz a mitad de escritura) no invalida el resto.
+    fs::read_to_string(path)
+        .map(|raw| {
+            raw.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn append_line<T: serde::Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(value).map_err(|e| VasakError::Parse(e.to_string()))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+fn rewrite<'a, T: serde::Serialize + 'a>(
+    path: &Path,
+    values: impl Iterator<Item = &'a T>,
+) -> Result<()> {
+    let mut raw = String::new();
+    for value in values {
+        let line = serde_json::to_string(value).map_err(|e| VasakError::Parse(e.to_string()))?;
+        raw.push_str(&line);
+        raw.push('\n');
+    }
+    let tmp = path.with_extension("jsonl.tmp");
+    fs::write(&tmp, raw)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) struct Store {
+    samples: VecDeque<BatterySample>,
+    health: Vec<BatteryHealthSample>,
+    samples_path: PathBuf,
+    health_path: PathBuf,
+}
+
+impl Store {
+    pub(crate) fn load(dir: &Path, now: i64) -> Self {
+        let mut store = Self {
+            samples: read_lines(&dir.join("battery-history.jsonl")).into(),
+            health: read_lines(&dir.join("battery-health.jsonl")),
+            samples_path: dir.join("battery-history.jsonl"),
+            health_path: dir.join("battery-health.jsonl"),
+        };
+        store.prune(now);
+        store
+    }
+
+    /// Descarta lo que tiene más de una semana y reescribe el archivo.
+    fn prune(&mut self, now: i64) {
+        let before = self.samples.len();
+        while self.samples.front().is_some_and(|s| now - s.time > KEEP) {
+            self.samples.pop_front();
+        }
+        if self.samples.len() != before {
+            if let Err(e) = rewrite(&self.samples_path, self.samples.iter()) {
+                log_warning(&format!(
+                    "No se pudo recortar el historial de batería: {}",
+                    e
+                ));
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, info: &BatteryInfo, now: i64) -> Result<()> {
+        let sample = BatterySample {
+            time: now,
+            percentage: info.percentage,
+            rate: info.energy_rate,
+            state: info.state.clone(),
+        };
+        if should_record(self.samples.back(), &sample) {
+            append_line(&self.samples_path, &sample)?;
+            self.samples.push_back(sample);
+            // Recortar de a un día evita reescribir el archivo en cada muestra.
+            if self
+                .samples
+                .front()
+                .is_some_and(|s| now - s.time > KEEP + DAY)
+            {
+                self.prune(now);
+            }
+        }
+
+        if let (Some(energy_full), Some(energy_full_design)) =
+            (info.energy_full, info.energy_full_design)
+        {
+            let due = self.health.last().is_none_or(|last| now - last.time >= DAY);
+            if due && energy_full > 0.0 && energy_full_design > 0.0 {
+                let sample = BatteryHealthSample {
+                    time: now,
+                    energy_full,
+                    energy_full_design,
+                };
+                append_line(&self.health_path, &sample)?;
+                self.health.push(sample);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn since(&self, from: i64) -> Vec<BatterySample> {
+        self.samples
+            .iter()
+            .filter(|s| s.time >= from)
+            .cloned()
+            .collect()
+    }
+}
+
+fn history_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("vasak-desktop"))
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+
+fn with_store<T>(f: impl FnOnce(&mut Store) -> T) -> Option<T> {
+    let mut guard = STORE.lock().ok()?;
+    if guard.is_none() {
+        *guard = Some(Store::load(&history_dir()?, now()));
+    }
+    guard.as_mut().map(f)
+}
+
+/// Anota una lectura si pasó suficiente tiempo o cambió algo.
+pub fn record(info: &BatteryInfo) {
+    if !info.has_battery || !info.is_present {
+        return;
+    }
+    if let Some(Err(e)) = with_store(|store| store.record(info, now())) {
+        log_warning(&format!(
+            "No se pudo guardar el historial de batería: {}",
+            e
+        ));
+    }
+}
+
+/// Une el historial de UPower con el propio: de UPower solo se toma lo que es
+/// más viejo que la primera muestra propia.
+pub(crate) fn merge(
+    upower: Vec<BatterySample>,
+    local: Vec<BatterySample>,
+) -> (Vec<BatterySample>, &'static str) {
+    let oldest_local = local.first().map(|s| s.time).unwrap_or(i64::MAX);
+    let mut merged: Vec<BatterySample> = upower
+        .into_iter()
+        .filter(|s| s.time < oldest_local)
+        .collect();
+    merged.sort_by_key(|s| s.time);
+
+    let source = match (merged.is_empty(), local.is_empty()) {
+        (true, true) => "none",
+        (true, false) => "local",
+        (false, true) => "upower",
+        (false, false) => "mixed",
+    };
+    merged.extend(local);
+    (merged, source)
+}
+
+/// `GetHistory` de UPower: porcentaje y potencia por separado, que se juntan
+/// por cercanía en el tiempo.
+async fn upower_history(connection: &Connection, path: &str, span: i64) -> Vec<BatterySample> {
+    async fn series(
+        connection: &Connection,
+        path: &str,
+        kind: &str,
+        span: i64,
+    ) -> zbus::Result<Vec<(u32, f64, u32)>> {
+        connection
+            .call_method(
+                Some("org.freedesktop.UPower"),
+                path,
+                Some("org.freedesktop.UPower.Device"),
+                "GetHistory",
+                &(kind, span as u32, UPOWER_RESOLUTION),
+            )
+            .await?
+            .body()
+            .deserialize()
+    }
+
+    let charge = match series(connection, path, "charge", span).await {
+        Ok(charge) => charge,
+        Err(_) => return Vec::new(),
+    };
+    let rate = series(connection, path, "rate", span)
+        .await
+        .unwrap_or_default();
+    let resolution = span / UPOWER_RESOLUTION as i64;
+
+    charge
+        .into_iter()
+        .filter(|(time, _, _)| *time > 0)
+        .map(|(time, percentage, state)| {
+            let time = time as i64;
+            let rate = rate
+                .iter()
+                .min_by_key(|(t, _, _)| (*t as i64 - time).abs())
+                .filter(|(t, _, _)| (*t as i64 - time).abs() <= resolution)
+                .map(|(_, value, _)| *value);
+            BatterySample {
+                time,
+                percentage,
+                rate,
+                state: crate::applets::battery::state_name(state).to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Historial del período pedido y la salud de la batería.
+pub async fn history(connection: Option<&Connection>, span: HistorySpan) -> BatteryHistory {
+    let from = now() - span.seconds();
+    let (local, health) =
+        with_store(|store| (store.since(from), store.health.clone())).unwrap_or_default();
+
+    // Solo hace falta UPower si lo propio no llega al principio del período.
+    let covered = local
+        .first()
+        .is_some_and(|s| s.time - from <= SAMPLE_INTERVAL * 2);
+    let upower = match (
+        covered,
+        connection,
+        crate::applets::battery::battery_device_path(),
+    ) {
+        (false, Some(connection), Some(path)) => {
+            upower_history(connection, &path, span.seconds()).await
+        }
+        _ => Vec::new(),
+    };
+
+    let (samples, source) = merge(upower, local);
+    BatteryHistory {
+        samples: samples.into_iter().filter(|s| s.time >= from).collect(),
+        source: source.to_string(),
+        health,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(percentage: f64, state: &str) -> BatteryInfo {
+        BatteryInfo {
+            has_battery: true,
+            percentage,
+            state: state.to_string(),
+            time_to_empty: None,
+            time_to_full: None,
+            is_present: true,
+            is_charging: state == "Charging",
+            vendor: None,
+            model: None,
+            technology: None,
+            energy: None,
+            energy_full: Some(45.0),
+            energy_full_design: Some(50.0),
+            energy_rate: Some(7.5),
+            charge_threshold_supported: false,
+            charge_threshold_enabled: false,
+            charge_start_threshold: None,
+            charge_end_threshold: None,
+            voltage: None,
+            temperature: None,
+            serial: None,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vasak-battery-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn samples_are_throttled_and_survive_a_restart() {
+        let dir = temp_dir("store");
+        let start = 1_700_000_000;
+        let mut store = Store::load(&dir, start);
+
+        store.record(&info(80.0, "Discharging"), start).unwrap();
+        store
+            .record(&info(79.8, "Discharging"), start + 10)
+            .unwrap();
+        store
+            .record(&info(79.0, "Discharging"), start + 20)
+            .unwrap();
+        store.record(&info(79.0, "Charging"), start + 30).unwrap();
+        store.record(&info(79.2, "Charging"), start + 200).unwrap();
+        assert_eq!(store.samples.len(), 4);
+        assert_eq!(store.health.len(), 1);
+
+        // Una semana y pico después, lo viejo se descarta al cargar.
+        let reloaded = Store::load(&dir, start + 100);
+        assert_eq!(reloaded.samples.len(), 4);
+        assert_eq!(reloaded.health.len(), 1);
+        let later = Store::load(&dir, start + KEEP + 150);
+        assert_eq!(later.since(0).len(), 1);
+        assert_eq!(later.health.len(), 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn upower_only_fills_what_is_missing() {
+        let sample = |time, percentage| BatterySample {
+            time,
+            percentage,
+            rate: None,
+            state: "Discharging".to_string(),
+        };
+        let upower = vec![sample(300, 70.0), sample(100, 90.0), sample(200, 80.0)];
+        let local = vec![sample(250, 75.0), sample(350, 65.0)];
+
+        let (merged, source) = merge(upower.clone(), local.clone());
+        let times: Vec<i64> = merged.iter().map(|s| s.time).collect();
+        assert_eq!(times, vec![100, 200, 250, 350]);
+        assert_eq!(source, "mixed");
+
+        assert_eq!(merge(upower, Vec::new()).1, "upower");
+        assert_eq!(merge(Vec::new(), local).1, "local");
+        assert_eq!(merge(Vec::new(), Vec::new()).1, "none");
+    }
+}
@@ -0,0 +1,324 @@
+//! Avisos de batería baja y acción al llegar al nivel crítico.
+//!
+//! Cada lectura de la batería del equipo pasa por `observe`. Los avisos salen
+//! una sola vez por descarga y cada uno es más urgente que el anterior. Al
+//! llegar al nivel crítico arranca una cuenta regresiva que el usuario puede
+//! cancelar; si nadie la cancela, el equipo se suspende, hiberna o apaga según
+//! las preferencias. Al conectar el cargador todo vuelve a empezar.
+
+use crate::error::Result;
+use crate::logger::{log_error, log_info, log_warning};
+use crate::notifications::{localized, send_system_notification};
+use crate::session_actions::system_bus;
+use crate::settings::{self, BatterySettings, CriticalBatteryAction};
+use crate::structs::{BatteryInfo, CriticalBatteryCountdown};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// Se cruzó un umbral de aviso
+    Low { level: u32, urgency: &'static str },
+    /// Se llegó al nivel crítico
+    Critical,
+    /// Se conectó el cargador después de algún aviso
+    Reset,
+}
+
+/// Qué avisos ya se dieron en la descarga actual.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    /// El umbral más bajo que ya se avisó
+    lowest_warned: Option<u32>,
+    critical: bool,
+}
+
+impl Tracker {
+    pub(crate) const fn new() -> Self {
+        Self {
+            lowest_warned: None,
+            critical: false,
+        }
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        settings: &BatterySettings,
+        percentage: f64,
+        on_battery: bool,
+    ) -> Option<Event> {
+        if !on_battery {
+            let warned = self.critical || self.lowest_warned.is_some();
+            *self = Self::new();
+            return warned.then_some(Event::Reset);
+        }
+
+        if percentage <= settings.critical_percent as f64 {
+            if self.critical {
+                return None;
+            }
+            self.critical = true;
+            return Some(Event::Critical);
+        }
+        if self.critical {
+            return None;
+        }
+
+        let mut levels: Vec<u32> = settings
+            .warning_levels
+            .iter()
+            .copied()
+            .filter(|level| *level > settings.critical_percent)
+            .collect();
+        levels.sort_unstable_by(|a, b| b.cmp(a));
+        levels.dedup();
+
+        // El umbral más bajo ya alcanzado: si la descarga empieza por debajo
+        // de varios, alcanza con el aviso más urgente.
+        let index = levels
+            .iter()
+            .rposition(|level| percentage <= *level as f64)?;
+        let level = levels[index];
+        if self.lowest_warned.is_some_and(|warned| warned <= level) {
+            return None;
+        }
+        self.lowest_warned = Some(level);
+
+        let urgency = if index == 0 { "low" } else { "normal" };
+        Some(Event::Low { level, urgency })
+    }
+}
+
+static TRACKER: Mutex<Tracker> = Mutex::new(Tracker::new());
+
+/// Sube con cada cuenta regresiva y con cada cancelación; una cuenta que ve
+/// otro número sabe que la cancelaron.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static PENDING: Mutex<Option<CriticalBatteryAction>> = Mutex::new(None);
+
+/// Si la batería se está descargando. "Unknown" incluye el caso de un equipo
+/// enchufado que dejó de cargar por un umbral, que no merece avisos.
+pub(crate) fn on_battery(info: &BatteryInfo) -> bool {
+    !info.is_charging && matches!(info.state.as_str(), "Discharging" | "Empty")
+}
+
+fn action_name(action: CriticalBatteryAction) -> &'static str {
+    match action {
+        CriticalBatteryAction::Nothing => "nothing",
+        CriticalBatteryAction::Suspend => "suspend",
+        CriticalBatteryAction::Hibernate => "hibernate",
+        CriticalBatteryAction::PowerOff => "poweroff",
+    }
+}
+
+/// Revisa una lectura de la batería del equipo y avisa si hace falta.
+pub fn observe(app: &AppHandle, info: &BatteryInfo) {
+    if !info.has_battery || !info.is_present {
+        return;
+    }
+
+    let settings = settings::get().battery;
+    let event = match TRACKER.lock() {
+        Ok(mut tracker) => tracker.update(&settings, info.percentage, on_battery(info)),
+        Err(_) => return,
+    };
+
+    match event {
+        None => {}
+        Some(Event::Reset) => {
+            log_info("Cargador conectado: se reinician los avisos de batería");
+            cancel(app);
+        }
+        Some(Event::Low { level, urgency }) => {
+            log_info(&format!(
+                "Batería por debajo del {}%: {:.0}%",
+                level, info.percentage
+            ));
+            notify(
+                localized(app, "battery.low"),
+                format!("{:.0}%", info.percentage),
+                urgency,
+            );
+        }
+        Some(Event::Critical) => {
+            let action = settings.critical_action;
+            log_warning(&format!(
+                "Batería crítica ({:.0}%), acción: {}",
+                info.percentage,
+                action_name(action)
+            ));
+            let hint = localized(app, &format!("battery.action.{}", action_name(action)));
+            notify(
+                localized(app, "battery.critical"),
+                format!("{:.0}% · {}", info.percentage, hint),
+                "critical",
+            );
+            if action != CriticalBatteryAction::Nothing {
+                start_countdown(app.clone(), action, settings.action_delay_seconds);
+            }
+        }
+    }
+}
+
+fn notify(summary: String, body: String, urgency: &'static str) {
+    tokio::spawn(async move {
+        if let Err(e) =
+            send_system_notification(summary, Some(body), Some(urgency.to_string())).await
+        {
+            log_warning(&format!("No se pudo avisar de la batería: {}", e));
+        }
+    });
+}
+
+fn emit_countdown(app: &AppHandle, action: CriticalBatteryAction, remaining: Option<u32>) {
+    let _ = app.emit(
+        "battery-critical-countdown",
+        CriticalBatteryCountdown {
+            action: action_name(action).to_string(),
+            remaining,
+        },
+    );
+}
+
+fn start_countdown(app: AppHandle, action: CriticalBatteryAction, seconds: u32) {
+    let generation = match PENDING.lock() {
+        Ok(mut pending) => {
+            *pending = Some(action);
+            GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+        }
+        Err(_) => return,
+    };
+
+    tokio::spawn(async move {
+        for remaining in (1..=seconds).rev() {
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            emit_countdown(&app, action, Some(remaining));
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        // Se revisa con el candado tomado para no pisar una cancelación de
+        // último momento.
+        {
+            let Ok(mut pending) = PENDING.lock() else {
+                return;
+            };
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            *pending = None;
+        }
+        emit_countdown(&app, action, Some(0));
+
+        log_info(&format!(
+            "Batería crítica: ejecutando {}",
+            action_name(action)
+        ));
+        if let Err(e) = run_action(&app, action).await {
+            log_error(&format!(
+                "No se pudo ejecutar {} por batería crítica: {}",
+                action_name(action),
+                e
+            ));
+        }
+    });
+}
+
+/// Cancela la acción pendiente por batería crítica. Devuelve si había una.
+pub fn cancel(app: &AppHandle) -> bool {
+    let action = match PENDING.lock() {
+        Ok(mut pending) => {
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+            pending.take()
+        }
+        Err(_) => return false,
+    };
+
+    match action {
+        Some(action) => {
+            log_info(&format!(
+                "Acción por batería crítica cancelada: {}",
+                action_name(action)
+            ));
+            emit_countdown(app, action, None);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn run_action(app: &AppHandle, action: CriticalBatteryAction) -> Result<()> {
+    let method = match action {
+        CriticalBatteryAction::Nothing => return Ok(()),
+        CriticalBatteryAction::Suspend => "Suspend",
+        CriticalBatteryAction::Hibernate => "Hibernate",
+        CriticalBatteryAction::PowerOff => "PowerOff",
+    };
+
+    let connection = system_bus(app).await?;
+
+    // Sin interacción: a esta altura no hay tiempo para pedir contraseña.
+    connection
+        .call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1",
+            Some("org.freedesktop.login1.Manager"),
+            method,
+            &(false,),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings_come_once_per_discharge_and_grow_urgent() {
+        let settings = BatterySettings::default();
+        let mut tracker = Tracker::new();
+
+        assert_eq!(tracker.update(&settings, 50.0, true), None);
+        assert_eq!(
+            tracker.update(&settings, 20.0, true),
+            Some(Event::Low { level: 20, urgency: "low" })
+        );
+        assert_eq!(tracker.update(&settings, 19.0, true), None);
+        assert_eq!(
+            tracker.update(&settings, 9.5, true),
+            Some(Event::Low { level: 10, urgency: "normal" })
+        );
+        // Un rebote de la lectura no repite el aviso.
+        assert_eq!(tracker.update(&settings, 11.0, true), None);
+        assert_eq!(tracker.update(&settings, 10.0, true), None);
+        assert_eq!(tracker.update(&settings, 5.0, true), Some(Event::Critical));
+        assert_eq!(tracker.update(&settings, 4.0, true), None);
+
+        assert_eq!(tracker.update(&settings, 4.0, false), Some(Event::Reset));
+        assert_eq!(tracker.update(&settings, 30.0, false), None);
+        assert_eq!(
+            tracker.update(&settings, 18.0, true),
+            Some(Event::Low { level: 20, urgency: "low" })
+        );
+    }
+
+    #[test]
+    fn starting_below_several_levels_warns_only_the_lowest() {
+        let settings = BatterySettings::default();
+        let mut tracker = Tracker::new();
+
+        assert_eq!(
+            tracker.update(&settings, 8.0, true),
+            Some(Event::Low { level: 10, urgency: "normal" })
+        );
+        assert_eq!(tracker.update(&settings, 15.0, true), None);
+
+        let mut tracker = Tracker::new();
+        assert_eq!(tracker.update(&settings, 3.0, true), Some(Event::Critical));
+        assert_eq!(tracker.update(&settings, 8.0, true), None);
+    }
+}
@@ -1,32 +1,117 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::sync::OnceLock;
-use crate::constants::{CMD_BRIGHTNESSCTL, CMD_BUSCTL, BACKLIGHT_PATH};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::constants::{CMD_BRIGHTNESSCTL, CMD_BUSCTL, SYS_CLASS_PATH};
 use crate::error::{Result, VasakError};
 use crate::logger::{log_info, log_error, log_debug};
-use crate::structs::BrightnessInfo;
+use crate::settings;
+use crate::structs::{BrightnessDevice, BrightnessInfo};
 use crate::utils::CommandExecutor;
 
 /// Método de control de brillo disponible
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug)]
 enum BrightnessMethod {
     Brightnessctl,
-    Logind(PathBuf),
-    Sysfs(PathBuf),
+    Logind,
+    Sysfs,
 }
 
+/// Qué ilumina un dispositivo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrightnessKind {
+    /// Retroiluminación de una pantalla (`/sys/class/backlight`)
+    Display,
+    /// Retroiluminación del teclado (`/sys/class/leds/*kbd_backlight*`)
+    Keyboard,
+    /// Monitor sin retroiluminación regulable: se oscurece por software con
+    /// su tabla de gamma
+    Output,
+}
+
+impl BrightnessKind {
+    /// Clase de sysfs, que es también el subsistema que piden brightnessctl y
+    /// logind.
+    fn class(self) -> &'static str {
+        match self {
+            BrightnessKind::Display => "backlight",
+            BrightnessKind::Keyboard => "leds",
+            BrightnessKind::Output => "output",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BrightnessKind::Display => "display",
+            BrightnessKind::Keyboard => "keyboard",
+            BrightnessKind::Output => "output",
+        }
+    }
+
+    /// Evento que recibe el frontend cuando cambia un dispositivo de este tipo.
+    pub fn changed_event(self) -> &'static str {
+        match self {
+            BrightnessKind::Display | BrightnessKind::Output => "brightness-changed",
+            BrightnessKind::Keyboard => "keyboard-brightness-changed",
+        }
+    }
+
+    /// Icono y clave de traducción del OSD.
+    pub fn osd(self) -> (&'static str, &'static str) {
+        match self {
+            BrightnessKind::Display | BrightnessKind::Output => {
+                ("display-brightness", "osd.brightness")
+            }
+            BrightnessKind::Keyboard => ("keyboard-brightness", "osd.keyboardBrightness"),
+        }
+    }
+}
+
+/// Un dispositivo con brillo regulable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BacklightDevice {
+    pub kind: BrightnessKind,
+    /// Nombre en sysfs (`intel_backlight`, `tpacpi::kbd_backlight`) o, para
+    /// las salidas, el conector (`DP-1`)
+    pub name: String,
+    /// Directorio en sysfs; vacío para las salidas
+    pub path: PathBuf,
+}
+
+impl BacklightDevice {
+    /// Identificador que usan los comandos: `backlight/intel_backlight`,
+    /// `leds/tpacpi::kbd_backlight`, `output/DP-1`.
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.kind.class(), self.name)
+    }
+
+    pub fn brightness_path(&self) -> PathBuf {
+        self.path.join("brightness")
+    }
+
+    pub fn max_brightness_path(&self) -> PathBuf {
+        self.path.join("max_brightness")
+    }
+}
+
+/// Intervalo entre escrituras durante una transición: unos 60 cuadros por
+/// segundo, aunque con brightnessctl cada escritura es un proceso y el ritmo
+/// real es algo menor.
+const TRANSITION_FRAME: Duration = Duration::from_millis(16);
+
 /// Cache global del método de brillo detectado
 static BRIGHTNESS_METHOD: OnceLock<BrightnessMethod> = OnceLock::new();
 
 /// Obtiene el método de brillo, detectándolo solo la primera vez
 fn get_brightness_method() -> Result<BrightnessMethod> {
     if let Some(method) = BRIGHTNESS_METHOD.get() {
-        return Ok(method.clone());
+        return Ok(*method);
     }
 
     let method = detect_brightness_method()?;
 
-    let _ = BRIGHTNESS_METHOD.set(method.clone());
+    let _ = BRIGHTNESS_METHOD.set(method);
 
     Ok(method)
 }
@@ -41,21 +126,18 @@ fn detect_brightness_method() -> Result<BrightnessMethod> {
         return Ok(BrightnessMethod::Brightnessctl);
     }
 
+    let devices = list_devices();
+
     // 2. logind D-Bus via busctl (no requiere contraseña)
-    if let Ok(device_path) = find_backlight_device() {
-        if CommandExecutor::run_silent(CMD_BUSCTL, &["--version"]) {
-            log_info(&format!("Usando logind (busctl) para control de brillo: {:?}", device_path));
-            return Ok(BrightnessMethod::Logind(device_path));
-        }
+    if !devices.is_empty() && CommandExecutor::run_silent(CMD_BUSCTL, &["--version"]) {
+        log_info("Usando logind (busctl) para control de brillo");
+        return Ok(BrightnessMethod::Logind);
     }
 
     // 3. sysfs directo (puede fallar si no hay permisos)
-    if let Ok(device_path) = find_backlight_device() {
-        let test_path = device_path.join("brightness");
-        if fs::read_to_string(&test_path).is_ok() {
-            log_info(&format!("Usando sysfs para control de brillo: {:?}", device_path));
-            return Ok(BrightnessMethod::Sysfs(device_path));
-        }
+    if devices.iter().any(|device| fs::read_to_string(device.brightness_path()).is_ok()) {
+        log_info("Usando sysfs para control de brillo");
+        return Ok(BrightnessMethod::Sysfs);
     }
 
     log_error("No se encontró método de control de brillo disponible");
@@ -64,125 +146,464 @@ fn detect_brightness_method() -> Result<BrightnessMethod> {
     ))
 }
 
-/// Encuentra el primer dispositivo de backlight disponible
-fn find_backlight_device() -> Result<PathBuf> {
-    if !Path::new(BACKLIGHT_PATH).exists() {
-        return Err(VasakError::NotFound("No backlight devices found".to_string()));
+/// Prioridad del `type` de un backlight: la interfaz del firmware es la que
+/// mejor conoce el panel; `raw` habla directo con la GPU.
+fn backlight_type_rank(device_path: &Path) -> u8 {
+    match fs::read_to_string(device_path.join("type")).as_deref().map(str::trim) {
+        Ok("firmware") => 0,
+        Ok("platform") => 1,
+        Ok("raw") => 2,
+        _ => 3,
     }
+}
 
-    let entries = fs::read_dir(BACKLIGHT_PATH)
-        .map_err(VasakError::Io)?;
+fn scan_class(class_dir: &Path, kind: BrightnessKind) -> Vec<BacklightDevice> {
+    let Ok(entries) = fs::read_dir(class_dir) else {
+        return Vec::new();
+    };
 
-    for entry in entries {
-        let entry = entry.map_err(VasakError::Io)?;
-        let device_path = entry.path();
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if kind == BrightnessKind::Keyboard && !name.contains("kbd_backlight") {
+                return None;
+            }
+
+            let device = BacklightDevice {
+                kind,
+                name,
+                path: entry.path(),
+            };
+            (device.brightness_path().exists() && device.max_brightness_path().exists())
+                .then_some(device)
+        })
+        .collect()
+}
 
-        let brightness_file = device_path.join("brightness");
-        let max_brightness_file = device_path.join("max_brightness");
+/// Dispositivos bajo `sys_class` (normalmente `/sys/class`): primero las
+/// pantallas, la principal al frente, y después los teclados.
+fn scan_devices(sys_class: &Path) -> Vec<BacklightDevice> {
+    let mut displays = scan_class(&sys_class.join("backlight"), BrightnessKind::Display);
+    displays.sort_by(|a, b| {
+        backlight_type_rank(&a.path)
+            .cmp(&backlight_type_rank(&b.path))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut keyboards = scan_class(&sys_class.join("leds"), BrightnessKind::Keyboard);
+    keyboards.sort_by(|a, b| a.name.cmp(&b.name));
+
+    displays.extend(keyboards);
+    displays
+}
 
-        if brightness_file.exists() && max_brightness_file.exists() {
-            return Ok(device_path);
-        }
-    }
+/// Todos los dispositivos de brillo de sysfs.
+pub fn list_devices() -> Vec<BacklightDevice> {
+    scan_devices(Path::new(SYS_CLASS_PATH))
+}
 
-    Err(VasakError::NotFound("No valid backlight device found".to_string()))
+/// Conectores de paneles internos, que ya tienen retroiluminación.
+fn is_internal_panel(connector: &str) -> bool {
+    ["eDP", "LVDS", "DSI"]
+        .iter()
+        .any(|prefix| connector.starts_with(prefix))
 }
 
-/// Obtiene el brillo usando brightnessctl
-fn get_brightness_brightnessctl() -> Result<BrightnessInfo> {
-    let current_output = CommandExecutor::run(CMD_BRIGHTNESSCTL, &["get"])?;
-    let max_output = CommandExecutor::run(CMD_BRIGHTNESSCTL, &["max"])?;
+/// Salidas que se oscurecen por software. Si hay retroiluminación, el panel
+/// interno queda afuera: oscurecerlo dos veces solo confunde.
+pub fn list_outputs(has_backlight: bool) -> Vec<BacklightDevice> {
+    let Some(gamma) = crate::gamma::service() else {
+        return Vec::new();
+    };
 
-    let current: u32 = current_output.trim()
-        .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse brightness: {}", e)))?;
+    gamma
+        .outputs()
+        .into_iter()
+        .filter(|(name, _)| !(has_backlight && is_internal_panel(name)))
+        .map(|(name, _)| BacklightDevice {
+            kind: BrightnessKind::Output,
+            name,
+            path: PathBuf::new(),
+        })
+        .collect()
+}
 
-    let max: u32 = max_output.trim()
-        .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse max brightness: {}", e)))?;
+/// Dispositivos de sysfs más las salidas que se oscurecen por software.
+fn all_devices() -> Vec<BacklightDevice> {
+    let mut devices = list_devices();
+    let has_backlight = devices.iter().any(|d| d.kind == BrightnessKind::Display);
+    devices.extend(list_outputs(has_backlight));
+    devices
+}
 
-    let current_percent = if max > 0 {
-        ((current as f32 / max as f32) * 100.0) as u32
-    } else {
-        0
+/// Busca un dispositivo por su id; sin id, la pantalla principal (sin
+/// retroiluminación, el primer monitor).
+pub fn find_device(device_id: Option<&str>) -> Result<BacklightDevice> {
+    let Some(id) = device_id else {
+        return first_of(BrightnessKind::Display).or_else(|e| {
+            list_outputs(false).into_iter().next().ok_or(e)
+        });
     };
 
-    Ok(BrightnessInfo {
-        current: current_percent,
-        max: 100,
-        min: 0,
-    })
+    all_devices()
+        .into_iter()
+        .find(|device| device.id() == id)
+        .ok_or_else(|| VasakError::NotFound(format!("Dispositivo de brillo {} no encontrado", id)))
 }
 
-/// Obtiene el brillo usando sysfs
-fn get_brightness_sysfs(device_path: &Path) -> Result<BrightnessInfo> {
-    let brightness_file = device_path.join("brightness");
-    let max_brightness_file = device_path.join("max_brightness");
-
-    let current: u32 = fs::read_to_string(&brightness_file)
-        .map_err(VasakError::Io)?
-        .trim()
-        .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse brightness: {}", e)))?;
+/// El primer dispositivo de un tipo: la pantalla principal o el teclado.
+pub fn first_of(kind: BrightnessKind) -> Result<BacklightDevice> {
+    list_devices()
+        .into_iter()
+        .find(|device| device.kind == kind)
+        .ok_or_else(|| VasakError::NotFound(format!("No valid {} brightness device found", kind.label())))
+}
 
-    let max: u32 = fs::read_to_string(&max_brightness_file)
-        .map_err(VasakError::Io)?
-        .trim()
+fn parse_level(raw: &str, what: &str) -> Result<u32> {
+    raw.trim()
         .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse max brightness: {}", e)))?;
+        .map_err(|e| VasakError::Parse(format!("Failed to parse {}: {}", what, e)))
+}
 
-    let current_percent = if max > 0 {
-        ((current as f32 / max as f32) * 100.0) as u32
+pub fn to_percent(current: u32, max: u32) -> u32 {
+    if max > 0 {
+        ((current as f64 / max as f64) * 100.0).round() as u32
     } else {
         0
-    };
+    }
+}
 
-    Ok(BrightnessInfo {
-        current: current_percent,
-        max: 100,
-        min: 0,
+fn to_raw(percent: u32, max: u32) -> u32 {
+    ((percent.min(100) as f64 / 100.0) * max as f64).round() as u32
+}
+
+/// Reparto del control (0-100) sobre las unidades del panel.
+///
+/// La luz que emite un panel es casi proporcional a las unidades, pero el ojo
+/// la percibe de forma logarítmica: repartido en línea recta, el primer 20 %
+/// del control es casi negro y la mitad de arriba parece toda igual. Con un
+/// exponente, cada paso del control se nota parecido. El 0 del control es el
+/// mínimo garantizado, no el apagado.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BrightnessCurve {
+    pub exponent: f64,
+    pub min_percent: u32,
+}
+
+impl BrightnessCurve {
+    pub fn from_settings() -> Self {
+        let brightness = settings::get().brightness;
+        Self {
+            exponent: brightness.curve_exponent,
+            min_percent: brightness.min_percent,
+        }
+    }
+
+    fn exponent(&self) -> f64 {
+        if self.exponent.is_finite() {
+            self.exponent.clamp(0.25, 4.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Unidades que corresponden al 0 del control; al menos 1, porque en
+    /// muchos paneles 0 apaga la retroiluminación.
+    fn floor(&self, max: u32) -> u32 {
+        if max == 0 {
+            return 0;
+        }
+        ((max as f64 * self.min_percent.min(100) as f64 / 100.0).round() as u32).clamp(1, max)
+    }
+
+    pub fn to_raw(&self, percent: u32, max: u32) -> u32 {
+        let floor = self.floor(max);
+        let position = (percent.min(100) as f64 / 100.0).powf(self.exponent());
+        (floor as f64 + (max - floor) as f64 * position).round() as u32
+    }
+
+    pub fn to_percent(&self, raw: u32, max: u32) -> u32 {
+        let floor = self.floor(max);
+        if max <= floor {
+            return if raw >= max && max > 0 { 100 } else { 0 };
+        }
+        let position = (raw.clamp(floor, max) - floor) as f64 / (max - floor) as f64;
+        (position.powf(1.0 / self.exponent()) * 100.0).round() as u32
+    }
+}
+
+/// La curva solo tiene sentido en las pantallas con retroiluminación: los
+/// teclados tienen dos o tres niveles y apagarlos es válido, y las salidas
+/// por software ya trabajan en porcentaje.
+fn curve_for(device: &BacklightDevice) -> Option<BrightnessCurve> {
+    (device.kind == BrightnessKind::Display).then(BrightnessCurve::from_settings)
+}
+
+/// Porcentaje del control para un nivel del driver.
+pub fn percent_of(device: &BacklightDevice, raw: u32, max: u32) -> u32 {
+    match curve_for(device) {
+        Some(curve) => curve.to_percent(raw, max),
+        None => to_percent(raw, max),
+    }
+}
+
+fn raw_of(device: &BacklightDevice, percent: u32, max: u32) -> u32 {
+    match curve_for(device) {
+        Some(curve) => curve.to_raw(percent, max),
+        None => to_raw(percent, max),
+    }
+}
+
+/// Última transición de cada dispositivo. Cada una invalida la anterior del
+/// mismo dispositivo (al arrastrar el control, la última posición gana); la
+/// luz del teclado u otra pantalla no cortan el fundido de esta.
+#[derive(Debug, Default)]
+struct Transitions(HashMap<String, u64>);
+
+impl Transitions {
+    /// Empieza una transición en `device` y devuelve su número.
+    fn start(&mut self, device: &str) -> u64 {
+        let generation = self.0.entry(device.to_string()).or_insert(0);
+        *generation += 1;
+        *generation
+    }
+
+    /// Si la transición `generation` sigue siendo la última de `device`.
+    fn current(&self, device: &str, generation: u64) -> bool {
+        self.0.get(device) == Some(&generation)
+    }
+}
+
+static TRANSITIONS: LazyLock<Mutex<Transitions>> = LazyLock::new(Default::default);
+
+/// Pasos de una transición de `from` a `to` en unidades del driver, sin
+/// incluir `from`. Con pocas unidades (un panel de 0 a 15) hay menos pasos.
+fn transition_steps(from: u32, to: u32, duration: Duration) -> Vec<u32> {
+    let distance = from.abs_diff(to);
+    let count = (duration.as_millis() / TRANSITION_FRAME.as_millis()).max(1) as u32;
+    let count = count.min(distance).max(1);
+
+    (1..=count)
+        .map(|step| {
+            // Sale rápido y frena al llegar
+            let t = step as f64 / count as f64;
+            let eased = 1.0 - (1.0 - t).powi(2);
+            (from as f64 + (to as f64 - from as f64) * eased).round() as u32
+        })
+        .fold(Vec::new(), |mut steps, value| {
+            if steps.last() != Some(&value) {
+                steps.push(value);
+            }
+            steps
+        })
+}
+
+/// Mientras dura, lo que lee el monitor de sysfs se toma como escrito por el
+/// shell aunque no coincida con el último valor: una transición, o un driver
+/// que redondea en `actual_brightness`.
+const SHELL_WRITE_WINDOW: Duration = Duration::from_millis(750);
+
+/// Lo último que escribió el shell en cada dispositivo. El monitor de sysfs
+/// muestra el OSD para los cambios que vienen de afuera (teclas que maneja el
+/// firmware, otro programa); los del shell ya los muestra quien los pidió, o
+/// no se anuncian, como los pasos del brillo automático.
+#[derive(Debug, Default)]
+struct ShellWrites(HashMap<String, (u32, Instant)>);
+
+impl ShellWrites {
+    fn note(&mut self, device: String, raw: u32, now: Instant) {
+        self.0.insert(device, (raw, now));
+    }
+
+    /// Si el valor `raw` que se leyó en `device` lo escribió el shell. Llegar
+    /// al último valor escrito cierra la cuenta; pasada la ventana, lo que se
+    /// lee ya es de otro.
+    fn claim(&mut self, device: &str, raw: u32, now: Instant) -> bool {
+        let Some(&(last, at)) = self.0.get(device) else {
+            return false;
+        };
+        if raw == last {
+            self.0.remove(device);
+            return true;
+        }
+        if now.duration_since(at) < SHELL_WRITE_WINDOW {
+            return true;
+        }
+        self.0.remove(device);
+        false
+    }
+}
+
+static SHELL_WRITES: LazyLock<Mutex<ShellWrites>> = LazyLock::new(Default::default);
+
+/// Para el monitor de sysfs: si el cambio a `raw` lo hizo el shell.
+pub fn claim_shell_write(device: &BacklightDevice, raw: u32) -> bool {
+    SHELL_WRITES
+        .lock()
+        .map(|mut writes| writes.claim(&device.id(), raw, Instant::now()))
+        .unwrap_or(false)
+}
+
+/// Lleva el dispositivo de `from` a `to` de a poco. Se corta si mientras
+/// tanto llega otro cambio animado al mismo dispositivo.
+fn animate(device: &BacklightDevice, from: u32, to: u32) -> Result<()> {
+    let transition_ms = settings::get().brightness.transition_ms;
+    if transition_ms == 0 || device.kind != BrightnessKind::Display {
+        return write_raw(device, to);
+    }
+
+    let id = device.id();
+    let generation = TRANSITIONS
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("brightness transitions"))?
+        .start(&id);
+
+    for value in transition_steps(from, to, Duration::from_millis(transition_ms as u64)) {
+        let current = TRANSITIONS
+            .lock()
+            .map(|transitions| transitions.current(&id, generation))
+            .unwrap_or(false);
+        if !current {
+            return Ok(());
+        }
+        write_raw(device, value)?;
+        if value != to {
+            std::thread::sleep(TRANSITION_FRAME);
+        }
+    }
+    Ok(())
+}
+
+/// Lee el nivel usando brightnessctl
+fn read_brightnessctl(device: &BacklightDevice) -> Result<(u32, u32)> {
+    let args = |action: &'static str| ["-d", device.name.as_str(), "-c", device.kind.class(), action];
+
+    let current = CommandExecutor::run(CMD_BRIGHTNESSCTL, &args("get"))?;
+    let max = CommandExecutor::run(CMD_BRIGHTNESSCTL, &args("max"))?;
+
+    Ok((parse_level(&current, "brightness")?, parse_level(&max, "max brightness")?))
+}
+
+/// Lee el nivel usando sysfs
+fn read_sysfs(device: &BacklightDevice) -> Result<(u32, u32)> {
+    let current = fs::read_to_string(device.brightness_path()).map_err(VasakError::Io)?;
+    let max = fs::read_to_string(device.max_brightness_path()).map_err(VasakError::Io)?;
+
+    Ok((parse_level(&current, "brightness")?, parse_level(&max, "max brightness")?))
+}
+
+/// Oscurecer del todo un monitor por software lo deja en negro sin forma de
+/// ver el control para volver; por debajo de esto no se baja.
+const OUTPUT_MIN_PERCENT: u32 = 10;
+
+fn gamma() -> Result<&'static crate::gamma::GammaService> {
+    crate::gamma::service().ok_or_else(|| {
+        VasakError::Brightness("El compositor no permite cambiar la gamma".to_string())
     })
 }
 
-/// Obtiene la información actual del brillo del sistema
-pub fn get_brightness() -> Result<BrightnessInfo> {
-    log_debug("Obteniendo información de brillo del sistema");
-    let method = get_brightness_method()?;
+/// Las salidas no tienen unidades propias: se manejan en porcentaje.
+fn read_output(device: &BacklightDevice) -> Result<(u32, u32)> {
+    gamma()?
+        .outputs()
+        .into_iter()
+        .find(|(name, _)| *name == device.name)
+        .map(|(_, brightness)| (brightness, 100))
+        .ok_or_else(|| VasakError::NotFound(format!("Salida {} no encontrada", device.name)))
+}
 
-    let result = match method {
-        BrightnessMethod::Brightnessctl => get_brightness_brightnessctl(),
-        BrightnessMethod::Logind(ref device_path) | BrightnessMethod::Sysfs(ref device_path) => get_brightness_sysfs(device_path),
-    };
+/// Cuánto tiene que quedar quieto el brillo de las salidas para guardarlo.
+const OUTPUT_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Brillo de las salidas que todavía no está en las preferencias. Arrastrar el
+/// control escribe decenas de veces por segundo; se guarda una vez, cuando se
+/// suelta.
+#[derive(Debug, Default)]
+struct UnsavedOutputs {
+    values: HashMap<String, u32>,
+    last_write: Option<Instant>,
+    /// Si ya hay un hilo esperando para guardar
+    saver: bool,
+}
 
-    if let Ok(ref info) = result {
-        log_debug(&format!("Brillo actual: {}%", info.current));
+impl UnsavedOutputs {
+    /// Lo que hay para guardar, si desde la última escritura pasó la espera.
+    fn take_if_idle(&mut self, now: Instant) -> Option<HashMap<String, u32>> {
+        let last_write = self.last_write?;
+        if now.duration_since(last_write) < OUTPUT_SAVE_DELAY {
+            return None;
+        }
+        self.last_write = None;
+        Some(std::mem::take(&mut self.values))
     }
-    result
 }
 
-/// Establece el brillo usando brightnessctl
-fn set_brightness_brightnessctl(brightness: u32) -> Result<()> {
-    let brightness_str = format!("{}%", brightness);
-    CommandExecutor::run(CMD_BRIGHTNESSCTL, &["set", &brightness_str])?;
+static UNSAVED_OUTPUTS: LazyLock<Mutex<UnsavedOutputs>> = LazyLock::new(Default::default);
+
+fn save_outputs_when_idle() {
+    loop {
+        std::thread::sleep(OUTPUT_SAVE_DELAY);
+        let Ok(mut unsaved) = UNSAVED_OUTPUTS.lock() else {
+            return;
+        };
+        let Some(values) = unsaved.take_if_idle(Instant::now()) else {
+            continue;
+        };
+        unsaved.saver = false;
+        drop(unsaved);
+
+        if let Err(e) = settings::update(|s| s.brightness.outputs.extend(values)) {
+            log_error(&format!("No se pudo guardar el brillo de las salidas: {}", e));
+        }
+        return;
+    }
+}
+
+fn write_output(device: &BacklightDevice, percent: u32) -> Result<()> {
+    let percent = percent.clamp(OUTPUT_MIN_PERCENT, 100);
+    gamma()?.set_output_brightness(&device.name, percent)?;
+
+    // La tabla de gamma se pierde al cerrar la sesión; la retroiluminación no.
+    let mut unsaved = UNSAVED_OUTPUTS
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("unsaved outputs"))?;
+    unsaved.values.insert(device.name.clone(), percent);
+    unsaved.last_write = Some(Instant::now());
+    if !unsaved.saver {
+        unsaved.saver = true;
+        std::thread::spawn(save_outputs_when_idle);
+    }
     Ok(())
 }
 
-/// Establece el brillo usando logind D-Bus (busctl)
-fn set_brightness_logind(device_path: &Path, brightness: u32) -> Result<()> {
-    let max_brightness_file = device_path.join("max_brightness");
-    let device_name = device_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| VasakError::Brightness("Invalid device name".to_string()))?;
+/// Nivel actual y máximo del dispositivo, en unidades del driver.
+pub fn read_raw(device: &BacklightDevice) -> Result<(u32, u32)> {
+    if device.kind == BrightnessKind::Output {
+        return read_output(device);
+    }
 
-    let max: u32 = fs::read_to_string(&max_brightness_file)
-        .map_err(VasakError::Io)?
-        .trim()
-        .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse max brightness: {}", e)))?;
+    match get_brightness_method()? {
+        BrightnessMethod::Brightnessctl => read_brightnessctl(device),
+        BrightnessMethod::Logind | BrightnessMethod::Sysfs => read_sysfs(device),
+    }
+}
 
-    let absolute_value = ((brightness as f32 / 100.0) * max as f32) as u32;
+/// Establece el nivel usando brightnessctl
+fn write_brightnessctl(device: &BacklightDevice, value: u32) -> Result<()> {
+    CommandExecutor::run(CMD_BRIGHTNESSCTL, &[
+        "-d",
+        &device.name,
+        "-c",
+        device.kind.class(),
+        "set",
+        &value.to_string(),
+    ])?;
+    Ok(())
+}
 
+/// Establece el nivel usando logind D-Bus (busctl)
+fn write_logind(device: &BacklightDevice, value: u32) -> Result<()> {
     CommandExecutor::run(CMD_BUSCTL, &[
         "call",
         "org.freedesktop.login1",
@@ -190,28 +611,17 @@ fn set_brightness_logind(device_path: &Path, brightness: u32) -> Result<()> {
         "org.freedesktop.login1.Session",
         "SetBrightness",
         "ssu",
-        "backlight",
-        device_name,
-        &absolute_value.to_string(),
+        device.kind.class(),
+        &device.name,
+        &value.to_string(),
     ])?;
 
     Ok(())
 }
 
-/// Establece el brillo usando sysfs
-fn set_brightness_sysfs(device_path: &Path, brightness: u32) -> Result<()> {
-    let brightness_file = device_path.join("brightness");
-    let max_brightness_file = device_path.join("max_brightness");
-
-    let max: u32 = fs::read_to_string(&max_brightness_file)
-        .map_err(VasakError::Io)?
-        .trim()
-        .parse()
-        .map_err(|e| VasakError::Parse(format!("Failed to parse max brightness: {}", e)))?;
-
-    let absolute_value = ((brightness as f32 / 100.0) * max as f32) as u32;
-
-    fs::write(&brightness_file, absolute_value.to_string())
+/// Establece el nivel usando sysfs
+fn write_sysfs(device: &BacklightDevice, value: u32) -> Result<()> {
+    fs::write(device.brightness_path(), value.to_string())
         .map_err(|e| VasakError::Brightness(
             format!("Cannot write brightness (check permissions): {}", e)
         ))?;
@@ -219,21 +629,241 @@ fn set_brightness_sysfs(device_path: &Path, brightness: u32) -> Result<()> {
     Ok(())
 }
 
-/// Establece el brillo del sistema
-pub fn set_brightness(brightness: u32) -> Result<()> {
-    log_debug(&format!("Estableciendo brillo del sistema a: {}%", brightness));
-    let method = get_brightness_method()?;
-
-    let result = match method {
-        BrightnessMethod::Brightnessctl => set_brightness_brightnessctl(brightness),
-        BrightnessMethod::Logind(ref device_path) => set_brightness_logind(device_path, brightness),
-        BrightnessMethod::Sysfs(ref device_path) => set_brightness_sysfs(device_path, brightness),
+/// Establece el nivel del dispositivo en unidades del driver.
+pub fn write_raw(device: &BacklightDevice, value: u32) -> Result<()> {
+    let result = match device.kind {
+        BrightnessKind::Output => write_output(device, value),
+        _ => {
+            // Antes de escribir: el evento de inotify puede llegar antes de
+            // que vuelva la escritura.
+            if let Ok(mut writes) = SHELL_WRITES.lock() {
+                writes.note(device.id(), value, Instant::now());
+            }
+            write_backlight(device, value)
+        }
     };
 
-    if result.is_ok() {
-        log_debug(&format!("Brillo establecido correctamente a: {}%", brightness));
-    } else if let Err(ref e) = result {
-        log_error(&format!("Error al establecer brillo: {}", e));
+    if let Err(ref e) = result {
+        log_error(&format!("Error al establecer brillo de {}: {}", device.id(), e));
     }
     result
 }
+
+fn write_backlight(device: &BacklightDevice, value: u32) -> Result<()> {
+    match get_brightness_method()? {
+        BrightnessMethod::Brightnessctl => write_brightnessctl(device, value),
+        BrightnessMethod::Logind => write_logind(device, value),
+        BrightnessMethod::Sysfs => write_sysfs(device, value),
+    }
+}
+
+fn info_from(device: &BacklightDevice, current: u32, max: u32) -> BrightnessInfo {
+    BrightnessInfo {
+        current: percent_of(device, current, max),
+        max: 100,
+        min: 0,
+        device: device.id(),
+    }
+}
+
+/// Brillo de un dispositivo en porcentaje
+pub fn get_device_brightness(device: &BacklightDevice) -> Result<BrightnessInfo> {
+    let (current, max) = read_raw(device)?;
+    let info = info_from(device, current, max);
+    log_debug(&format!("Brillo de {}: {}%", info.device, info.current));
+    Ok(info)
+}
+
+/// Establece el brillo de un dispositivo en porcentaje, con una transición
+/// corta en las pantallas.
+pub fn set_device_brightness(device: &BacklightDevice, brightness: u32) -> Result<()> {
+    log_debug(&format!("Estableciendo brillo de {} a: {}%", device.id(), brightness));
+    let (current, max) = read_raw(device)?;
+    let target = raw_of(device, brightness, max);
+    if target == current {
+        return Ok(());
+    }
+    animate(device, current, target)
+}
+
+/// Lista los dispositivos con su brillo actual
+pub fn list_brightness_devices() -> Result<Vec<BrightnessDevice>> {
+    all_devices()
+        .iter()
+        .map(|device| {
+            let (current, max) = read_raw(device)?;
+            Ok(BrightnessDevice {
+                id: device.id(),
+                name: device.name.clone(),
+                kind: device.kind.label().to_string(),
+                current: percent_of(device, current, max),
+                levels: max,
+            })
+        })
+        .collect()
+}
+
+/// Obtiene la información actual del brillo del sistema
+pub fn get_brightness() -> Result<BrightnessInfo> {
+    log_debug("Obteniendo información de brillo del sistema");
+    get_device_brightness(&find_device(None)?)
+}
+
+/// Establece el brillo del sistema. Sin retroiluminación (un equipo de
+/// escritorio) oscurece todos los monitores a la vez.
+pub fn set_brightness(brightness: u32) -> Result<()> {
+    if let Ok(display) = first_of(BrightnessKind::Display) {
+        return set_device_brightness(&display, brightness);
+    }
+
+    let outputs = list_outputs(false);
+    if outputs.is_empty() {
+        return Err(VasakError::NotFound("No valid display brightness device found".to_string()));
+    }
+    for output in &outputs {
+        set_device_brightness(output, brightness)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_device(root: &Path, class: &str, name: &str, kind_type: Option<&str>) {
+        let dir = root.join(class).join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("brightness"), "3\n").unwrap();
+        fs::write(dir.join("max_brightness"), "10\n").unwrap();
+        if let Some(kind_type) = kind_type {
+            fs::write(dir.join("type"), format!("{}\n", kind_type)).unwrap();
+        }
+    }
+
+    #[test]
+    fn displays_come_first_ordered_by_type_then_keyboards() {
+        let root = std::env::temp_dir().join(format!("vasak-sysfs-{}", std::process::id()));
+        fake_device(&root, "backlight", "acpi_video0", Some("firmware"));
+        fake_device(&root, "backlight", "intel_backlight", Some("raw"));
+        fake_device(&root, "backlight", "amdgpu_bl1", Some("raw"));
+        fake_device(&root, "leds", "tpacpi::kbd_backlight", None);
+        fake_device(&root, "leds", "input3::capslock", None);
+        // Sin max_brightness no es un dispositivo usable
+        fs::create_dir_all(root.join("backlight").join("broken")).unwrap();
+
+        let ids: Vec<String> = scan_devices(&root).iter().map(BacklightDevice::id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "backlight/acpi_video0",
+                "backlight/amdgpu_bl1",
+                "backlight/intel_backlight",
+                "leds/tpacpi::kbd_backlight",
+            ]
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    /// Los teclados suelen tener dos o tres niveles: redondear y no truncar,
+    /// o el 33 % queda apagado.
+    #[test]
+    fn few_level_devices_round_to_the_nearest_level() {
+        assert_eq!(to_raw(33, 2), 1);
+        assert_eq!(to_raw(100, 2), 2);
+        assert_eq!(to_raw(150, 2), 2);
+        assert_eq!(to_percent(1, 2), 50);
+        assert_eq!(to_percent(1, 3), 33);
+        assert_eq!(to_percent(5, 0), 0);
+    }
+
+    #[test]
+    fn the_curve_keeps_a_minimum_and_spreads_the_low_end() {
+        let curve = BrightnessCurve { exponent: 2.0, min_percent: 5 };
+        assert_eq!(curve.to_raw(0, 1000), 50);
+        assert_eq!(curve.to_raw(100, 1000), 1000);
+        // La mitad del control está bastante por debajo de la mitad del panel.
+        assert!(curve.to_raw(50, 1000) < 350);
+
+        for percent in [0, 10, 25, 50, 75, 100] {
+            assert_eq!(curve.to_percent(curve.to_raw(percent, 1000), 1000), percent);
+        }
+        // Un nivel por debajo del mínimo (de antes de la curva) se ve como 0.
+        assert_eq!(curve.to_percent(10, 1000), 0);
+    }
+
+    #[test]
+    fn even_a_zero_minimum_never_writes_zero() {
+        let curve = BrightnessCurve { exponent: 1.0, min_percent: 0 };
+        assert_eq!(curve.to_raw(0, 255), 1);
+        assert_eq!(curve.to_raw(100, 255), 255);
+    }
+
+    #[test]
+    fn transitions_ease_into_the_target() {
+        let steps = transition_steps(100, 900, Duration::from_millis(200));
+        assert_eq!(steps.len(), 12);
+        assert_eq!(steps.last(), Some(&900));
+        assert!(steps.windows(2).all(|w| w[0] < w[1]));
+        // Los primeros pasos son los más largos.
+        assert!(steps[1] - steps[0] > steps[11] - steps[10]);
+
+        // En un panel de pocos niveles no hay pasos repetidos.
+        assert_eq!(transition_steps(3, 1, Duration::from_millis(200)), vec![2, 1]);
+    }
+
+    #[test]
+    fn only_external_changes_are_left_for_the_osd() {
+        let mut writes = ShellWrites::default();
+        let start = Instant::now();
+        let id = "backlight/intel_backlight";
+
+        assert!(!writes.claim(id, 400, start), "nothing written yet");
+
+        // Una transición: los pasos intermedios y el final son del shell.
+        writes.note(id.to_string(), 420, start);
+        writes.note(id.to_string(), 450, start + Duration::from_millis(16));
+        assert!(writes.claim(id, 420, start + Duration::from_millis(20)));
+        assert!(writes.claim(id, 450, start + Duration::from_millis(40)));
+
+        // Llegado el final, lo que sigue es de afuera.
+        assert!(!writes.claim(id, 300, start + Duration::from_millis(60)));
+
+        // Lo que se lee pasada la ventana, también.
+        writes.note(id.to_string(), 500, start);
+        assert!(!writes.claim(id, 200, start + Duration::from_secs(5)));
+        assert!(!writes.claim(id, 500, start + Duration::from_secs(6)));
+        assert!(!writes.claim("leds/tpacpi::kbd_backlight", 500, start));
+    }
+
+    #[test]
+    fn output_brightness_is_saved_once_the_slider_rests() {
+        let mut unsaved = UnsavedOutputs::default();
+        let start = Instant::now();
+        assert_eq!(unsaved.take_if_idle(start), None);
+
+        unsaved.values.insert("DP-1".to_string(), 40);
+        unsaved.last_write = Some(start);
+        unsaved.values.insert("DP-1".to_string(), 35);
+        unsaved.last_write = Some(start + Duration::from_millis(300));
+
+        assert_eq!(unsaved.take_if_idle(start + Duration::from_millis(1000)), None);
+        let saved = unsaved.take_if_idle(start + Duration::from_millis(1300)).unwrap();
+        assert_eq!(saved.get("DP-1"), Some(&35));
+        assert_eq!(unsaved.take_if_idle(start + Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn only_a_newer_write_to_the_same_device_cuts_a_fade() {
+        let mut transitions = Transitions::default();
+        let display = transitions.start("backlight/intel_backlight");
+
+        // La luz del teclado y otra pantalla tienen su propia transición.
+        transitions.start("leds/tpacpi::kbd_backlight");
+        transitions.start("backlight/acpi_video0");
+        assert!(transitions.current("backlight/intel_backlight", display));
+
+        transitions.start("backlight/intel_backlight");
+        assert!(!transitions.current("backlight/intel_backlight", display));
+    }
+}
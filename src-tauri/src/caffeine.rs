@@ -0,0 +1,300 @@
+//! Modo cafeína: mantener el equipo despierto.
+//!
+//! Mientras está activo se tienen dos bloqueos: uno de logind
+//! (`Inhibit("idle:sleep", ...)`), que frena la suspensión automática y a los
+//! gestores de inactividad que lo consultan, y el inhibidor de inactividad de
+//! Wayland, para el compositor. Se activa a mano, sin plazo, por unos minutos
+//! o hasta que se desenchufe el cargador; y, si así se configuró, solo,
+//! mientras hay una ventana a pantalla completa enfocada o un reproductor
+//! reproduciendo.
+
+use crate::error::{Result, VasakError};
+use crate::idle_inhibit::IdleInhibitor;
+use crate::logger::log_warning;
+use crate::settings::CaffeineSettings;
+use crate::structs::{BatteryInfo, CaffeineState};
+use serde::Deserialize;
+use std::os::fd::OwnedFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use zbus::Connection;
+
+/// Cuánto dura la cafeína activada a mano.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CaffeineDuration {
+    /// Hasta que se desactive
+    Forever,
+    Minutes { minutes: u32 },
+    /// Hasta que se desconecte el cargador
+    Unplugged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    Manual,
+    Fullscreen,
+    Media,
+}
+
+impl Reason {
+    fn name(self) -> &'static str {
+        match self {
+            Reason::Manual => "manual",
+            Reason::Fullscreen => "fullscreen",
+            Reason::Media => "media",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Manual {
+    until: Option<Instant>,
+    until_unplugged: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Caffeine {
+    manual: Option<Manual>,
+    /// Hay una ventana a pantalla completa con el foco
+    pub(crate) fullscreen: bool,
+    /// Algún reproductor MPRIS está reproduciendo
+    pub(crate) playing: bool,
+}
+
+impl Caffeine {
+    pub(crate) const fn new() -> Self {
+        Self {
+            manual: None,
+            fullscreen: false,
+            playing: false,
+        }
+    }
+
+    pub(crate) fn start(
+        &mut self,
+        duration: CaffeineDuration,
+        now: Instant,
+        on_battery: bool,
+    ) -> Result<()> {
+        self.manual = Some(match duration {
+            CaffeineDuration::Forever => Manual {
+                until: None,
+                until_unplugged: false,
+            },
+            CaffeineDuration::Minutes { minutes } => Manual {
+                until: Some(now + Duration::from_secs(minutes.max(1) as u64 * 60)),
+                until_unplugged: false,
+            },
+            CaffeineDuration::Unplugged => {
+                if on_battery {
+                    return Err(VasakError::InvalidState(
+                        "el cargador no está conectado".to_string(),
+                    ));
+                }
+                Manual {
+                    until: None,
+                    until_unplugged: true,
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.manual = None;
+    }
+
+    /// Termina la activación manual si venció el plazo o se desenchufó el
+    /// cargador. Devuelve si terminó.
+    pub(crate) fn expire(&mut self, now: Instant, on_battery: bool) -> bool {
+        let expired = self.manual.is_some_and(|manual| {
+            manual.until.is_some_and(|until| now >= until) || (manual.until_unplugged && on_battery)
+        });
+        if expired {
+            self.manual = None;
+        }
+        expired
+    }
+
+    pub(crate) fn reason(&self, settings: &CaffeineSettings) -> Option<Reason> {
+        if self.manual.is_some() {
+            Some(Reason::Manual)
+        } else if settings.auto_fullscreen && self.fullscreen {
+            Some(Reason::Fullscreen)
+        } else if settings.auto_media && self.playing {
+            Some(Reason::Media)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn state(&self, settings: &CaffeineSettings, now: Instant) -> CaffeineState {
+        let reason = self.reason(settings);
+        CaffeineState {
+            active: reason.is_some(),
+            reason: reason.map(|r| r.name().to_string()),
+            manual: self.manual.is_some(),
+            remaining: self
+                .manual
+                .and_then(|manual| manual.until)
+                .map(|until| until.saturating_duration_since(now).as_secs_f64().ceil() as u64),
+            until_unplugged: self.manual.is_some_and(|manual| manual.until_unplugged),
+        }
+    }
+}
+
+static CAFFEINE: Mutex<Caffeine> = Mutex::new(Caffeine::new());
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn with_caffeine<T>(f: impl FnOnce(&mut Caffeine) -> T) -> Result<T> {
+    let mut caffeine = CAFFEINE
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("caffeine"))?;
+    Ok(f(&mut caffeine))
+}
+
+pub(crate) fn on_battery() -> bool {
+    ON_BATTERY.load(Ordering::Relaxed)
+}
+
+/// Despierta al applet para que aplique un cambio ya.
+pub(crate) fn changed() -> &'static Notify {
+    static CHANGED: OnceLock<Notify> = OnceLock::new();
+    CHANGED.get_or_init(Notify::new)
+}
+
+/// Recuerda si el equipo está a batería, para "hasta desenchufar".
+pub fn observe_battery(info: &BatteryInfo) {
+    let now = info.has_battery && crate::battery_warnings::on_battery(info);
+    if ON_BATTERY.swap(now, Ordering::Relaxed) != now {
+        changed().notify_one();
+    }
+}
+
+pub fn start(duration: CaffeineDuration) -> Result<()> {
+    with_caffeine(|caffeine| caffeine.start(duration, Instant::now(), on_battery()))??;
+    changed().notify_one();
+    Ok(())
+}
+
+pub fn stop() -> Result<()> {
+    with_caffeine(Caffeine::stop)?;
+    changed().notify_one();
+    Ok(())
+}
+
+pub fn state(settings: &CaffeineSettings) -> CaffeineState {
+    with_caffeine(|caffeine| caffeine.state(settings, Instant::now())).unwrap_or(CaffeineState {
+        active: false,
+        reason: None,
+        manual: false,
+        remaining: None,
+        until_unplugged: false,
+    })
+}
+
+/// Los bloqueos que mantienen el equipo despierto; se sueltan al descartarlos.
+pub(crate) struct Locks {
+    _logind: Option<OwnedFd>,
+    _idle: Option<IdleInhibitor>,
+}
+
+impl Locks {
+    pub(crate) async fn acquire(system: Option<&Connection>) -> Self {
+        let logind = match system {
+            Some(connection) => match inhibit_logind(connection).await {
+                Ok(fd) => Some(fd),
+                Err(e) => {
+                    log_warning(&format!("Cafeína: logind no dio el bloqueo: {}", e));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let idle = match tokio::task::spawn_blocking(IdleInhibitor::new).await {
+            Ok(Ok(inhibitor)) => Some(inhibitor),
+            Ok(Err(e)) => {
+                log_warning(&format!("Cafeína: sin inhibidor de Wayland: {}", e));
+                None
+            }
+            Err(_) => None,
+        };
+
+        Self {
+            _logind: logind,
+            _idle: idle,
+        }
+    }
+}
+
+async fn inhibit_logind(connection: &Connection) -> Result<OwnedFd> {
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1",
+            Some("org.freedesktop.login1.Manager"),
+            "Inhibit",
+            &("idle:sleep", "Vasak", "Modo cafeína", "block"),
+        )
+        .await?;
+    let fd: zbus::zvariant::OwnedFd = reply.body().deserialize()?;
+    Ok(fd.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_counts_down_and_expires() {
+        let settings = CaffeineSettings::default();
+        let start = Instant::now();
+        let mut caffeine = Caffeine::new();
+        assert_eq!(caffeine.reason(&settings), None);
+
+        caffeine
+            .start(CaffeineDuration::Minutes { minutes: 30 }, start, false)
+            .unwrap();
+        let state = caffeine.state(&settings, start + Duration::from_secs(61));
+        assert!(state.active && state.manual);
+        assert_eq!(state.remaining, Some(29 * 60 - 1));
+
+        assert!(!caffeine.expire(start + Duration::from_secs(29 * 60), false));
+        assert!(caffeine.expire(start + Duration::from_secs(30 * 60), false));
+        assert!(!caffeine.state(&settings, start).active);
+    }
+
+    #[test]
+    fn until_unplugged_needs_the_charger() {
+        let now = Instant::now();
+        let mut caffeine = Caffeine::new();
+        assert!(caffeine.start(CaffeineDuration::Unplugged, now, true).is_err());
+
+        caffeine.start(CaffeineDuration::Unplugged, now, false).unwrap();
+        assert!(!caffeine.expire(now + Duration::from_secs(86_400), false));
+        assert!(caffeine.expire(now, true));
+    }
+
+    #[test]
+    fn automatic_reasons_follow_settings() {
+        let mut settings = CaffeineSettings::default();
+        let mut caffeine = Caffeine::new();
+        caffeine.fullscreen = true;
+        caffeine.playing = true;
+        assert_eq!(caffeine.reason(&settings), None);
+
+        settings.auto_media = true;
+        assert_eq!(caffeine.reason(&settings), Some(Reason::Media));
+        settings.auto_fullscreen = true;
+        assert_eq!(caffeine.reason(&settings), Some(Reason::Fullscreen));
+
+        caffeine.start(CaffeineDuration::Forever, Instant::now(), true).unwrap();
+        assert_eq!(caffeine.reason(&settings), Some(Reason::Manual));
+        caffeine.stop();
+        assert_eq!(caffeine.reason(&settings), Some(Reason::Fullscreen));
+    }
+}
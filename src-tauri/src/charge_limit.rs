@@ -0,0 +1,225 @@
+//! Límite de carga de la batería.
+//!
+//! Muchas notebooks pueden dejar de cargar antes del 100 % con
+//! `charge_control_end_threshold` (y volver a cargar por debajo de
+//! `charge_control_start_threshold`) en `/sys/class/power_supply/BAT*`. A
+//! quien la tiene enchufada todo el día, cortar en 80 % le alarga la vida a la
+//! batería.
+//!
+//! Si UPower sabe manejar los umbrales de esta batería (`ChargeThresholdSupported`,
+//! desde la 1.90) se le pide a él, que usa los valores de su configuración. Si
+//! no, se escriben en sysfs con un ayudante que corre como root a través de
+//! pkexec; la política de polkit lo permite sin contraseña en la sesión activa.
+
+use crate::constants::{CHARGE_THRESHOLD_HELPER, CMD_PKEXEC, SYS_CLASS_PATH};
+use crate::error::{Result, VasakError};
+use crate::logger::{log_info, log_warning};
+use crate::utils::CommandExecutor;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zbus::zvariant::OwnedValue;
+use zbus::Connection;
+
+/// Distancia entre el fin y el inicio: sin ella, la batería cargaría de a un
+/// punto cada pocos minutos.
+const START_GAP: u32 = 5;
+
+/// Por debajo de esto el límite deja de tener sentido.
+const MIN_LIMIT: u32 = 50;
+
+/// pkexec puede esperar a que el agente de polkit pida contraseña.
+const HELPER_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    pub supported: bool,
+    pub enabled: bool,
+    pub start: Option<u32>,
+    pub end: Option<u32>,
+}
+
+fn read_percent(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Umbrales de la batería en `dir` (un directorio de power_supply).
+pub(crate) fn read_sysfs(dir: &Path) -> Thresholds {
+    let end = read_percent(&dir.join("charge_control_end_threshold"));
+    Thresholds {
+        supported: end.is_some(),
+        enabled: end.is_some_and(|end| end < 100),
+        start: read_percent(&dir.join("charge_control_start_threshold")),
+        end,
+    }
+}
+
+/// La primera batería que permite limitar la carga.
+pub(crate) fn battery_dir(power_supply: &Path) -> Option<PathBuf> {
+    let mut batteries: Vec<PathBuf> = fs::read_dir(power_supply)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        .map(|entry| entry.path())
+        .filter(|path| path.join("charge_control_end_threshold").exists())
+        .collect();
+    batteries.sort();
+    batteries.into_iter().next()
+}
+
+fn power_supply() -> PathBuf {
+    Path::new(SYS_CLASS_PATH).join("power_supply")
+}
+
+/// Umbrales leídos de sysfs, para cuando UPower no los informa.
+pub fn sysfs_thresholds() -> Thresholds {
+    battery_dir(&power_supply())
+        .map(|dir| read_sysfs(&dir))
+        .unwrap_or_default()
+}
+
+/// El límite que de verdad se puede aplicar.
+pub(crate) fn clamp_limit(limit: u32) -> u32 {
+    limit.clamp(MIN_LIMIT, 100)
+}
+
+/// Inicio y fin para un límite de `limit` %, o los de "cargar siempre".
+pub(crate) fn thresholds_for(enabled: bool, limit: u32) -> (u32, u32) {
+    if !enabled {
+        return (0, 100);
+    }
+    let end = clamp_limit(limit);
+    (end.saturating_sub(START_GAP), end)
+}
+
+async fn upower_property(connection: &Connection, path: &str, name: &str) -> Option<OwnedValue> {
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.UPower"),
+            path,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &("org.freedesktop.UPower.Device", name),
+        )
+        .await;
+    reply
+        .ok()
+        .and_then(|reply| reply.body().deserialize::<OwnedValue>().ok())
+}
+
+async fn upower_supported(connection: &Connection, path: &str) -> bool {
+    upower_property(connection, path, "ChargeThresholdSupported")
+        .await
+        .and_then(|value| bool::try_from(value).ok())
+        .unwrap_or(false)
+}
+
+/// Activa o desactiva el límite de carga en `limit` %. Devuelve el límite que
+/// quedó: UPower usa los umbrales de su configuración, no `limit`.
+pub async fn set_limit(
+    connection: Option<&Connection>,
+    upower_path: Option<&str>,
+    enabled: bool,
+    limit: u32,
+) -> Result<u32> {
+    let limit = clamp_limit(limit);
+    if let (Some(connection), Some(path)) = (connection, upower_path) {
+        if upower_supported(connection, path).await {
+            connection
+                .call_method(
+                    Some("org.freedesktop.UPower"),
+                    path,
+                    Some("org.freedesktop.UPower.Device"),
+                    "EnableChargeThreshold",
+                    &(enabled,),
+                )
+                .await?;
+            let end = upower_property(connection, path, "ChargeEndThreshold")
+                .await
+                .and_then(|value| u32::try_from(value).ok())
+                .filter(|end| (1..=100).contains(end))
+                .unwrap_or(limit);
+            if enabled && end != limit {
+                log_warning(&format!(
+                    "UPower limita la carga en {}%, no en {}%",
+                    end, limit
+                ));
+            }
+            log_info(&format!("Límite de carga vía UPower: {} ({}%)", enabled, end));
+            return Ok(end);
+        }
+    }
+
+    let dir = battery_dir(&power_supply())
+        .ok_or_else(|| VasakError::NotFound("batería con límite de carga".to_string()))?;
+    let battery = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| VasakError::InvalidState(format!("ruta inválida {}", dir.display())))?;
+
+    let (start, end) = thresholds_for(enabled, limit);
+    let start = if dir.join("charge_control_start_threshold").exists() {
+        start.to_string()
+    } else {
+        "-".to_string()
+    };
+    let end = end.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        CommandExecutor::run_with_timeout(
+            CMD_PKEXEC,
+            &[CHARGE_THRESHOLD_HELPER, &battery, &start, &end],
+            HELPER_TIMEOUT,
+        )
+    })
+    .await
+    .map_err(|e| VasakError::Command(e.to_string()))??;
+
+    log_info(&format!("Límite de carga en sysfs: {} ({}%)", enabled, limit));
+    Ok(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_are_read_from_sysfs() {
+        let root = std::env::temp_dir().join(format!("vasak-charge-{}", std::process::id()));
+        let bat = root.join("BAT1");
+        fs::create_dir_all(&bat).unwrap();
+        fs::create_dir_all(root.join("AC")).unwrap();
+        fs::create_dir_all(root.join("BAT0")).unwrap();
+
+        // BAT0 no permite limitar la carga: se elige BAT1.
+        assert_eq!(battery_dir(&root), None);
+        fs::write(bat.join("charge_control_end_threshold"), "80\n").unwrap();
+        fs::write(bat.join("charge_control_start_threshold"), "75\n").unwrap();
+        assert_eq!(battery_dir(&root), Some(bat.clone()));
+
+        assert_eq!(
+            read_sysfs(&bat),
+            Thresholds {
+                supported: true,
+                enabled: true,
+                start: Some(75),
+                end: Some(80),
+            }
+        );
+        fs::write(bat.join("charge_control_end_threshold"), "100\n").unwrap();
+        assert!(!read_sysfs(&bat).enabled);
+        assert!(!read_sysfs(&root.join("BAT0")).supported);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn limits_leave_room_to_recharge() {
+        assert_eq!(thresholds_for(true, 80), (75, 80));
+        assert_eq!(thresholds_for(true, 20), (45, 50));
+        assert_eq!(thresholds_for(true, 120), (95, 100));
+        assert_eq!(thresholds_for(false, 80), (0, 100));
+        assert_eq!(clamp_limit(20), 50);
+        assert_eq!(clamp_limit(85), 85);
+    }
+}
@@ -1,8 +1,15 @@
 use tauri::AppHandle;
 
-use crate::audio::{get_volume, set_volume, toggle_mute, list_audio_devices, set_default_audio_device};
+use crate::audio::{
+    get_mic_volume, get_volume, list_audio_cards, list_audio_devices, list_audio_sources,
+    set_default_audio_device, set_default_audio_source, set_mic_volume, set_volume, toggle_mic_mute,
+    toggle_mute,
+};
+use crate::audio_backend::DeviceKind;
+use crate::audio_effects;
 use crate::logger::{log_info, log_error, log_debug};
-use crate::structs::{VolumeInfo, AudioDevice};
+use crate::mic_level;
+use crate::structs::{AudioCard, AudioDevice, VolumeInfo};
 use crate::windows_apps::applets::create_applet_audio_window;
 use tauri::{async_runtime::spawn, Manager, Emitter};
 
@@ -10,6 +17,8 @@ use tauri::{async_runtime::spawn, Manager, Emitter};
 // Each one shells out to pactl through CommandExecutor, which spawns a thread
 // and blocks the caller on it for up to three seconds — done on the main thread
 // that stalls the panel, the clock and every animation while it waits.
+// get_audio_volume is the exception: it answers from the audio monitor's
+// cached state and only shells out before the monitor has started.
 #[tauri::command]
 pub async fn get_audio_volume() -> Result<VolumeInfo, String> {
     log_debug("Comando: get_audio_volume");
@@ -56,6 +65,117 @@ pub async fn set_audio_device(device_id: String, app: AppHandle) -> Result<bool,
         })
 }
 
+#[tauri::command]
+pub async fn get_audio_sources() -> Result<Vec<AudioDevice>, String> {
+    list_audio_sources().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_audio_source(device_id: String, app: AppHandle) -> Result<bool, String> {
+    log_info(&format!("Cambiando entrada de audio a: {}", device_id));
+    set_default_audio_source(&device_id, app)
+        .map(|_| true)
+        .map_err(|e| {
+            log_error(&format!("Error al cambiar entrada de audio: {}", e));
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_audio_source_volume() -> Result<VolumeInfo, String> {
+    log_debug("Comando: get_audio_source_volume");
+    get_mic_volume().map_err(|e| {
+        log_error(&format!("Error al obtener volumen del micrófono: {}", e));
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn set_audio_source_volume(volume: i64, app: AppHandle) -> Result<(), String> {
+    set_mic_volume(volume, app).map_err(|e| {
+        log_error(&format!("Error al establecer volumen del micrófono: {}", e));
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn toggle_audio_source_mute(app: AppHandle) -> Result<bool, String> {
+    toggle_mic_mute(app).map_err(|e| {
+        log_error(&format!("Error al alternar silencio del micrófono: {}", e));
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn get_audio_cards() -> Result<Vec<AudioCard>, String> {
+    list_audio_cards().map_err(|e| e.to_string())
+}
+
+/// `is_input` elige entre los puertos de una entrada o de una salida; por
+/// defecto, salida.
+#[tauri::command]
+pub async fn set_audio_port(
+    device_id: String,
+    port: String,
+    is_input: Option<bool>,
+    app: AppHandle,
+) -> Result<bool, String> {
+    let kind = if is_input.unwrap_or(false) {
+        DeviceKind::Source
+    } else {
+        DeviceKind::Sink
+    };
+
+    crate::audio::set_audio_port(kind, &device_id, &port, app)
+        .map(|_| true)
+        .map_err(|e| {
+            log_error(&format!("Error al cambiar puerto de audio: {}", e));
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn set_audio_card_profile(
+    card_id: String,
+    profile: String,
+    app: AppHandle,
+) -> Result<bool, String> {
+    crate::audio::set_audio_card_profile(&card_id, &profile, app)
+        .map(|_| true)
+        .map_err(|e| {
+            log_error(&format!("Error al cambiar perfil de tarjeta: {}", e));
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_audio_noise_suppression() -> Result<bool, String> {
+    tokio::task::spawn_blocking(audio_effects::is_noise_suppression_enabled)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Tarda hasta unos segundos: espera a que aparezca la entrada virtual para
+/// dejarla por defecto.
+#[tauri::command]
+pub async fn set_audio_noise_suppression(enabled: bool, app: AppHandle) -> Result<bool, String> {
+    let handle = app.clone();
+    tokio::task::spawn_blocking(move || audio_effects::set_noise_suppression(&handle, enabled))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log_error(&format!("Error al cambiar la supresión de ruido: {}", e));
+            e.to_string()
+        })?;
+
+    let _ = app.emit("noise-suppression-changed", enabled);
+    if let Ok(sources) = list_audio_sources() {
+        let _ = app.emit(DeviceKind::Source.devices_event(), &sources);
+    }
+    Ok(enabled)
+}
+
 #[tauri::command]
 pub fn toggle_audio_applet(app: AppHandle) -> Result<(), ()> {
     log_debug("Alternando applet de audio");
@@ -65,12 +185,15 @@ pub fn toggle_audio_applet(app: AppHandle) -> Result<(), ()> {
             // reloads the page and re-runs Vue. The view refreshes on
             // "window-shown" instead.
             let _ = audio_window.hide();
+            mic_level::unwatch(mic_level::WATCHER_AUDIO_APPLET);
         } else {
             let _ = audio_window.emit("window-shown", ());
             let _ = audio_window.show();
             let _ = audio_window.set_focus();
+            mic_level::watch(&app, mic_level::WATCHER_AUDIO_APPLET);
         }
     } else {
+        mic_level::watch(&app, mic_level::WATCHER_AUDIO_APPLET);
         spawn(async move {
             let _ = create_applet_audio_window(app).await;
         });
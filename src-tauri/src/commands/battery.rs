@@ -1,6 +1,11 @@
-use crate::structs::BatteryInfo;
-use crate::applets::battery::{has_battery, get_battery_info as get_battery_info_internal};
-use crate::logger::{log_debug, log_info};
+use crate::battery_history::{self, HistorySpan};
+use crate::structs::{BatteryHistory, BatteryInfo, PeripheralBattery};
+use crate::applets::battery::{has_battery, get_battery_info as get_battery_info_internal, peripheral_batteries, battery_device_path, forget_sysfs_thresholds};
+use crate::charge_limit;
+use crate::logger::{log_debug, log_error, log_info};
+use crate::session_actions::system_bus;
+use crate::settings;
+use tauri::{AppHandle, Emitter};
 
 #[tauri::command]
 pub async fn battery_exists() -> bool {
@@ -26,4 +31,66 @@ pub async fn battery_fetch_info() -> Option<BatteryInfo> {
 #[tauri::command]
 pub async fn get_battery_info() -> Option<BatteryInfo> {
     battery_fetch_info().await
-}
\ No newline at end of file
+}
+
+/// Mice, keyboards, headsets, phones and UPSes that report a battery to UPower.
+#[tauri::command]
+pub async fn get_peripheral_batteries() -> Vec<PeripheralBattery> {
+    peripheral_batteries()
+}
+
+/// Cancela la suspensión, hibernación o apagado por batería crítica mientras
+/// corre la cuenta regresiva. Devuelve si había una acción pendiente.
+#[tauri::command]
+pub async fn cancel_critical_battery_action(app: AppHandle) -> bool {
+    crate::battery_warnings::cancel(&app)
+}
+
+/// Historial de las últimas 24 horas ("day") o 7 días ("week") y la salud de
+/// la batería a lo largo de las sesiones.
+#[tauri::command]
+pub async fn get_battery_history(span: HistorySpan, app: AppHandle) -> BatteryHistory {
+    let connection = system_bus(&app).await.ok();
+    battery_history::history(connection.as_ref(), span).await
+}
+
+/// Activa o desactiva el límite de carga en `limit` % (o en el guardado). Se
+/// guarda el límite que quedó aplicado: el camino de sysfs no baja de 50 % y
+/// UPower usa los umbrales de su configuración.
+#[tauri::command]
+pub async fn set_charge_limit(
+    enabled: bool,
+    limit: Option<u32>,
+    app: AppHandle,
+) -> Result<Option<BatteryInfo>, String> {
+    let limit = limit.unwrap_or_else(|| settings::get().battery.charge_limit);
+    let limit = charge_limit::clamp_limit(limit);
+    log_info(&format!("Límite de carga: {} ({}%)", enabled, limit));
+
+    let connection = system_bus(&app).await.ok();
+    let applied = charge_limit::set_limit(
+        connection.as_ref(),
+        battery_device_path().as_deref(),
+        enabled,
+        limit,
+    )
+    .await
+    .map_err(|e| {
+        log_error(&format!("Error al cambiar el límite de carga: {}", e));
+        e.to_string()
+    })?;
+    if applied != settings::get().battery.charge_limit {
+        let saved = settings::update(|s| s.battery.charge_limit = applied).map_err(|e| {
+            log_error(&format!("Error al guardar el límite de carga: {}", e));
+            e.to_string()
+        })?;
+        let _ = app.emit("shell-settings-changed", &saved);
+    }
+    forget_sysfs_thresholds();
+
+    let info = get_battery_info_internal().await;
+    if let Some(ref info) = info {
+        let _ = app.emit("battery-update", info);
+    }
+    Ok(info)
+}
@@ -1,23 +1,96 @@
-use crate::brightness::{get_brightness, set_brightness};
+use tauri::{AppHandle, Emitter};
+
+use crate::auto_brightness;
+use crate::brightness::{
+    find_device, get_device_brightness, list_brightness_devices, set_device_brightness,
+    BrightnessKind,
+};
 use crate::logger::{log_info, log_error, log_debug};
-use crate::structs::BrightnessInfo;
+use crate::settings;
+use crate::structs::{AutoBrightnessStatus, BrightnessDevice, BrightnessInfo};
 
 // Async for the same reason as the audio commands: these shell out to
 // brightnessctl, and doing that on the main thread stalls the whole shell.
+
+/// `device` is an id from `get_brightness_devices`; without it, the main display.
 #[tauri::command]
-pub async fn get_brightness_info() -> Result<BrightnessInfo, String> {
+pub async fn get_brightness_info(device: Option<String>) -> Result<BrightnessInfo, String> {
     log_debug("Comando: get_brightness_info");
-    get_brightness().map_err(|e| {
-        log_error(&format!("Error al obtener información de brillo: {}", e));
-        e.to_string()
-    })
+    find_device(device.as_deref())
+        .and_then(|device| get_device_brightness(&device))
+        .map_err(|e| {
+            log_error(&format!("Error al obtener información de brillo: {}", e));
+            e.to_string()
+        })
 }
 
+/// `device` can also be a monitor without a backlight (`output/DP-1`),
+/// which is dimmed in software.
 #[tauri::command]
-pub async fn set_brightness_info(brightness: u32) -> Result<(), String> {
+pub async fn set_brightness_info(
+    brightness: u32,
+    device: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
     log_info(&format!("Estableciendo brillo a: {}%", brightness));
-    set_brightness(brightness).map_err(|e| {
+    let device = find_device(device.as_deref()).map_err(|e| {
         log_error(&format!("Error al establecer brillo: {}", e));
         e.to_string()
+    })?;
+    if device.kind != BrightnessKind::Keyboard {
+        auto_brightness::note_manual_change(brightness);
+    }
+    // Displays fade over a couple hundred milliseconds; keep that off the
+    // async workers.
+    let target = device.clone();
+    tokio::task::spawn_blocking(move || set_device_brightness(&target, brightness))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            log_error(&format!("Error al establecer brillo: {}", e));
+            e.to_string()
+        })?;
+
+    // Nothing in sysfs changes for a software-dimmed output, so the
+    // brightness applet never sees it: announce it here.
+    if device.kind == BrightnessKind::Output {
+        if let Ok(info) = get_device_brightness(&device) {
+            let _ = app.emit(device.kind.changed_event(), info);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_brightness_devices() -> Result<Vec<BrightnessDevice>, String> {
+    list_brightness_devices().map_err(|e| {
+        log_error(&format!("Error al listar dispositivos de brillo: {}", e));
+        e.to_string()
     })
 }
+
+#[tauri::command]
+pub async fn get_auto_brightness() -> Result<AutoBrightnessStatus, String> {
+    let (paused, lux) = auto_brightness::status();
+    Ok(AutoBrightnessStatus {
+        enabled: settings::get().brightness.auto,
+        available: auto_brightness::is_sensor_available(),
+        paused,
+        lux,
+    })
+}
+
+#[tauri::command]
+pub async fn set_auto_brightness(enabled: bool, app: AppHandle) -> Result<AutoBrightnessStatus, String> {
+    log_info(&format!("Brillo automático: {}", enabled));
+    let saved = settings::update(|s| s.brightness.auto = enabled).map_err(|e| {
+        log_error(&format!("Error al guardar el brillo automático: {}", e));
+        e.to_string()
+    })?;
+    auto_brightness::reload();
+
+    let status = get_auto_brightness().await?;
+    let _ = app.emit("shell-settings-changed", &saved);
+    let _ = app.emit("auto-brightness-changed", &status);
+    Ok(status)
+}
@@ -0,0 +1,42 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::caffeine::{self, CaffeineDuration};
+use crate::logger::{log_error, log_info};
+use crate::settings;
+use crate::structs::CaffeineState;
+
+#[tauri::command]
+pub async fn get_caffeine() -> CaffeineState {
+    caffeine::state(&settings::get().caffeine)
+}
+
+/// Activa o desactiva el modo cafeína. Sin `duration` dura hasta que se
+/// desactive; si no, `{ kind: "minutes", minutes: 30 }` o
+/// `{ kind: "unplugged" }`.
+#[tauri::command]
+pub async fn set_caffeine(
+    enabled: bool,
+    duration: Option<CaffeineDuration>,
+    app: AppHandle,
+) -> Result<CaffeineState, String> {
+    log_info(&format!("Modo cafeína: {} {:?}", enabled, duration));
+    let result = if enabled {
+        caffeine::start(duration.unwrap_or(CaffeineDuration::Forever))
+    } else {
+        caffeine::stop()
+    };
+    result.map_err(|e| {
+        log_error(&format!("Error al cambiar el modo cafeína: {}", e));
+        e.to_string()
+    })?;
+
+    let state = get_caffeine().await;
+    let _ = app.emit("caffeine-changed", &state);
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn toggle_caffeine(app: AppHandle) -> Result<CaffeineState, String> {
+    let manual = get_caffeine().await.manual;
+    set_caffeine(!manual, None, app).await
+}
@@ -1,6 +1,7 @@
 use tauri::{AppHandle, Emitter, Manager};
 
 use crate::logger::{log_info, log_warning};
+use crate::mic_level;
 use crate::windows_apps::control_center::CONTROL_CENTER_LABEL;
 use crate::windows_apps::shell_layer::{
     hide_layer_window, layer_window_exists, layer_window_visible, show_layer_window,
@@ -23,6 +24,7 @@ pub fn toggle_control_center(app: AppHandle) -> Result<(), ()> {
     if layer_window_visible(CONTROL_CENTER_LABEL).unwrap_or(false) {
         log_info("[control_center] ocultando");
         hide_layer_window(CONTROL_CENTER_LABEL);
+        mic_level::unwatch(mic_level::WATCHER_CONTROL_CENTER);
         return Ok(());
     }
 
@@ -34,6 +36,7 @@ pub fn toggle_control_center(app: AppHandle) -> Result<(), ()> {
         let _ = webview.emit("window-shown", ());
     }
     show_layer_window(CONTROL_CENTER_LABEL);
+    mic_level::watch(&app, mic_level::WATCHER_CONTROL_CENTER);
 
     Ok(())
 }
@@ -46,5 +49,6 @@ pub fn toggle_control_center(app: AppHandle) -> Result<(), ()> {
 #[tauri::command]
 pub fn hide_control_center() -> Result<(), ()> {
     hide_layer_window(CONTROL_CENTER_LABEL);
+    mic_level::unwatch(mic_level::WATCHER_CONTROL_CENTER);
     Ok(())
 }
@@ -3,6 +3,7 @@ mod batch;
 mod battery;
 mod bluetooth;
 mod brightness;
+mod caffeine;
 mod connect;
 mod control_center;
 mod logger;
@@ -11,23 +12,31 @@ pub mod osd;
 mod panel;
 mod music;
 mod network;
+mod night_light;
+mod power_profiles;
 mod notifications;
 pub mod runner;
 mod search;
 mod search_window;
 mod session;
 mod session_popup;
+mod settings;
 mod tray;
 mod window_manager;
 
 pub use batch::batch_invoke;
 pub use audio::{
-    get_audio_devices, get_audio_volume, set_audio_device, set_audio_volume, toggle_audio_applet,
-    toggle_audio_mute,
+    get_audio_cards, get_audio_devices, get_audio_noise_suppression, get_audio_source_volume,
+    get_audio_sources, get_audio_volume, set_audio_card_profile, set_audio_device,
+    set_audio_noise_suppression, set_audio_port, set_audio_source, set_audio_source_volume,
+    set_audio_volume, toggle_audio_applet, toggle_audio_mute, toggle_audio_source_mute,
 };
-pub use battery::{battery_exists, battery_fetch_info, get_battery_info};
+pub use battery::{battery_exists, battery_fetch_info, get_battery_info, get_peripheral_batteries, cancel_critical_battery_action, get_battery_history, set_charge_limit};
 pub use bluetooth::toggle_bluetooth_applet;
-pub use brightness::{get_brightness_info, set_brightness_info};
+pub use brightness::{
+    get_auto_brightness, get_brightness_devices, get_brightness_info, set_auto_brightness,
+    set_brightness_info,
+};
 pub use connect::toggle_connect_menu;
 pub use control_center::{hide_control_center, toggle_control_center};
 pub use logger::{log_from_frontend, get_log_file_path, read_log_file, get_last_log_lines};
@@ -36,6 +45,10 @@ pub use osd::show_osd;
 pub use panel::show_panel;
 pub use music::{music_next_track, music_now_playing, music_play_pause, music_previous_track};
 pub use network::toggle_network_applet;
+pub use night_light::{get_night_light, set_night_light, toggle_night_light};
+pub(crate) use night_light::night_light_state;
+pub use power_profiles::{get_power_profiles, set_power_profile};
+pub use caffeine::{get_caffeine, set_caffeine, toggle_caffeine};
 pub use notifications::{
     clear_notifications, delete_notification, get_all_notifications, invoke_notification_action,
     send_notify,
@@ -43,8 +56,15 @@ pub use notifications::{
 pub use runner::{open_app, open_settings};
 pub use search::{execute_search_result, global_search};
 pub use search_window::toggle_search;
-pub use session::{detect_display_server, logout, reboot, shutdown, suspend};
+pub use session::{
+    activate_session, cancel_scheduled_action, cancel_shutdown, detect_display_server, get_boot_entries,
+    get_scheduled_action, get_session_capabilities, get_session_user, hibernate, hybrid_sleep, lock, logout,
+    reboot, reboot_to_boot_entry, reboot_to_firmware_setup, request_session_action, schedule_session_action,
+    shutdown, suspend, suspend_then_hibernate, switch_user,
+};
+pub(crate) use session_popup::show_session_popup;
 pub use session_popup::toggle_session_popup;
+pub use settings::{get_shell_settings, set_shell_settings};
 pub use tray::{
     get_tray_items, get_tray_menu, get_tray_popup_data, init_sni_watcher, open_tray_popup,
     tray_item_activate, tray_item_secondary_activate, tray_menu_item_click, tray_popup_click,
@@ -0,0 +1,50 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::logger::{log_error, log_info};
+use crate::night_light::{self, DAYLIGHT};
+use crate::settings;
+use crate::structs::NightLightState;
+
+pub(crate) fn night_light_state() -> NightLightState {
+    let temperature = night_light::current_temperature();
+    NightLightState {
+        enabled: settings::get().night_light.enabled,
+        active: temperature < DAYLIGHT,
+        temperature,
+        available: crate::gamma::service().is_some(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_night_light() -> NightLightState {
+    tokio::task::spawn_blocking(night_light_state)
+        .await
+        .unwrap_or_else(|_| NightLightState {
+            enabled: false,
+            active: false,
+            temperature: DAYLIGHT,
+            available: false,
+        })
+}
+
+#[tauri::command]
+pub async fn set_night_light(enabled: bool, app: AppHandle) -> Result<NightLightState, String> {
+    log_info(&format!("Luz nocturna: {}", enabled));
+    let saved = settings::update(|s| s.night_light.enabled = enabled).map_err(|e| {
+        log_error(&format!("Error al guardar la luz nocturna: {}", e));
+        e.to_string()
+    })?;
+    night_light::reload();
+
+    // La temperatura cambia de a poco; el applet avisa cuando termina.
+    let state = get_night_light().await;
+    let _ = app.emit("shell-settings-changed", &saved);
+    let _ = app.emit("night-light-changed", &state);
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn toggle_night_light(app: AppHandle) -> Result<NightLightState, String> {
+    let enabled = settings::get().night_light.enabled;
+    set_night_light(!enabled, app).await
+}
@@ -0,0 +1,39 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::logger::{log_error, log_info};
+use crate::power_profiles;
+use crate::session_actions::system_bus;
+use crate::structs::PowerProfilesState;
+
+#[tauri::command]
+pub async fn get_power_profiles(app: AppHandle) -> PowerProfilesState {
+    match system_bus(&app).await {
+        Ok(connection) => power_profiles::state(&connection).await,
+        Err(_) => PowerProfilesState {
+            available: false,
+            active: None,
+            profiles: Vec::new(),
+            degraded: None,
+            holds: Vec::new(),
+        },
+    }
+}
+
+#[tauri::command]
+pub async fn set_power_profile(
+    profile: String,
+    app: AppHandle,
+) -> Result<PowerProfilesState, String> {
+    log_info(&format!("Cambiando modo de energía a {}", profile));
+    let connection = system_bus(&app).await.map_err(|e| e.to_string())?;
+    power_profiles::set_profile(&connection, &profile)
+        .await
+        .map_err(|e| {
+            log_error(&format!("Error al cambiar el modo de energía: {}", e));
+            e.to_string()
+        })?;
+
+    let state = power_profiles::state(&connection).await;
+    let _ = app.emit("power-profiles-changed", &state);
+    Ok(state)
+}
@@ -1,5 +1,7 @@
 use crate::utils::search;
 use crate::logger::{log_info, log_error, log_debug};
+use crate::schedule;
+use crate::session_actions::{self, SessionAction};
 
 #[tauri::command]
 pub async fn global_search(query: String, limit: Option<usize>) -> Vec<search::SearchResult> {
@@ -11,7 +13,12 @@ pub async fn global_search(query: String, limit: Option<usize>) -> Vec<search::S
 }
 
 #[tauri::command]
-pub async fn execute_search_result(id: String, category: String, exec: Option<String>) -> Result<String, String> {
+pub async fn execute_search_result(
+    id: String,
+    category: String,
+    exec: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
     log_info(&format!("Ejecutando resultado de búsqueda: {} ({})", id, category));
     match category.as_str() {
         "application" => {
@@ -40,50 +47,24 @@ pub async fn execute_search_result(id: String, category: String, exec: Option<St
         "action" => {
             // Handle system actions
             match id.as_str() {
-                "shutdown" => {
-                    log_info("Acción de búsqueda: Apagar sistema");
-                    tokio::spawn(async {
-                        let _ = tokio::process::Command::new("systemctl")
-                            .args(["poweroff"])
-                            .spawn();
+                "shutdown" | "reboot" | "suspend" | "lock" | "logout" => {
+                    let Some(action) = SessionAction::parse(&id) else {
+                        return Err(format!("Unknown action: {}", id));
+                    };
+                    // Same path as the session popup, D-Bus and shortcuts:
+                    // the confirmation policy decides whether the popup opens
+                    // first. Errors are logged by the service.
+                    let message = match action {
+                        SessionAction::PowerOff => "Shutting down...",
+                        SessionAction::Reboot => "Rebooting...",
+                        SessionAction::Suspend => "Suspending...",
+                        SessionAction::Lock => "Locking screen...",
+                        _ => "Logging out...",
+                    };
+                    tokio::spawn(async move {
+                        let _ = session_actions::request(&app, action, "búsqueda").await;
                     });
-                    Ok("Shutting down...".to_string())
-                }
-                "reboot" => {
-                    log_info("Acción de búsqueda: Reiniciar sistema");
-                    tokio::spawn(async {
-                        let _ = tokio::process::Command::new("systemctl")
-                            .args(["reboot"])
-                            .spawn();
-                    });
-                    Ok("Rebooting...".to_string())
-                }
-                "suspend" => {
-                    log_info("Acción de búsqueda: Suspender sistema");
-                    tokio::spawn(async {
-                        let _ = tokio::process::Command::new("systemctl")
-                            .args(["suspend"])
-                            .spawn();
-                    });
-                    Ok("Suspending...".to_string())
-                }
-                "lock" => {
-                    log_info("Acción de búsqueda: Bloquear sesión");
-                    tokio::spawn(async {
-                        let _ = tokio::process::Command::new("loginctl")
-                            .args(["lock-session"])
-                            .spawn();
-                    });
-                    Ok("Locking screen...".to_string())
-                }
-                "logout" => {
-                    log_info("Acción de búsqueda: Cerrar sesión");
-                    tokio::spawn(async {
-                        let _ = tokio::process::Command::new("loginctl")
-                            .args(["terminate-user", &std::env::var("USER").unwrap_or_default()])
-                            .spawn();
-                    });
-                    Ok("Logging out...".to_string())
+                    Ok(message.to_string())
                 }
                 "settings" => {
                     log_info("Acción de búsqueda: Abrir configuración");
@@ -92,6 +73,21 @@ pub async fn execute_search_result(id: String, category: String, exec: Option<St
                     crate::commands::runner::spawn_settings()?;
                     Ok("Opening settings...".to_string())
                 }
+                _ if id.starts_with("schedule:") => {
+                    let query = id.trim_start_matches("schedule:");
+                    let Some((action, when)) = schedule::parse_query(query) else {
+                        return Err(format!("Unknown action: {}", id));
+                    };
+                    let delay = when.delay(&chrono::Local::now());
+                    let scheduled = schedule::schedule(&app, action, delay)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    Ok(format!(
+                        "Scheduled {} at {}",
+                        scheduled.action,
+                        schedule::clock(scheduled.at)
+                    ))
+                }
                 _ => {
                     log_error(&format!("Acción desconocida en búsqueda: {}", id));
                     Err(format!("Unknown action: {}", id))
@@ -1,9 +1,11 @@
-use zbus::Connection;
+use tauri::AppHandle;
 use crate::logger::{log_info, log_error};
-
-extern "C" {
-    fn getuid() -> u32;
-}
+use crate::logind;
+use crate::schedule;
+use crate::session_actions::{self, system_bus, SessionAction};
+use crate::shutdown;
+use crate::structs::{BootEntry, ScheduledAction, SessionCapabilities, SessionUser};
+use crate::users;
 
 #[tauri::command]
 pub fn detect_display_server() -> String {
@@ -21,122 +23,153 @@ pub fn detect_display_server() -> String {
     "unknown".to_string()
 }
 
+/// Ejecuta una acción que ya se confirmó en el popup.
+async fn perform(app: AppHandle, action: SessionAction) -> Result<(), String> {
+    session_actions::perform(&app, action)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-pub async fn logout(_display_server: String) -> Result<(), String> {
-    log_info("Cerrando sesión de usuario");
+pub async fn logout(_display_server: String, app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::Logout).await
+}
 
-    // The graphical session is managed by uwsm as a systemd user unit hierarchy.
-    // `uwsm stop` tears down graphical-session.target and its daemons in order.
-    // Spawn without waiting: uwsm ends the session (and this process) itself.
-    match std::process::Command::new("uwsm").arg("stop").spawn() {
-        Ok(_) => {
-            log_info("Cierre de sesión iniciado vía uwsm stop");
-            return Ok(());
-        }
-        Err(e) => {
-            log_error(&format!(
-                "uwsm no disponible ({}); usando logind TerminateUser como fallback",
-                e
-            ));
-        }
-    }
+#[tauri::command]
+pub async fn shutdown(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::PowerOff).await
+}
+
+#[tauri::command]
+pub async fn reboot(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::Reboot).await
+}
 
-    // Fallback (sin uwsm, p. ej. en dev): terminar la sesión vía logind.
-    let connection = Connection::system()
+#[tauri::command]
+pub async fn cancel_shutdown(app: AppHandle) -> bool {
+    shutdown::cancel(&app)
+}
+
+#[tauri::command]
+pub async fn suspend(_display_server: String, app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::Suspend).await
+}
+
+#[tauri::command]
+pub async fn lock(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::Lock).await
+}
+
+/// Pide una acción desde fuera del popup (un atajo, un applet): según la
+/// política de confirmación se ejecuta o se abre el popup para confirmarla.
+#[tauri::command]
+pub async fn request_session_action(action: String, app: AppHandle) -> Result<(), String> {
+    let Some(action) = SessionAction::parse(&action) else {
+        return Err(format!("Acción de sesión desconocida: {}", action));
+    };
+    session_actions::request(&app, action, "atajo")
         .await
-        .map_err(|e| {
-            log_error(&format!("No se pudo conectar a D-Bus para logout: {}", e));
-            format!("No se pudo conectar a D-Bus: {}", e)
-        })?;
-
-    let uid = unsafe { getuid() };
-
-    connection
-        .call_method(
-            Some("org.freedesktop.login1"),
-            "/org/freedesktop/login1",
-            Some("org.freedesktop.login1.Manager"),
-            "TerminateUser",
-            &(uid,),
-        )
+        .map_err(|e| e.to_string())
+}
+
+/// Programa una acción de energía para dentro de `seconds` segundos; la
+/// cuenta regresiva llega en `session-schedule`.
+#[tauri::command]
+pub async fn schedule_session_action(
+    action: String,
+    seconds: u64,
+    app: AppHandle,
+) -> Result<ScheduledAction, String> {
+    let Some(action) = SessionAction::parse(&action) else {
+        return Err(format!("Acción de sesión desconocida: {}", action));
+    };
+    let name = action.name();
+    schedule::schedule(&app, action, seconds)
         .await
         .map_err(|e| {
-            log_error(&format!("Error al terminar sesión: {}", e));
-            format!("Error al terminar sesión: {}", e)
-        })?;
+            log_error(&format!("No se pudo programar {}: {}", name, e));
+            e.to_string()
+        })
+}
 
-    log_info("Sesión cerrada correctamente");
-    Ok(())
+#[tauri::command]
+pub async fn cancel_scheduled_action(app: AppHandle) -> bool {
+    schedule::cancel(&app).await
 }
 
 #[tauri::command]
-pub async fn shutdown() -> Result<(), String> {
-    log_info("Apagando el sistema");
-    let connection = Connection::system()
-        .await
-        .map_err(|e| {
-            log_error(&format!("No se pudo conectar a D-Bus para shutdown: {}", e));
-            format!("No se pudo conectar a D-Bus: {}", e)
-        })?;
-
-    connection
-        .call_method(
-            Some("org.freedesktop.login1"),
-            "/org/freedesktop/login1",
-            Some("org.freedesktop.login1.Manager"),
-            "PowerOff",
-            &(true,),
-        )
-        .await
-        .map_err(|e| format!("No se pudo ejecutar shutdown: {}", e))?;
+pub async fn get_scheduled_action() -> Option<ScheduledAction> {
+    schedule::current()
+}
 
-    Ok(())
+/// El usuario actual y las demás sesiones del asiento.
+#[tauri::command]
+pub async fn get_session_user(app: AppHandle) -> SessionUser {
+    users::session_user(&app).await
 }
 
 #[tauri::command]
-pub async fn reboot() -> Result<(), String> {
-    log_info("Reiniciando el sistema");
-    let connection = Connection::system()
-        .await
-        .map_err(|e| {
-            log_error(&format!("No se pudo conectar a D-Bus para reboot: {}", e));
-            format!("No se pudo conectar a D-Bus: {}", e)
-        })?;
-
-    connection
-        .call_method(
-            Some("org.freedesktop.login1"),
-            "/org/freedesktop/login1",
-            Some("org.freedesktop.login1.Manager"),
-            "Reboot",
-            &(true,),
-        )
-        .await
-        .map_err(|e| format!("No se pudo ejecutar reboot: {}", e))?;
+pub async fn switch_user(app: AppHandle) -> Result<(), String> {
+    users::switch_user(&app).await.map_err(|e| {
+        log_error(&format!("No se pudo cambiar de usuario: {}", e));
+        e.to_string()
+    })
+}
 
-    Ok(())
+#[tauri::command]
+pub async fn activate_session(id: String, app: AppHandle) -> Result<(), String> {
+    users::activate_session(&app, &id).await.map_err(|e| {
+        log_error(&format!("No se pudo pasar a la sesión {}: {}", id, e));
+        e.to_string()
+    })
 }
 
+/// Qué acciones de energía admite el equipo, para no ofrecer las que no.
 #[tauri::command]
-pub async fn suspend(_display_server: String) -> Result<(), String> {
-    log_info("Suspendiendo el sistema");
-    let connection = Connection::system()
-        .await
-        .map_err(|e| {
-            log_error(&format!("No se pudo conectar a D-Bus para suspend: {}", e));
-            format!("No se pudo conectar a D-Bus: {}", e)
-        })?;
-
-    connection
-        .call_method(
-            Some("org.freedesktop.login1"),
-            "/org/freedesktop/login1",
-            Some("org.freedesktop.login1.Manager"),
-            "Suspend",
-            &(true,),
-        )
-        .await
-        .map_err(|e| format!("No se pudo ejecutar suspend: {}", e))?;
+pub async fn get_session_capabilities(app: AppHandle) -> SessionCapabilities {
+    match system_bus(&app).await {
+        Ok(connection) => logind::capabilities(&connection).await,
+        Err(e) => {
+            log_error(&format!("No se pudo conectar a D-Bus: {}", e));
+            SessionCapabilities::default()
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn hibernate(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::Hibernate).await
+}
+
+#[tauri::command]
+pub async fn hybrid_sleep(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::HybridSleep).await
+}
+
+#[tauri::command]
+pub async fn suspend_then_hibernate(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::SuspendThenHibernate).await
+}
 
-    Ok(())
-}
\ No newline at end of file
+#[tauri::command]
+pub async fn reboot_to_firmware_setup(app: AppHandle) -> Result<(), String> {
+    perform(app, SessionAction::RebootToFirmware).await
+}
+
+#[tauri::command]
+pub async fn get_boot_entries(app: AppHandle) -> Result<Vec<BootEntry>, String> {
+    let connection = system_bus(&app).await.map_err(|e| {
+        log_error(&format!("No se pudo conectar a D-Bus: {}", e));
+        e.to_string()
+    })?;
+    logind::boot_entries(&connection).await.map_err(|e| {
+        log_error(&format!("No se pudieron leer las entradas de arranque: {}", e));
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn reboot_to_boot_entry(entry: String, app: AppHandle) -> Result<(), String> {
+    log_info(&format!("Reiniciando en la entrada de arranque {}", entry));
+    perform(app, SessionAction::RebootToEntry(entry)).await
+}
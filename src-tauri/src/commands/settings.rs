@@ -0,0 +1,26 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::logger::{log_error, log_info};
+use crate::settings::{self, ShellSettings};
+
+#[tauri::command]
+pub async fn get_shell_settings() -> ShellSettings {
+    settings::get()
+}
+
+#[tauri::command]
+pub async fn set_shell_settings(
+    new_settings: ShellSettings,
+    app: AppHandle,
+) -> Result<ShellSettings, String> {
+    log_info("Guardando preferencias del shell");
+    let saved = settings::update(|current| *current = new_settings).map_err(|e| {
+        log_error(&format!("Error al guardar preferencias: {}", e));
+        e.to_string()
+    })?;
+
+    crate::auto_brightness::reload();
+    crate::night_light::reload();
+    let _ = app.emit("shell-settings-changed", &saved);
+    Ok(saved)
+}
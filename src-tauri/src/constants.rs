@@ -1,5 +1,5 @@
 // Paths del sistema
-pub const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+pub const SYS_CLASS_PATH: &str = "/sys/class";
 
 // Timeouts
 pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 3;
@@ -11,3 +11,11 @@ pub const DBUS_SERVICE_NAME: &str = "org.vasak.os.Desktop";
 pub const CMD_PACTL: &str = "pactl";
 pub const CMD_BRIGHTNESSCTL: &str = "brightnessctl";
 pub const CMD_BUSCTL: &str = "busctl";
+pub const CMD_WPCTL: &str = "wpctl";
+pub const CMD_PW_DUMP: &str = "pw-dump";
+pub const CMD_PW_CLI: &str = "pw-cli";
+pub const CMD_PIPEWIRE: &str = "pipewire";
+pub const CMD_PKEXEC: &str = "pkexec";
+
+// Ayudante que cambia los umbrales de carga de la batería (ver resources/)
+pub const CHARGE_THRESHOLD_HELPER: &str = "/usr/libexec/vasak-desktop/charge-threshold";
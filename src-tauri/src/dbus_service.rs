@@ -1,6 +1,8 @@
 use crate::commands::{toggle_control_center, toggle_menu, toggle_search, toggle_session_popup};
 use crate::constants::DBUS_SERVICE_NAME;
 use crate::logger::{log_info, log_error, log_warning, log_debug};
+use crate::media_keys::{self, MediaKey};
+use crate::session_actions::{self, SessionAction};
 use futures_util::TryStreamExt;
 use tauri::{AppHandle, Emitter};
 use zbus::{Connection, Message, Result as ZbusResult};
@@ -21,6 +23,13 @@ impl DesktopService {
         let member = header.member().map(|m| m.as_str()).unwrap_or("Unknown");
 
         log_debug(&format!("D-Bus: Método llamado: {}", member));
+
+        // Volumen, brillo y reproducción: las teclas que Wayfire reenvía.
+        if let Some(key) = MediaKey::from_member(member) {
+            tauri::async_runtime::spawn(media_keys::press(key, self.app_handle.clone()));
+            return Ok(());
+        }
+
         match member {
             "OpenMenu" => {
                 log_info("D-Bus: Abriendo menú");
@@ -50,6 +59,30 @@ impl DesktopService {
                     let _ = toggle_search(app_handle).await;
                 });
             }
+            // Las acciones de sesión pasan por el mismo servicio que el popup
+            // y la búsqueda, con su política de confirmación.
+            "Lock" | "Logout" | "Suspend" | "Hibernate" | "Shutdown" | "Reboot"
+            | "RebootToFirmware" | "RebootToEntry" => {
+                let action = match member {
+                    "RebootToFirmware" => Some(SessionAction::RebootToFirmware),
+                    // El id de la entrada de arranque llega como argumento.
+                    "RebootToEntry" => msg
+                        .body()
+                        .deserialize::<String>()
+                        .ok()
+                        .map(SessionAction::RebootToEntry),
+                    _ => SessionAction::parse(&member.to_lowercase()),
+                };
+                let Some(action) = action else {
+                    log_warning(&format!("D-Bus: {} sin entrada de arranque", member));
+                    return Ok(());
+                };
+                log_info(&format!("D-Bus: acción de sesión {}", action.name()));
+                let app_handle = self.app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = session_actions::request(&app_handle, action, "D-Bus").await;
+                });
+            }
             "OpenSessionPopup" | "PowerButtonPressed" => {
                 log_info("D-Bus: Abriendo popup de sesión");
                 let app_handle = self.app_handle.clone();
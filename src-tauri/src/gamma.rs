@@ -0,0 +1,455 @@
+//! Tablas de gamma de las salidas, con `zwlr_gamma_control_manager_v1`.
+//!
+//! Una conexión Wayland propia, con su hilo despachando eventos, mantiene un
+//! control de gamma por salida. Las salidas que aparecen después (un monitor
+//! enchufado más tarde) reciben la tabla actual en cuanto el compositor avisa
+//! el tamaño de su tabla. Cuando se cierra la conexión el compositor restaura
+//! las tablas originales, así que no hace falta limpiar nada al salir.
+//!
+//! Cada tabla combina dos cosas: la temperatura de color de la luz nocturna,
+//! igual para todas las salidas, y un brillo por salida, que es la única forma
+//! de oscurecer un monitor externo que no tiene retroiluminación regulable.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_debug, log_info, log_warning};
+use crate::night_light::DAYLIGHT;
+use crate::settings;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::os::fd::{AsFd, FromRawFd, OwnedFd};
+use std::sync::{Arc, Mutex, OnceLock};
+use wayland_client::protocol::wl_output::{self, WlOutput};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+use wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1;
+use wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_v1::{
+    self, ZwlrGammaControlV1,
+};
+
+/// Multiplicadores (rojo, verde, azul) de una temperatura de color, normalizados
+/// para que la luz de día deje la tabla intacta. Aproximación de Tanner Helland
+/// a la curva del cuerpo negro.
+pub(crate) fn temperature_to_rgb(kelvin: u32) -> (f64, f64, f64) {
+    fn raw(kelvin: u32) -> (f64, f64, f64) {
+        let t = kelvin as f64 / 100.0;
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2)
+        };
+        let green = if t <= 66.0 {
+            99.470_802_586_1 * t.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7
+        };
+        (red, green, blue)
+    }
+
+    if kelvin >= DAYLIGHT {
+        return (1.0, 1.0, 1.0);
+    }
+    let (r, g, b) = raw(kelvin);
+    let (dr, dg, db) = raw(DAYLIGHT);
+    (
+        (r / dr).clamp(0.0, 1.0),
+        (g / dg).clamp(0.0, 1.0),
+        (b / db).clamp(0.0, 1.0),
+    )
+}
+
+/// Tabla para `set_gamma`: `size` valores de rojo, luego verde, luego azul.
+/// `brightness` (0-100) escala los tres canales por igual.
+pub(crate) fn build_ramp(size: usize, kelvin: u32, brightness: u32) -> Vec<u16> {
+    let (r, g, b) = temperature_to_rgb(kelvin);
+    let last = size.saturating_sub(1).max(1) as f64;
+    let scale = brightness.min(100) as f64 / 100.0;
+
+    [r, g, b]
+        .into_iter()
+        .flat_map(|factor| {
+            (0..size).map(move |i| {
+                (i as f64 / last * factor * scale * u16::MAX as f64).round() as u16
+            })
+        })
+        .collect()
+}
+
+/// El protocolo recibe la tabla en un descriptor de archivo.
+fn ramp_fd(ramp: &[u16]) -> std::io::Result<OwnedFd> {
+    let fd = unsafe { libc::memfd_create(c"vasak-gamma".as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let bytes: Vec<u8> = ramp.iter().flat_map(|value| value.to_ne_bytes()).collect();
+    file.write_all(&bytes)?;
+    // El compositor lee desde la posición actual.
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file.into())
+}
+
+struct Output {
+    output: WlOutput,
+    /// Nombre del conector ("DP-1"), si el compositor lo informa
+    name: Option<String>,
+    control: Option<ZwlrGammaControlV1>,
+    /// Entradas por canal de la tabla; llega con `gamma_size`
+    size: Option<usize>,
+    /// Brillo por software, 0-100
+    brightness: u32,
+}
+
+impl Output {
+    fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("?")
+    }
+
+    fn apply(&self, kelvin: u32) {
+        let (Some(control), Some(size)) = (&self.control, self.size) else {
+            return;
+        };
+
+        match ramp_fd(&build_ramp(size, kelvin, self.brightness)) {
+            Ok(fd) => control.set_gamma(fd.as_fd()),
+            Err(e) => log_warning(&format!(
+                "Gamma: no se pudo preparar la tabla para {}: {}",
+                self.label(),
+                e
+            )),
+        }
+    }
+}
+
+struct State {
+    manager: Option<ZwlrGammaControlManagerV1>,
+    /// Por nombre global del `wl_output`
+    outputs: HashMap<u32, Output>,
+    temperature: u32,
+}
+
+impl State {
+    fn add_control(&mut self, global: u32, qh: &QueueHandle<Shared>) {
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        if let Some(output) = self.outputs.get_mut(&global) {
+            if output.control.is_none() {
+                output.control = Some(manager.get_gamma_control(&output.output, qh, global));
+            }
+        }
+    }
+}
+
+/// Lo que recibe cada `Dispatch`: el estado, compartido con quien cambia la
+/// temperatura desde otros hilos.
+struct Shared(Arc<Mutex<State>>);
+
+pub struct GammaService {
+    connection: Connection,
+    state: Arc<Mutex<State>>,
+}
+
+impl GammaService {
+    fn connect() -> Result<Self> {
+        let connection = Connection::connect_to_env()
+            .map_err(|e| VasakError::InvalidState(format!("Wayland: {}", e)))?;
+        let mut queue: EventQueue<Shared> = connection.new_event_queue();
+        let qh = queue.handle();
+        connection.display().get_registry(&qh, ());
+
+        let state = Arc::new(Mutex::new(State {
+            manager: None,
+            outputs: HashMap::new(),
+            temperature: DAYLIGHT,
+        }));
+        let mut shared = Shared(state.clone());
+
+        // La primera vuelta anuncia los globales; la segunda trae los nombres
+        // de las salidas y el tamaño de sus tablas.
+        for _ in 0..2 {
+            queue
+                .roundtrip(&mut shared)
+                .map_err(|e| VasakError::InvalidState(format!("Wayland: {}", e)))?;
+        }
+
+        let has_manager = state
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("gamma"))?
+            .manager
+            .is_some();
+        if !has_manager {
+            return Err(VasakError::NotFound(
+                "zwlr_gamma_control_manager_v1 (el compositor no permite cambiar la gamma)"
+                    .to_string(),
+            ));
+        }
+
+        std::thread::Builder::new()
+            .name("gamma".to_string())
+            .spawn(move || loop {
+                if let Err(e) = queue.blocking_dispatch(&mut shared) {
+                    log_warning(&format!("Gamma: se perdió la conexión Wayland: {}", e));
+                    break;
+                }
+            })?;
+
+        log_info("Gamma: control de las tablas de las salidas listo");
+        Ok(Self { connection, state })
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut State) -> T) -> Result<T> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("gamma"))?;
+        let result = f(&mut state);
+        drop(state);
+        self.connection
+            .flush()
+            .map_err(|e| VasakError::InvalidState(format!("Wayland: {}", e)))?;
+        Ok(result)
+    }
+
+    /// Lleva todas las salidas a `kelvin`.
+    pub fn set_temperature(&self, kelvin: u32) -> Result<()> {
+        self.with_state(|state| {
+            if state.temperature == kelvin {
+                return;
+            }
+            state.temperature = kelvin;
+            for output in state.outputs.values() {
+                output.apply(kelvin);
+            }
+        })
+    }
+
+    /// Vuelve a mandar las tablas, para salidas que el compositor reconfiguró.
+    pub fn reapply(&self) -> Result<()> {
+        self.with_state(|state| {
+            for output in state.outputs.values() {
+                output.apply(state.temperature);
+            }
+        })
+    }
+
+    /// Salidas que se pueden oscurecer, por nombre de conector, con su brillo.
+    pub fn outputs(&self) -> Vec<(String, u32)> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+
+        let mut outputs: Vec<(String, u32)> = state
+            .outputs
+            .values()
+            .filter(|output| output.control.is_some())
+            .filter_map(|output| Some((output.name.clone()?, output.brightness)))
+            .collect();
+        outputs.sort();
+        outputs
+    }
+
+    /// Cambia el brillo por software de la salida `name` (0-100).
+    pub fn set_output_brightness(&self, name: &str, brightness: u32) -> Result<()> {
+        self.with_state(|state| {
+            let temperature = state.temperature;
+            let output = state
+                .outputs
+                .values_mut()
+                .find(|output| output.name.as_deref() == Some(name))
+                .ok_or_else(|| VasakError::NotFound(format!("Salida {} no encontrada", name)))?;
+
+            output.brightness = brightness.min(100);
+            output.apply(temperature);
+            Ok(())
+        })?
+    }
+}
+
+/// El servicio, conectado la primera vez que se pide. `None` si no hay
+/// sesión Wayland o el compositor no tiene el protocolo.
+pub fn service() -> Option<&'static GammaService> {
+    static SERVICE: OnceLock<Option<GammaService>> = OnceLock::new();
+
+    SERVICE
+        .get_or_init(|| match GammaService::connect() {
+            Ok(service) => Some(service),
+            Err(e) => {
+                log_warning(&format!("Gamma no disponible: {}", e));
+                None
+            }
+        })
+        .as_ref()
+}
+
+impl Dispatch<WlRegistry, ()> for Shared {
+    fn event(
+        shared: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let Ok(mut state) = shared.0.lock() else {
+            return;
+        };
+
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => {
+                if interface == WlOutput::interface().name {
+                    // La versión 4 agrega el nombre del conector.
+                    let output = registry.bind::<WlOutput, _, _>(name, version.min(4), qh, name);
+                    state.outputs.insert(
+                        name,
+                        Output {
+                            output,
+                            name: None,
+                            control: None,
+                            size: None,
+                            brightness: 100,
+                        },
+                    );
+                    state.add_control(name, qh);
+                } else if interface == ZwlrGammaControlManagerV1::interface().name {
+                    state.manager =
+                        Some(registry.bind::<ZwlrGammaControlManagerV1, _, _>(name, 1, qh, ()));
+                    let globals: Vec<u32> = state.outputs.keys().copied().collect();
+                    for global in globals {
+                        state.add_control(global, qh);
+                    }
+                }
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(output) = state.outputs.remove(&name) {
+                    log_debug(&format!("Gamma: se quitó la salida {}", output.label()));
+                    if let Some(control) = output.control {
+                        control.destroy();
+                    }
+                    if output.output.version() >= 3 {
+                        output.output.release();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, u32> for Shared {
+    fn event(
+        shared: &mut Self,
+        _: &WlOutput,
+        event: wl_output::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            // Llega antes que `gamma_size`: la primera tabla ya sale con el
+            // brillo que el usuario dejó la última vez.
+            let saved = settings::get().brightness.outputs.get(&name).copied();
+            if let Ok(mut state) = shared.0.lock() {
+                if let Some(output) = state.outputs.get_mut(global) {
+                    output.brightness = saved.unwrap_or(100);
+                    output.name = Some(name);
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrGammaControlV1, u32> for Shared {
+    fn event(
+        shared: &mut Self,
+        _: &ZwlrGammaControlV1,
+        event: zwlr_gamma_control_v1::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Ok(mut state) = shared.0.lock() else {
+            return;
+        };
+        let temperature = state.temperature;
+        let Some(output) = state.outputs.get_mut(global) else {
+            return;
+        };
+
+        match event {
+            zwlr_gamma_control_v1::Event::GammaSize { size } => {
+                log_debug(&format!(
+                    "Gamma: salida {} con tabla de {} entradas",
+                    output.label(),
+                    size
+                ));
+                output.size = Some(size as usize);
+                // Una salida nueva toma la temperatura actual de entrada.
+                output.apply(temperature);
+            }
+            zwlr_gamma_control_v1::Event::Failed => {
+                // Otro programa (wlsunset, gammastep) ya la tiene, o la
+                // salida desapareció.
+                log_warning(&format!(
+                    "Gamma: el compositor rechazó el control de {}",
+                    output.label()
+                ));
+                if let Some(control) = output.control.take() {
+                    control.destroy();
+                }
+                output.size = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(Shared: ignore ZwlrGammaControlManagerV1);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daylight_leaves_the_ramp_linear() {
+        let ramp = build_ramp(256, DAYLIGHT, 100);
+        assert_eq!(ramp.len(), 256 * 3);
+        assert_eq!(ramp[0], 0);
+        assert_eq!(ramp[255], u16::MAX);
+        assert_eq!(ramp[511], u16::MAX);
+        assert_eq!(ramp[767], u16::MAX);
+    }
+
+    #[test]
+    fn warm_temperatures_cut_blue_more_than_red() {
+        let (r, g, b) = temperature_to_rgb(3500);
+        assert_eq!(r, 1.0);
+        assert!(g < r && b < g, "{} {} {}", r, g, b);
+
+        let ramp = build_ramp(256, 3500, 100);
+        assert_eq!(ramp[255], u16::MAX);
+        assert!(ramp[767] < ramp[511]);
+    }
+
+    #[test]
+    fn brightness_scales_on_top_of_the_temperature() {
+        let warm = build_ramp(256, 3500, 100);
+        let dimmed = build_ramp(256, 3500, 50);
+        assert_eq!(dimmed[255], u16::MAX / 2 + 1);
+        for (full, half) in warm.iter().zip(&dimmed) {
+            assert!((*full as f64 / 2.0 - *half as f64).abs() <= 1.0);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Inhibidor de inactividad de Wayland (`zwp_idle_inhibit_manager_v1`).
+//!
+//! El protocolo ata el inhibidor a una superficie. Se usa una conexión propia
+//! con una superficie sin rol, que nunca se muestra: Wayfire cuenta los
+//! inhibidores sin mirar si su superficie está a la vista. Un compositor que
+//! sí lo mire (sway, por ejemplo) lo ignora, y queda el bloqueo de logind.
+//!
+//! No hay eventos que atender, así que no hace falta un hilo despachando: al
+//! soltar el inhibidor se destruyen los objetos y se cierra la conexión.
+
+use crate::error::{Result, VasakError};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1;
+
+struct State;
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as wayland_client::Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: ignore WlCompositor);
+delegate_noop!(State: ignore WlSurface);
+delegate_noop!(State: ignore ZwpIdleInhibitManagerV1);
+delegate_noop!(State: ignore ZwpIdleInhibitorV1);
+
+/// Mientras exista, la sesión no pasa a inactiva.
+pub struct IdleInhibitor {
+    connection: Connection,
+    surface: WlSurface,
+    inhibitor: ZwpIdleInhibitorV1,
+}
+
+impl IdleInhibitor {
+    pub fn new() -> Result<Self> {
+        let connection = Connection::connect_to_env()
+            .map_err(|e| VasakError::NotFound(format!("compositor Wayland: {}", e)))?;
+        let (globals, mut queue) = registry_queue_init::<State>(&connection)
+            .map_err(|e| VasakError::InvalidState(format!("registro Wayland: {}", e)))?;
+        let qh = queue.handle();
+
+        let compositor: WlCompositor = globals
+            .bind(&qh, 1..=4, ())
+            .map_err(|e| VasakError::NotFound(format!("wl_compositor: {}", e)))?;
+        let manager: ZwpIdleInhibitManagerV1 = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|e| VasakError::NotFound(format!("zwp_idle_inhibit_manager_v1: {}", e)))?;
+
+        let surface = compositor.create_surface(&qh, ());
+        let inhibitor = manager.create_inhibitor(&surface, &qh, ());
+        manager.destroy();
+
+        queue
+            .roundtrip(&mut State)
+            .map_err(|e| VasakError::InvalidState(format!("Wayland: {}", e)))?;
+
+        Ok(Self {
+            connection,
+            surface,
+            inhibitor,
+        })
+    }
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        self.inhibitor.destroy();
+        self.surface.destroy();
+        let _ = self.connection.flush();
+    }
+}
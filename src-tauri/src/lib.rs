@@ -9,12 +9,24 @@ mod structs;
 // Feature modules
 mod applets;
 mod audio;
+mod audio_backend;
+mod audio_effects;
+mod audio_policy;
 mod audio_native;
+mod auto_brightness;
+mod battery_history;
+mod battery_warnings;
 mod brightness;
+mod caffeine;
+mod charge_limit;
 mod commands;
 mod connect;
 mod dbus_service;
 mod eventloops;
+mod gamma;
+mod idle_inhibit;
+mod lock;
+mod logind;
 /// Where the translations live.
 ///
 /// The i18n plugin resolves them at runtime and only probes paths relative to
@@ -47,11 +59,20 @@ fn default_locale() -> String {
     }
 }
 
+mod media_keys;
 mod menu_manager;
 mod menu_watcher;
+mod mic_level;
 mod monitor_manager;
+mod night_light;
 mod notifications;
+mod power_profiles;
+mod schedule;
+mod session_actions;
+mod settings;
+mod shutdown;
 mod tray;
+mod users;
 mod utils;
 mod gtk_utils;
 mod window_manager;
@@ -84,6 +105,8 @@ pub(crate) struct PanelReadyLatch(pub(crate) watch::Sender<bool>);
 use applets::{
     manager::{AppletManager, AppletPriority},
     audio::AudioApplet,
+    audio_policy::AudioPolicyApplet,
+    auto_brightness::AutoBrightnessApplet,
     battery::BatteryApplet,
     bluetooth::BluetoothApplet,
     brightness::BrightnessApplet,
@@ -91,6 +114,10 @@ use applets::{
     keyboard_leds::KeyboardLedsApplet,
     music::MusicApplet,
     network::NetworkApplet,
+    night_light::NightLightApplet,
+    power_profiles::PowerProfilesApplet,
+    caffeine::CaffeineApplet,
+    lock::LockApplet,
     notifications::NotificationApplet, 
     tray::TrayApplet
 };
@@ -138,10 +165,26 @@ pub fn run() {
             open_settings,
             show_osd,
             toggle_session_popup,
+            lock,
             logout,
             shutdown,
             reboot,
             suspend,
+            hibernate,
+            hybrid_sleep,
+            suspend_then_hibernate,
+            reboot_to_firmware_setup,
+            reboot_to_boot_entry,
+            cancel_shutdown,
+            request_session_action,
+            schedule_session_action,
+            cancel_scheduled_action,
+            get_scheduled_action,
+            get_session_user,
+            switch_user,
+            activate_session,
+            get_boot_entries,
+            get_session_capabilities,
             detect_display_server,
             get_menu_items,
             toggle_menu,
@@ -151,9 +194,32 @@ pub fn run() {
             toggle_audio_mute,
             get_audio_devices,
             set_audio_device,
+            get_audio_sources,
+            set_audio_source,
+            get_audio_source_volume,
+            set_audio_source_volume,
+            toggle_audio_source_mute,
+            get_audio_cards,
+            set_audio_port,
+            set_audio_card_profile,
+            get_audio_noise_suppression,
+            set_audio_noise_suppression,
+            get_shell_settings,
+            set_shell_settings,
             toggle_audio_applet,
             get_brightness_info,
             set_brightness_info,
+            get_brightness_devices,
+            get_auto_brightness,
+            set_auto_brightness,
+            get_night_light,
+            set_night_light,
+            toggle_night_light,
+            get_power_profiles,
+            set_power_profile,
+            get_caffeine,
+            set_caffeine,
+            toggle_caffeine,
             send_notify,
             clear_notifications,
             get_all_notifications,
@@ -179,6 +245,10 @@ pub fn run() {
             battery_exists,
             battery_fetch_info,
             get_battery_info,
+            get_peripheral_batteries,
+            cancel_critical_battery_action,
+            get_battery_history,
+            set_charge_limit,
             global_search,
             execute_search_result,
             toggle_search,
@@ -272,6 +342,12 @@ pub fn run() {
                 }
             }
             setup_dbus_service(app.handle().clone());
+
+            // A shutdown scheduled before the shell restarted is still in logind.
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                schedule::restore(&handle).await;
+            });
             
             // Initialize AppletManager with priority-based phased startup
             let app_handle = app.handle().clone();
@@ -285,13 +361,20 @@ pub fn run() {
                 // Normal: Spawned after critical are ready, without awaiting
                 manager.register(BatteryApplet, AppletPriority::Normal).await;
                 manager.register(KeyboardLedsApplet, AppletPriority::Normal).await;
+                manager.register(AudioPolicyApplet, AppletPriority::Normal).await;
                 manager.register(MusicApplet, AppletPriority::Normal).await;
                 manager.register(TrayApplet, AppletPriority::Normal).await;
                 manager.register(NotificationApplet, AppletPriority::Normal).await;
+                manager.register(NightLightApplet, AppletPriority::Normal).await;
+                manager.register(PowerProfilesApplet, AppletPriority::Normal).await;
+                manager.register(CaffeineApplet, AppletPriority::Normal).await;
+                manager.register(LockApplet, AppletPriority::Normal).await;
 
                 // Deferred: Started after panel-ready event from frontend
                 manager.register(BluetoothApplet, AppletPriority::Deferred).await;
                 manager.register(NetworkApplet, AppletPriority::Deferred).await;
+                // Opt-in, and the screen already has a usable brightness.
+                manager.register(AutoBrightnessApplet, AppletPriority::Deferred).await;
                 // The phone service: nothing on screen depends on it, and most
                 // sessions never plug one in.
                 manager.register(ConnectApplet, AppletPriority::Deferred).await;
@@ -0,0 +1,252 @@
+//! Bloqueo de pantalla.
+//!
+//! El escritorio no dibuja la pantalla de bloqueo: lanza un bloqueador que
+//! habla `ext-session-lock-v1` con el compositor (swaylock, gtklock o el de
+//! Vasak cuando exista) y lo sigue hasta que termina, que es cuando el usuario
+//! desbloqueó. Mientras tanto se mantiene al día el `LockedHint` de la sesión
+//! en logind, que es lo que miran los demás (el gestor de inactividad, por
+//! ejemplo) para saber si ya está bloqueada.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_info, log_warning};
+use crate::session_actions::system_bus;
+use crate::settings;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+
+/// Lo que tarda un bloqueador en tapar todas las salidas. Antes de suspender
+/// se le da este margen: si el equipo se duerme antes, al despertar se ve el
+/// escritorio un instante.
+pub const SETTLE: Duration = Duration::from_secs(1);
+
+struct Known {
+    program: &'static str,
+    /// Señal con la que desbloquea sin contraseña, si tiene una
+    unlock_signal: Option<i32>,
+    /// Opciones con las que pasa a segundo plano: su proceso terminaría con
+    /// la pantalla todavía bloqueada, así que se le quitan
+    daemonize: &'static [&'static str],
+}
+
+/// En orden de preferencia para `auto`.
+const KNOWN: &[Known] = &[
+    Known {
+        program: "vasak-locker",
+        unlock_signal: Some(libc::SIGUSR1),
+        daemonize: &[],
+    },
+    Known {
+        program: "swaylock",
+        unlock_signal: Some(libc::SIGUSR1),
+        daemonize: &["-f", "--daemonize"],
+    },
+    Known {
+        program: "gtklock",
+        unlock_signal: None,
+        daemonize: &["-d", "--daemonize"],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LockCommand {
+    pub(crate) program: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) unlock_signal: Option<i32>,
+}
+
+/// Qué ejecutar según la preferencia `locker`.
+pub(crate) fn resolve(locker: &str, installed: impl Fn(&str) -> bool) -> Option<LockCommand> {
+    let locker = locker.trim();
+    if locker.is_empty() || locker == "auto" {
+        return KNOWN
+            .iter()
+            .find(|known| installed(known.program))
+            .map(|known| LockCommand {
+                program: known.program.to_string(),
+                args: Vec::new(),
+                unlock_signal: known.unlock_signal,
+            });
+    }
+
+    let mut parts = locker.split_whitespace().map(str::to_string);
+    let program = parts.next()?;
+    let name = Path::new(&program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let known = KNOWN.iter().find(|known| known.program == name);
+    Some(LockCommand {
+        unlock_signal: known.and_then(|known| known.unlock_signal),
+        program,
+        args: parts
+            .filter(|arg| !known.is_some_and(|known| known.daemonize.contains(&arg.as_str())))
+            .collect(),
+    })
+}
+
+fn installed(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+    })
+}
+
+struct Running {
+    pid: Option<u32>,
+    unlock_signal: Option<i32>,
+}
+
+static RUNNING: Mutex<Option<Running>> = Mutex::new(None);
+
+pub fn is_locked() -> bool {
+    RUNNING.lock().map(|running| running.is_some()).unwrap_or(false)
+}
+
+/// Bloquea la pantalla, si no lo está ya.
+pub async fn lock(app: &AppHandle) -> Result<()> {
+    let command = resolve(&settings::get().lock.locker, installed)
+        .ok_or_else(|| VasakError::NotFound("bloqueador de pantalla".to_string()))?;
+
+    let mut child = {
+        let mut running = RUNNING
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("lock"))?;
+        if running.is_some() {
+            return Ok(());
+        }
+        let child = tokio::process::Command::new(&command.program)
+            .args(&command.args)
+            .spawn()
+            .map_err(|e| VasakError::Command(format!("{}: {}", command.program, e)))?;
+        *running = Some(Running {
+            pid: child.id(),
+            unlock_signal: command.unlock_signal,
+        });
+        child
+    };
+
+    log_info(&format!("Pantalla bloqueada con {}", command.program));
+    set_locked_hint(app, true).await;
+    let _ = app.emit("session-lock-changed", true);
+
+    let app = app.clone();
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) if !status.success() => {
+                log_warning(&format!("El bloqueador terminó con {}", status));
+            }
+            Err(e) => log_warning(&format!("No se pudo esperar al bloqueador: {}", e)),
+            _ => log_info("Pantalla desbloqueada"),
+        }
+        if let Ok(mut running) = RUNNING.lock() {
+            *running = None;
+        }
+        set_locked_hint(&app, false).await;
+        let _ = app.emit("session-lock-changed", false);
+    });
+    Ok(())
+}
+
+/// Desbloquea desde afuera (logind `Unlock`). Solo se puede con los
+/// bloqueadores que lo permiten; con los demás, desbloquea el usuario.
+pub fn unlock() {
+    let Ok(running) = RUNNING.lock() else {
+        return;
+    };
+    let Some(running) = running.as_ref() else {
+        return;
+    };
+    match (running.pid, running.unlock_signal) {
+        (Some(pid), Some(signal)) => {
+            log_info("Desbloqueando la pantalla a pedido de logind");
+            unsafe {
+                libc::kill(pid as i32, signal);
+            }
+        }
+        _ => log_warning("El bloqueador no se puede desbloquear desde afuera"),
+    }
+}
+
+/// Ruta en logind de la sesión gráfica. Con uwsm el escritorio corre como
+/// unidad de systemd, fuera del scope de la sesión: `auto` la resuelve igual,
+/// como la sesión gráfica del usuario.
+pub async fn session_path(connection: &Connection) -> Result<OwnedObjectPath> {
+    let id = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1",
+            Some("org.freedesktop.login1.Manager"),
+            "GetSession",
+            &(id.as_str(),),
+        )
+        .await?;
+    Ok(reply.body().deserialize()?)
+}
+
+async fn set_locked_hint(app: &AppHandle, locked: bool) {
+    let Ok(connection) = system_bus(app).await else {
+        return;
+    };
+    let result = async {
+        let path = session_path(&connection).await?;
+        connection
+            .call_method(
+                Some("org.freedesktop.login1"),
+                &path,
+                Some("org.freedesktop.login1.Session"),
+                "SetLockedHint",
+                &(locked,),
+            )
+            .await?;
+        Ok::<_, VasakError>(())
+    }
+    .await;
+    if let Err(e) = result {
+        log_warning(&format!("No se pudo avisar a logind del bloqueo: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_picks_the_first_installed_locker() {
+        let only_gtklock = |program: &str| program == "gtklock";
+        let command = resolve("auto", only_gtklock).unwrap();
+        assert_eq!(command.program, "gtklock");
+        assert_eq!(command.unlock_signal, None);
+
+        let command = resolve("", |_| true).unwrap();
+        assert_eq!(command.program, "vasak-locker");
+        assert_eq!(resolve("auto", |_| false), None);
+    }
+
+    #[test]
+    fn custom_command_keeps_arguments_and_known_signals() {
+        let command = resolve("/usr/bin/swaylock -c 000000", |_| false).unwrap();
+        assert_eq!(command.program, "/usr/bin/swaylock");
+        assert_eq!(command.args, ["-c", "000000"]);
+        assert_eq!(command.unlock_signal, Some(libc::SIGUSR1));
+
+        let command = resolve("my-locker --fast", |_| false).unwrap();
+        assert_eq!(command.unlock_signal, None);
+        assert_eq!(resolve("   ", |_| false), None);
+    }
+
+    #[test]
+    fn known_lockers_stay_in_the_foreground() {
+        let command = resolve("swaylock -f -c 000000 --daemonize", |_| false).unwrap();
+        assert_eq!(command.args, ["-c", "000000"]);
+        let command = resolve("gtklock -d", |_| false).unwrap();
+        assert!(command.args.is_empty());
+
+        // De los desconocidos no se sabe qué significa cada opción.
+        let command = resolve("my-locker -f", |_| false).unwrap();
+        assert_eq!(command.args, ["-f"]);
+    }
+}
@@ -0,0 +1,301 @@
+//! Acciones de energía a través de logind.
+//!
+//! logind sabe qué puede hacer este equipo: no hay hibernación sin swap
+//! suficiente, el reinicio al firmware depende de UEFI y elegir la entrada de
+//! arranque solo existe con un gestor que implemente la Boot Loader Interface
+//! (systemd-boot). Cada `Can*` responde "yes", "no", "challenge" (hace falta
+//! autenticarse) o "na" (no aplica). Se ofrece lo que da "yes" o "challenge":
+//! en el segundo caso polkit pide la contraseña.
+
+use crate::error::Result;
+use crate::structs::{BootEntry, Inhibitor, SessionCapabilities};
+use std::collections::HashMap;
+use std::path::Path;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::Connection;
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const PATH: &str = "/org/freedesktop/login1";
+const MANAGER: &str = "org.freedesktop.login1.Manager";
+
+/// Donde suele estar montada la partición EFI, con las entradas de arranque
+/// en `loader/entries`.
+const ESP_MOUNTS: &[&str] = &["/efi", "/boot", "/boot/efi"];
+
+/// Entrada que agrega systemd-boot para ir al firmware; se ofrece aparte.
+const FIRMWARE_ENTRY: &str = "auto-reboot-to-firmware-setup";
+
+pub(crate) fn allowed(answer: &str) -> bool {
+    matches!(answer, "yes" | "challenge")
+}
+
+async fn can(connection: &Connection, method: &str) -> bool {
+    let reply = connection
+        .call_method(Some(DESTINATION), PATH, Some(MANAGER), method, &())
+        .await;
+    reply
+        .ok()
+        .and_then(|reply| reply.body().deserialize::<String>().ok())
+        .is_some_and(|answer| allowed(&answer))
+}
+
+pub async fn capabilities(connection: &Connection) -> SessionCapabilities {
+    let (power_off, reboot, suspend, hibernate) = tokio::join!(
+        can(connection, "CanPowerOff"),
+        can(connection, "CanReboot"),
+        can(connection, "CanSuspend"),
+        can(connection, "CanHibernate"),
+    );
+    let (hybrid_sleep, suspend_then_hibernate, firmware, boot_entry) = tokio::join!(
+        can(connection, "CanHybridSleep"),
+        can(connection, "CanSuspendThenHibernate"),
+        can(connection, "CanRebootToFirmwareSetup"),
+        can(connection, "CanRebootToBootLoaderEntry"),
+    );
+    SessionCapabilities {
+        power_off,
+        reboot,
+        suspend,
+        hibernate,
+        hybrid_sleep,
+        suspend_then_hibernate,
+        reboot_to_firmware_setup: firmware,
+        reboot_to_boot_loader_entry: boot_entry,
+    }
+}
+
+/// Llama a una acción del Manager que recibe solo `interactive`
+/// (`PowerOff`, `Hibernate`, `HybridSleep`...).
+pub async fn call(connection: &Connection, method: &str, interactive: bool) -> Result<()> {
+    connection
+        .call_method(Some(DESTINATION), PATH, Some(MANAGER), method, &(interactive,))
+        .await?;
+    Ok(())
+}
+
+pub async fn reboot_to_firmware_setup(connection: &Connection) -> Result<()> {
+    connection
+        .call_method(
+            Some(DESTINATION),
+            PATH,
+            Some(MANAGER),
+            "SetRebootToFirmwareSetup",
+            &(true,),
+        )
+        .await?;
+    call(connection, "Reboot", true).await
+}
+
+pub async fn reboot_to_boot_loader_entry(connection: &Connection, entry: &str) -> Result<()> {
+    connection
+        .call_method(
+            Some(DESTINATION),
+            PATH,
+            Some(MANAGER),
+            "SetRebootToBootLoaderEntry",
+            &(entry,),
+        )
+        .await?;
+    call(connection, "Reboot", true).await
+}
+
+pub async fn inhibitors(connection: &Connection) -> Result<Vec<Inhibitor>> {
+    let reply = connection
+        .call_method(Some(DESTINATION), PATH, Some(MANAGER), "ListInhibitors", &())
+        .await?;
+    let list: Vec<(String, String, String, String, u32, u32)> = reply.body().deserialize()?;
+    Ok(list
+        .into_iter()
+        .map(|(what, who, why, mode, uid, pid)| Inhibitor {
+            what,
+            who,
+            why,
+            mode,
+            uid,
+            pid,
+        })
+        .collect())
+}
+
+/// Si impide `what` ("shutdown", "sleep"...). Los "delay" solo lo demoran
+/// unos segundos y logind los espera solo.
+pub(crate) fn blocks(inhibitor: &Inhibitor, what: &str) -> bool {
+    inhibitor.mode == "block" && inhibitor.what.split(':').any(|item| item == what)
+}
+
+/// Una sesión de `ListSessions`.
+#[derive(Debug, Clone)]
+pub struct ListedSession {
+    pub id: String,
+    pub uid: u32,
+    pub user: String,
+    pub seat: String,
+    pub path: OwnedObjectPath,
+}
+
+pub async fn sessions(connection: &Connection) -> Result<Vec<ListedSession>> {
+    let reply = connection
+        .call_method(Some(DESTINATION), PATH, Some(MANAGER), "ListSessions", &())
+        .await?;
+    let list: Vec<(String, u32, String, String, OwnedObjectPath)> = reply.body().deserialize()?;
+    Ok(list
+        .into_iter()
+        .map(|(id, uid, user, seat, path)| ListedSession {
+            id,
+            uid,
+            user,
+            seat,
+            path,
+        })
+        .collect())
+}
+
+/// Las propiedades de una sesión (`Class`, `State`, `Active`...).
+pub async fn session_properties(
+    connection: &Connection,
+    path: &OwnedObjectPath,
+) -> Result<HashMap<String, OwnedValue>> {
+    let reply = connection
+        .call_method(
+            Some(DESTINATION),
+            path,
+            Some("org.freedesktop.DBus.Properties"),
+            "GetAll",
+            &("org.freedesktop.login1.Session",),
+        )
+        .await?;
+    Ok(reply.body().deserialize()?)
+}
+
+/// Pasa el asiento a otra sesión, como cambiar de terminal virtual.
+pub async fn activate_session(connection: &Connection, id: &str) -> Result<()> {
+    connection
+        .call_method(Some(DESTINATION), PATH, Some(MANAGER), "ActivateSession", &(id,))
+        .await?;
+    Ok(())
+}
+
+/// El apagado que tiene programado logind (`ScheduleShutdown`): el tipo
+/// ("poweroff", "reboot"...) y cuándo, en microsegundos desde 1970.
+pub async fn scheduled_shutdown(connection: &Connection) -> Result<Option<(String, u64)>> {
+    let reply = connection
+        .call_method(
+            Some(DESTINATION),
+            PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(MANAGER, "ScheduledShutdown"),
+        )
+        .await?;
+    let value: Value = reply.body().deserialize::<OwnedValue>()?.into();
+    let scheduled = <(String, u64)>::try_from(value)
+        .ok()
+        .filter(|(kind, usec)| !kind.is_empty() && *usec > 0);
+    Ok(scheduled)
+}
+
+/// Las entradas del gestor de arranque para "reiniciar en…".
+pub async fn boot_entries(connection: &Connection) -> Result<Vec<BootEntry>> {
+    let reply = connection
+        .call_method(
+            Some(DESTINATION),
+            PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(MANAGER, "BootLoaderEntries"),
+        )
+        .await?;
+    let ids: Vec<String> = reply
+        .body()
+        .deserialize::<OwnedValue>()
+        .ok()
+        .and_then(|value| Vec::<String>::try_from(value).ok())
+        .unwrap_or_default();
+
+    Ok(ids
+        .into_iter()
+        .filter(|id| id != FIRMWARE_ENTRY)
+        .map(|id| BootEntry {
+            title: entry_title(&id, read_entry_file),
+            id,
+        })
+        .collect())
+}
+
+fn read_entry_file(id: &str) -> Option<String> {
+    ESP_MOUNTS
+        .iter()
+        .map(|mount| Path::new(mount).join("loader/entries").join(id))
+        .find_map(|path| std::fs::read_to_string(path).ok())
+}
+
+/// Nombre para mostrar: el `title` del archivo de la entrada si se puede
+/// leer, el de las entradas automáticas de systemd-boot, o el id.
+pub(crate) fn entry_title(id: &str, read: impl Fn(&str) -> Option<String>) -> String {
+    let known = match id {
+        "auto-windows" => Some("Windows Boot Manager"),
+        "auto-osx" => Some("macOS"),
+        "auto-efi-shell" => Some("EFI Shell"),
+        "auto-efi-default" => Some("EFI Default Loader"),
+        _ => None,
+    };
+    if let Some(title) = known {
+        return title.to_string();
+    }
+
+    read(id)
+        .and_then(|contents| {
+            contents.lines().find_map(|line| {
+                let title = line.trim().strip_prefix("title")?;
+                title
+                    .starts_with(char::is_whitespace)
+                    .then(|| title.trim().to_string())
+                    .filter(|title| !title.is_empty())
+            })
+        })
+        .unwrap_or_else(|| id.trim_end_matches(".conf").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_counts_as_allowed() {
+        assert!(allowed("yes"));
+        assert!(allowed("challenge"));
+        assert!(!allowed("no"));
+        assert!(!allowed("na"));
+    }
+
+    #[test]
+    fn only_blocking_inhibitors_count() {
+        let inhibitor = |what: &str, mode: &str| Inhibitor {
+            what: what.to_string(),
+            who: "Editor".to_string(),
+            why: "Cambios sin guardar".to_string(),
+            mode: mode.to_string(),
+            uid: 1000,
+            pid: 42,
+        };
+        assert!(blocks(&inhibitor("sleep:shutdown", "block"), "shutdown"));
+        assert!(!blocks(&inhibitor("shutdown", "delay"), "shutdown"));
+        assert!(!blocks(&inhibitor("idle:sleep", "block"), "shutdown"));
+        assert!(!blocks(&inhibitor("handle-power-key", "block"), "shutdown"));
+        assert!(blocks(&inhibitor("idle:sleep", "block"), "sleep"));
+    }
+
+    #[test]
+    fn entry_titles_come_from_the_entry_file() {
+        let read = |id: &str| {
+            (id == "arch.conf").then(|| "# comentario\ntitle   Arch Linux\nlinux /vmlinuz-linux\n".to_string())
+        };
+        assert_eq!(entry_title("arch.conf", read), "Arch Linux");
+        assert_eq!(entry_title("arch-fallback.conf", read), "arch-fallback");
+        assert_eq!(entry_title("auto-windows", read), "Windows Boot Manager");
+        assert_eq!(
+            entry_title("titled.conf", |_| Some("titlebar x\n".to_string())),
+            "titled"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Teclas multimedia atendidas por el shell.
+//!
+//! Wayfire las recibe y llama a `org.vasak.os.Desktop` (`VolumeUp`,
+//! `BrightnessDown`, `MediaPlayPause`, ...) en vez de correr un script por
+//! tecla. Así el paso, los límites y el OSD salen de un solo lugar: las
+//! preferencias del shell.
+
+use tauri::AppHandle;
+
+use crate::applets::audio::show_volume_osd;
+use crate::applets::keyboard_leds::announce_mic_state;
+use crate::applets::music::{
+    emit_now_playing, fetch_now_playing, mpris_next, mpris_playpause, mpris_previous,
+};
+use crate::audio_backend::{backend, DeviceKind};
+use crate::brightness::{self, BrightnessKind};
+use crate::commands::osd::show_osd_internal;
+use crate::logger::{log_debug, log_error};
+use crate::settings;
+use crate::structs::VolumeInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaKey {
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    MicMute,
+    BrightnessUp,
+    BrightnessDown,
+    KbdBrightnessUp,
+    KbdBrightnessDown,
+    /// Pasa al nivel siguiente y de ahí a apagada, como Fn+Espacio en muchas
+    /// notebooks
+    KbdBrightnessToggle,
+    MediaPlayPause,
+    MediaNext,
+    MediaPrevious,
+}
+
+impl MediaKey {
+    /// La tecla que corresponde a un método D-Bus, si es una.
+    pub fn from_member(member: &str) -> Option<Self> {
+        Some(match member {
+            "VolumeUp" => Self::VolumeUp,
+            "VolumeDown" => Self::VolumeDown,
+            "ToggleMute" => Self::ToggleMute,
+            "MicMute" => Self::MicMute,
+            "BrightnessUp" => Self::BrightnessUp,
+            "BrightnessDown" => Self::BrightnessDown,
+            "KbdBrightnessUp" => Self::KbdBrightnessUp,
+            "KbdBrightnessDown" => Self::KbdBrightnessDown,
+            "KbdBrightnessToggle" => Self::KbdBrightnessToggle,
+            "MediaPlayPause" => Self::MediaPlayPause,
+            "MediaNext" => Self::MediaNext,
+            "MediaPrevious" => Self::MediaPrevious,
+            _ => return None,
+        })
+    }
+}
+
+/// Aplica un paso sin salirse de `[min, max]`.
+///
+/// Si el valor ya está fuera del rango (se subió a 140 % con otra
+/// herramienta), subir no lo baja de golpe al límite: lo deja donde está.
+fn step_within(current: i64, delta: i64, min: i64, max: i64) -> i64 {
+    if delta >= 0 {
+        (current + delta).min(max.max(current))
+    } else {
+        (current + delta).max(min.min(current))
+    }
+}
+
+/// Atiende una tecla; los errores se registran y no se propagan, porque
+/// quien llama por D-Bus no espera respuesta.
+pub async fn press(key: MediaKey, app: AppHandle) {
+    log_debug(&format!("Tecla multimedia: {:?}", key));
+
+    let result = match key {
+        MediaKey::VolumeUp => change_volume(&app, 1).await,
+        MediaKey::VolumeDown => change_volume(&app, -1).await,
+        MediaKey::ToggleMute => toggle_mute(&app).await,
+        MediaKey::MicMute => toggle_mic(&app).await,
+        MediaKey::BrightnessUp => change_brightness(&app, 1).await,
+        MediaKey::BrightnessDown => change_brightness(&app, -1).await,
+        MediaKey::KbdBrightnessUp | MediaKey::KbdBrightnessDown | MediaKey::KbdBrightnessToggle => {
+            change_keyboard_light(&app, key).await
+        }
+        MediaKey::MediaPlayPause | MediaKey::MediaNext | MediaKey::MediaPrevious => {
+            media(&app, key).await
+        }
+    };
+
+    if let Err(e) = result {
+        log_error(&format!("Tecla multimedia {:?}: {}", key, e));
+    }
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> crate::error::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+async fn change_volume(app: &AppHandle, direction: i64) -> Result<(), String> {
+    let audio = settings::get().audio;
+    let info = blocking(crate::audio::get_volume).await?;
+
+    let target = step_within(info.current, direction * audio.volume_step, 0, audio.volume_limit());
+    if target != info.current {
+        let handle = app.clone();
+        blocking(move || crate::audio::set_volume(target, handle)).await?;
+    }
+    // Tocar el volumen es querer oírlo.
+    if info.is_muted {
+        blocking(|| backend().set_mute(DeviceKind::Sink, false)).await?;
+    }
+
+    // El monitor todavía no vio el cambio: el OSD se arma con lo escrito.
+    let info = VolumeInfo {
+        current: target,
+        is_muted: false,
+        ..info
+    };
+    show_volume_osd(app, &info).await;
+    Ok(())
+}
+
+async fn toggle_mute(app: &AppHandle) -> Result<(), String> {
+    let info = blocking(crate::audio::get_volume).await?;
+    let handle = app.clone();
+    let is_muted = blocking(move || crate::audio::toggle_mute(handle)).await?;
+
+    show_volume_osd(app, &VolumeInfo { is_muted, ..info }).await;
+    Ok(())
+}
+
+async fn toggle_mic(app: &AppHandle) -> Result<(), String> {
+    let handle = app.clone();
+    let muted = blocking(move || crate::audio::toggle_mic_mute(handle)).await?;
+    announce_mic_state(app, muted).await;
+    Ok(())
+}
+
+async fn change_brightness(app: &AppHandle, direction: i64) -> Result<(), String> {
+    let brightness = settings::get().brightness;
+    let info = blocking(brightness::get_brightness).await?;
+
+    // El mínimo lo garantiza la curva de brillo: 0 no es negro.
+    let target = step_within(info.current as i64, direction * brightness.step as i64, 0, 100) as u32;
+    crate::auto_brightness::note_manual_change(target);
+    if target != info.current {
+        blocking(move || brightness::set_brightness(target)).await?;
+    }
+
+    let _ = show_osd_internal("display-brightness", target as f64, 100.0, "osd.brightness", app).await;
+    Ok(())
+}
+
+/// Nivel siguiente de la luz del teclado. Va por los niveles del driver (casi
+/// siempre dos o tres), no por porcentaje.
+fn next_keyboard_level(key: MediaKey, current: u32, max: u32) -> u32 {
+    match key {
+        MediaKey::KbdBrightnessUp => (current + 1).min(max),
+        MediaKey::KbdBrightnessDown => current.saturating_sub(1),
+        _ if current >= max => 0,
+        _ => current + 1,
+    }
+}
+
+async fn change_keyboard_light(app: &AppHandle, key: MediaKey) -> Result<(), String> {
+    let device = blocking(|| brightness::first_of(BrightnessKind::Keyboard)).await?;
+    let reading = device.clone();
+    let (current, max) = blocking(move || brightness::read_raw(&reading)).await?;
+
+    let target = next_keyboard_level(key, current, max);
+    if target != current {
+        let writing = device.clone();
+        blocking(move || brightness::write_raw(&writing, target)).await?;
+    }
+
+    let (icon, label) = device.kind.osd();
+    let percent = brightness::to_percent(target, max);
+    let _ = show_osd_internal(icon, percent as f64, 100.0, label, app).await;
+    Ok(())
+}
+
+async fn media(app: &AppHandle, key: MediaKey) -> Result<(), String> {
+    // El reproductor activo lo resuelve music.rs con una cadena vacía.
+    let (result, icon, label) = match key {
+        MediaKey::MediaNext => (mpris_next(String::new()).await, "media-skip-forward", "osd.mediaNext"),
+        MediaKey::MediaPrevious => {
+            (mpris_previous(String::new()).await, "media-skip-backward", "osd.mediaPrevious")
+        }
+        _ => {
+            let was_playing = fetch_now_playing()
+                .await
+                .ok()
+                .and_then(|info| info.get("status")?.as_str().map(|s| s == "Playing"))
+                .unwrap_or(false);
+            let (icon, label) = if was_playing {
+                ("media-playback-pause", "osd.mediaPaused")
+            } else {
+                ("media-playback-start", "osd.mediaPlaying")
+            };
+            (mpris_playpause(String::new()).await, icon, label)
+        }
+    };
+
+    let target = result?;
+    let _ = emit_now_playing(app, &target).await;
+    let _ = show_osd_internal(icon, 0.0, 1.0, label, app).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_stop_at_the_limits() {
+        assert_eq!(step_within(50, 5, 0, 100), 55);
+        assert_eq!(step_within(98, 5, 0, 100), 100);
+        assert_eq!(step_within(3, -5, 0, 100), 0);
+        assert_eq!(step_within(7, -5, 5, 100), 5);
+    }
+
+    /// Un valor que ya estaba fuera de rango no salta al límite en la
+    /// dirección contraria a la tecla.
+    #[test]
+    fn out_of_range_values_are_not_pulled_back_the_wrong_way() {
+        assert_eq!(step_within(140, 5, 0, 100), 140);
+        assert_eq!(step_within(140, -5, 0, 100), 135);
+        assert_eq!(step_within(2, 5, 5, 100), 7);
+        assert_eq!(step_within(2, -5, 5, 100), 2);
+    }
+
+    #[test]
+    fn keyboard_light_cycles_through_driver_levels() {
+        assert_eq!(next_keyboard_level(MediaKey::KbdBrightnessUp, 1, 2), 2);
+        assert_eq!(next_keyboard_level(MediaKey::KbdBrightnessUp, 2, 2), 2);
+        assert_eq!(next_keyboard_level(MediaKey::KbdBrightnessDown, 0, 2), 0);
+        assert_eq!(next_keyboard_level(MediaKey::KbdBrightnessToggle, 1, 2), 2);
+        assert_eq!(next_keyboard_level(MediaKey::KbdBrightnessToggle, 2, 2), 0);
+    }
+
+    #[test]
+    fn only_known_members_are_keys() {
+        assert_eq!(MediaKey::from_member("VolumeUp"), Some(MediaKey::VolumeUp));
+        assert_eq!(MediaKey::from_member("MediaPrevious"), Some(MediaKey::MediaPrevious));
+        assert_eq!(MediaKey::from_member("OpenMenu"), None);
+    }
+}
@@ -0,0 +1,464 @@
+//! Live microphone level meter.
+//!
+//! While the control center or the audio applet is on screen, a capture stream
+//! on the default source measures peak and RMS levels and the frontend receives
+//! them as throttled `mic-level` events. When the last of those views closes,
+//! the stream is torn down — nothing records the microphone in the background.
+//!
+//! Two meters exist:
+//!
+//! 1. `PipeWireMeter` (feature = "pipewire-native") - a pipewire-rs capture
+//!    stream on its own thread, like `audio_native::PipeWireMonitor`.
+//! 2. `StubMeter` - synthetic levels, selected with `VASAK_MIC_LEVEL_STUB=1`,
+//!    so the meter can be exercised on machines without audio hardware.
+//!
+//! Both only accumulate samples; a tokio task drains the accumulator on a fixed
+//! interval and emits, so the event rate does not depend on the buffer size
+//! the audio server picked.
+
+use crate::logger::{log_debug, log_info};
+use crate::structs::MicLevel;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Views that keep the meter running while shown.
+pub const WATCHER_CONTROL_CENTER: &str = "control_center";
+pub const WATCHER_AUDIO_APPLET: &str = "applet_audio";
+
+/// ~20 events per second: smooth enough for a bar, cheap for the webview.
+const EMIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Samples gathered since the last emit.
+#[derive(Default)]
+struct LevelAccumulator {
+    peak: f32,
+    sum_squares: f64,
+    samples: u64,
+}
+
+impl LevelAccumulator {
+    fn add_sample(&mut self, sample: f32) {
+        let magnitude = sample.abs().min(1.0);
+        self.peak = self.peak.max(magnitude);
+        self.sum_squares += (magnitude as f64) * (magnitude as f64);
+        self.samples += 1;
+    }
+
+    fn add_samples(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.add_sample(sample);
+        }
+    }
+
+    /// F32LE samples straight from a capture buffer, without copying them out.
+    #[cfg_attr(not(feature = "pipewire-native"), allow(dead_code))]
+    fn add_f32le(&mut self, bytes: &[u8]) {
+        for b in bytes.chunks_exact(4) {
+            self.add_sample(f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        }
+    }
+
+    /// Level of everything added since the previous call, or `None` if no
+    /// samples arrived (stream still starting, or the source is suspended).
+    fn take(&mut self) -> Option<MicLevel> {
+        if self.samples == 0 {
+            return None;
+        }
+
+        let level = MicLevel {
+            peak: self.peak,
+            rms: (self.sum_squares / self.samples as f64).sqrt() as f32,
+        };
+        *self = Self::default();
+        Some(level)
+    }
+}
+
+type SharedAccumulator = Arc<Mutex<LevelAccumulator>>;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// PipeWireMeter - capture stream (requires feature "pipewire-native")
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(feature = "pipewire-native")]
+struct PipeWireMeter {
+    running: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "pipewire-native")]
+impl PipeWireMeter {
+    fn start(accumulator: SharedAccumulator) -> std::io::Result<Self> {
+        let running = Arc::new(AtomicBool::new(true));
+        let running_thread = running.clone();
+
+        std::thread::Builder::new()
+            .name("pipewire-mic-level".into())
+            .spawn(move || {
+                if let Err(e) = Self::run_loop(accumulator, running_thread) {
+                    crate::logger::log_error(&format!("PipeWireMeter: {}", e));
+                }
+            })?;
+
+        Ok(Self { running })
+    }
+
+    /// Capture stream on the default source, mono F32 so every sample is a
+    /// level reading without caring about the channel layout.
+    fn run_loop(
+        accumulator: SharedAccumulator,
+        running: Arc<AtomicBool>,
+    ) -> Result<(), pipewire::Error> {
+        use pipewire as pw;
+        use pw::properties::properties;
+        use pw::spa;
+
+        pw::init();
+
+        let mainloop = pw::main_loop::MainLoop::new(None)?;
+        let context = pw::context::Context::new(&mainloop)?;
+        let core = context.connect(None)?;
+
+        let props = properties! {
+            *pw::keys::MEDIA_TYPE => "Audio",
+            *pw::keys::MEDIA_CATEGORY => "Capture",
+            *pw::keys::MEDIA_ROLE => "DSP",
+            *pw::keys::NODE_NAME => "vasak-mic-level",
+        };
+        let stream = pw::stream::Stream::new(&core, "vasak-mic-level", props)?;
+
+        let _listener = stream
+            .add_local_listener_with_user_data(accumulator)
+            .process(|stream, accumulator| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let datas = buffer.datas_mut();
+                let Some(data) = datas.first_mut() else {
+                    return;
+                };
+
+                let size = data.chunk().size() as usize;
+                if let Some(bytes) = data.data() {
+                    // This runs on PipeWire's realtime thread: no allocating
+                    // and no waiting. If the emitter holds the lock, this
+                    // buffer is skipped; the next one is a few ms away.
+                    if let Ok(mut acc) = accumulator.try_lock() {
+                        acc.add_f32le(&bytes[..size.min(bytes.len())]);
+                    }
+                }
+            })
+            .register()?;
+
+        let mut audio_info = spa::param::audio::AudioInfoRaw::new();
+        audio_info.set_format(spa::param::audio::AudioFormat::F32LE);
+        audio_info.set_channels(1);
+        let format = spa::pod::Object {
+            type_: spa::utils::SpaTypes::ObjectParamFormat.as_raw(),
+            id: spa::param::ParamType::EnumFormat.as_raw(),
+            properties: audio_info.into(),
+        };
+        let values: Vec<u8> = spa::pod::serialize::PodSerializer::serialize(
+            std::io::Cursor::new(Vec::new()),
+            &spa::pod::Value::Object(format),
+        )
+        .map_err(|_| pw::Error::CreationFailed)?
+        .0
+        .into_inner();
+        let mut params = [spa::pod::Pod::from_bytes(&values).ok_or(pw::Error::CreationFailed)?];
+
+        stream.connect(
+            spa::utils::Direction::Input,
+            None,
+            pw::stream::StreamFlags::AUTOCONNECT
+                | pw::stream::StreamFlags::MAP_BUFFERS
+                | pw::stream::StreamFlags::RT_PROCESS,
+            &mut params,
+        )?;
+
+        log_info("PipeWireMeter: capturing default source");
+
+        while running.load(Ordering::Relaxed) {
+            mainloop.iterate(Duration::from_millis(100));
+        }
+
+        log_info("PipeWireMeter: stopped");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "pipewire-native")]
+impl Drop for PipeWireMeter {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// StubMeter - synthetic levels for testing without hardware
+// ─────────────────────────────────────────────────────────────────────────────
+
+struct StubMeter {
+    running: Arc<AtomicBool>,
+}
+
+/// 10 ms of a 440 Hz tone at 48 kHz whose loudness rises and falls every few
+/// seconds, like someone talking, so peak and RMS both move.
+fn synthetic_block(elapsed: f32) -> Vec<f32> {
+    const RATE: f32 = 48_000.0;
+    const LEN: usize = 480;
+
+    let envelope = 0.5 + 0.45 * (elapsed * 1.3).sin() * (elapsed * 0.37).cos();
+    (0..LEN)
+        .map(|i| {
+            let t = elapsed + i as f32 / RATE;
+            envelope * (t * 440.0 * std::f32::consts::TAU).sin()
+        })
+        .collect()
+}
+
+impl StubMeter {
+    fn start(accumulator: SharedAccumulator) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let running_task = running.clone();
+
+        tauri::async_runtime::spawn(async move {
+            log_info("StubMeter: generating synthetic microphone levels");
+            let started = std::time::Instant::now();
+            let mut tick = tokio::time::interval(Duration::from_millis(10));
+
+            while running_task.load(Ordering::Relaxed) {
+                tick.tick().await;
+                let block = synthetic_block(started.elapsed().as_secs_f32());
+                if let Ok(mut acc) = accumulator.lock() {
+                    acc.add_samples(&block);
+                }
+            }
+        });
+
+        Self { running }
+    }
+}
+
+impl Drop for StubMeter {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// LevelMeter - whichever meter is running, plus the emitter task
+// ─────────────────────────────────────────────────────────────────────────────
+
+enum Source {
+    #[cfg(feature = "pipewire-native")]
+    PipeWire(PipeWireMeter),
+    Stub(StubMeter),
+}
+
+struct LevelMeter {
+    _source: Source,
+    emitting: Arc<AtomicBool>,
+}
+
+impl LevelMeter {
+    fn start(app: &AppHandle) -> Option<Self> {
+        let accumulator = SharedAccumulator::default();
+        let source = Self::start_source(accumulator.clone())?;
+
+        let emitting = Arc::new(AtomicBool::new(true));
+        let emitting_task = emitting.clone();
+        let app = app.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let mut tick = tokio::time::interval(EMIT_INTERVAL);
+            while emitting_task.load(Ordering::Relaxed) {
+                tick.tick().await;
+                let level = accumulator.lock().ok().and_then(|mut acc| acc.take());
+                if let Some(level) = level {
+                    let _ = app.emit("mic-level", level);
+                }
+            }
+        });
+
+        Some(Self {
+            _source: source,
+            emitting,
+        })
+    }
+
+    fn start_source(accumulator: SharedAccumulator) -> Option<Source> {
+        if std::env::var_os("VASAK_MIC_LEVEL_STUB").is_some() {
+            return Some(Source::Stub(StubMeter::start(accumulator)));
+        }
+
+        #[cfg(feature = "pipewire-native")]
+        {
+            match PipeWireMeter::start(accumulator) {
+                Ok(meter) => return Some(Source::PipeWire(meter)),
+                Err(e) => crate::logger::log_error(&format!("PipeWireMeter: {}", e)),
+            }
+        }
+
+        #[cfg(not(feature = "pipewire-native"))]
+        {
+            let _ = accumulator;
+            log_debug("Mic level meter needs the pipewire-native feature");
+        }
+
+        None
+    }
+}
+
+impl Drop for LevelMeter {
+    fn drop(&mut self) {
+        self.emitting.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Who is watching, and the meter running for them. Generic over the meter so
+/// the bookkeeping can be tested without an audio server.
+struct MeterState<M = LevelMeter> {
+    watchers: HashSet<&'static str>,
+    meter: Option<M>,
+}
+
+impl<M> Default for MeterState<M> {
+    fn default() -> Self {
+        Self {
+            watchers: HashSet::new(),
+            meter: None,
+        }
+    }
+}
+
+impl<M> MeterState<M> {
+    /// Adds a watcher, starting the meter if none is running. Returns whether
+    /// it started one.
+    fn watch(&mut self, watcher: &'static str, start: impl FnOnce() -> Option<M>) -> bool {
+        self.watchers.insert(watcher);
+        if self.meter.is_some() {
+            return false;
+        }
+        self.meter = start();
+        self.meter.is_some()
+    }
+
+    /// Removes a watcher, dropping the meter with the last one. Returns whether
+    /// it stopped the meter.
+    fn unwatch(&mut self, watcher: &'static str) -> bool {
+        self.watchers.remove(watcher);
+        self.watchers.is_empty() && self.meter.take().is_some()
+    }
+}
+
+static METER: LazyLock<Mutex<MeterState>> = LazyLock::new(Default::default);
+
+/// A view that shows the level came on screen; starts the meter if it is the
+/// first one.
+pub fn watch(app: &AppHandle, watcher: &'static str) {
+    let Ok(mut state) = METER.lock() else {
+        return;
+    };
+
+    if state.watch(watcher, || LevelMeter::start(app)) {
+        log_debug(&format!("Mic level meter started for {}", watcher));
+    }
+}
+
+/// A view that shows the level went away; stops the meter if it was the last.
+///
+/// Every way a view leaves the screen has to end up here — the toggles, and
+/// also the windows dismissing themselves on Escape or focus loss — or the
+/// microphone stays open with nobody looking.
+pub fn unwatch(watcher: &'static str) {
+    let Ok(mut state) = METER.lock() else {
+        return;
+    };
+
+    if state.unwatch(watcher) {
+        log_debug("Mic level meter stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dismissing_the_last_view_drops_the_meter() {
+        let mut state = MeterState::<()>::default();
+        assert!(state.watch(WATCHER_CONTROL_CENTER, || Some(())));
+        assert!(!state.watch(WATCHER_AUDIO_APPLET, || panic!("already running")));
+
+        // The applet loses focus and closes itself; the centre still shows.
+        assert!(!state.unwatch(WATCHER_AUDIO_APPLET));
+        assert!(state.meter.is_some());
+
+        // Escape on the centre.
+        assert!(state.unwatch(WATCHER_CONTROL_CENTER));
+        assert!(state.meter.is_none());
+        assert!(state.watchers.is_empty());
+
+        // A repeated dismissal is harmless.
+        assert!(!state.unwatch(WATCHER_CONTROL_CENTER));
+    }
+
+    #[test]
+    fn accumulator_reports_peak_and_rms_then_resets() {
+        let mut acc = LevelAccumulator::default();
+        assert!(acc.take().is_none(), "no samples, no level");
+
+        acc.add_samples(&[0.5, -0.5, 0.5, -0.5]);
+        acc.add_samples(&[-0.8]);
+        let level = acc.take().unwrap();
+
+        assert!((level.peak - 0.8).abs() < 1e-6);
+        let expected_rms = ((4.0 * 0.25 + 0.64) / 5.0_f64).sqrt() as f32;
+        assert!((level.rms - expected_rms).abs() < 1e-6);
+
+        assert!(acc.take().is_none(), "taking resets the interval");
+    }
+
+    #[test]
+    fn capture_bytes_are_read_as_f32le() {
+        let mut acc = LevelAccumulator::default();
+        let bytes: Vec<u8> = [0.25_f32, -0.5]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .chain([0xff, 0xff])
+            .collect();
+        acc.add_f32le(&bytes);
+        let level = acc.take().unwrap();
+        assert_eq!(level.peak, 0.5);
+        let expected_rms = ((0.0625 + 0.25) / 2.0_f64).sqrt() as f32;
+        assert!((level.rms - expected_rms).abs() < 1e-6, "trailing partial sample is ignored");
+    }
+
+    #[test]
+    fn clipped_samples_count_as_full_scale() {
+        let mut acc = LevelAccumulator::default();
+        acc.add_samples(&[1.7, -2.0]);
+        let level = acc.take().unwrap();
+        assert_eq!(level.peak, 1.0);
+        assert_eq!(level.rms, 1.0);
+    }
+
+    #[test]
+    fn synthetic_levels_move_and_stay_in_range() {
+        let mut acc = LevelAccumulator::default();
+        let levels: Vec<MicLevel> = (0..40)
+            .map(|step| {
+                acc.add_samples(&synthetic_block(step as f32 * 0.1));
+                acc.take().unwrap()
+            })
+            .collect();
+
+        assert!(levels.iter().all(|l| (0.0..=1.0).contains(&l.peak) && l.rms <= l.peak));
+        let quietest = levels.iter().map(|l| l.peak).fold(f32::MAX, f32::min);
+        let loudest = levels.iter().map(|l| l.peak).fold(0.0, f32::max);
+        assert!(loudest - quietest > 0.2, "the stub should not be a flat line");
+    }
+}
@@ -121,6 +121,20 @@ pub fn rebuild_shell_surfaces(app: &AppHandle) {
     }
 }
 
+/// New outputs pick up the night-light ramp on their own once the compositor
+/// reports them, but a mode change can reset the tables of an existing one.
+/// Sent from a worker: connecting to the compositor the first time is a
+/// round trip that shouldn't hold the GTK loop.
+fn reapply_gamma() {
+    tauri::async_runtime::spawn_blocking(|| {
+        if let Some(gamma) = crate::gamma::service() {
+            if let Err(error) = gamma.reapply() {
+                log_error(&format!("No se pudo reaplicar la gamma: {}", error));
+            }
+        }
+    });
+}
+
 /// Watches for monitors being connected, disconnected or reconfigured and
 /// rebuilds the shell surfaces to match.
 ///
@@ -149,6 +163,7 @@ pub fn watch_monitor_changes(app: &AppHandle) {
             gtk::glib::timeout_add_local_once(REBUILD_DEBOUNCE, move || {
                 pending.set(false);
                 rebuild_shell_surfaces(&app);
+                reapply_gamma();
             });
         }
     };
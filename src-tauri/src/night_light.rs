@@ -0,0 +1,245 @@
+//! Luz nocturna: de noche la pantalla pasa a colores más cálidos.
+//!
+//! Este módulo solo decide qué temperatura de color corresponde a cada momento;
+//! la aplica `gamma` sobre todas las salidas. El horario puede ser fijo o ir
+//! de la puesta del sol al amanecer, que se calculan sin conexión a partir de
+//! las coordenadas de las preferencias. Los cambios entre día y noche son
+//! graduales: duran `transition_minutes`, centrados en la hora del cambio.
+
+use crate::logger::log_warning;
+use crate::settings::{NightLightSchedule, NightLightSettings};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::Notify;
+
+/// Temperatura de la luz de día: con ella las tablas de gamma quedan como
+/// vienen.
+pub const DAYLIGHT: u32 = 6500;
+
+/// Temperatura más cálida que se acepta; por debajo todo se ve naranja.
+const WARMEST: u32 = 1000;
+
+const MINUTES_PER_DAY: i32 = 24 * 60;
+
+/// "HH:MM" a minutos desde la medianoche.
+pub(crate) fn parse_time(value: &str) -> Option<i32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    ((0..24).contains(&hours) && (0..60).contains(&minutes)).then_some(hours * 60 + minutes)
+}
+
+/// Diferencia `a - b` en minutos, dando la vuelta por la medianoche:
+/// siempre entre -720 y 719.
+fn circular_distance(a: i32, b: i32) -> i32 {
+    (a - b + MINUTES_PER_DAY / 2).rem_euclid(MINUTES_PER_DAY) - MINUTES_PER_DAY / 2
+}
+
+/// Cuánto de noche es `now` (0 de día, 1 de noche) si la noche va de `start`
+/// a `end`. Alrededor de cada cambio hay una rampa de `transition` minutos.
+pub(crate) fn night_factor(now: i32, start: i32, end: i32, transition: i32) -> f64 {
+    let half = transition.max(0) as f64 / 2.0;
+    let ramp = |distance: i32| (distance as f64 + half) / (2.0 * half);
+
+    if half > 0.0 {
+        let from_start = circular_distance(now, start);
+        if (from_start as f64).abs() < half {
+            return ramp(from_start).clamp(0.0, 1.0);
+        }
+        let from_end = circular_distance(now, end);
+        if (from_end as f64).abs() < half {
+            return (1.0 - ramp(from_end)).clamp(0.0, 1.0);
+        }
+    }
+
+    let night = if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    };
+    if night {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Día juliano (días desde el mediodía del 1 de enero de 4713 a. C.) a UTC.
+fn julian_to_utc(julian: f64) -> Option<DateTime<Utc>> {
+    let seconds = (julian - 2_440_587.5) * 86_400.0;
+    DateTime::from_timestamp(seconds.round() as i64, 0)
+}
+
+/// Amanecer y puesta del sol en `date` para una latitud y longitud (este
+/// positivo), con la ecuación del amanecer de la NOAA simplificada. Tiene un
+/// error de un par de minutos, más que suficiente para esto. `None` en
+/// latitudes donde ese día el sol no sale o no se pone.
+pub(crate) fn sun_times(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let rad = PI / 180.0;
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let days = (date - j2000).num_days() as f64;
+
+    // Mediodía solar medio
+    let mean_noon = days - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon).rem_euclid(360.0);
+    let center = 1.9148 * (anomaly * rad).sin()
+        + 0.02 * (2.0 * anomaly * rad).sin()
+        + 0.0003 * (3.0 * anomaly * rad).sin();
+    let ecliptic = (anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+    let transit = 2_451_545.0 + mean_noon + 0.0053 * (anomaly * rad).sin()
+        - 0.0069 * (2.0 * ecliptic * rad).sin();
+
+    let declination = ((ecliptic * rad).sin() * (23.4397 * rad).sin()).asin();
+    // -0.833°: refracción y radio del disco solar
+    let cos_hour_angle = ((-0.833 * rad).sin() - (latitude * rad).sin() * declination.sin())
+        / ((latitude * rad).cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos() / rad;
+
+    Some((
+        julian_to_utc(transit - hour_angle / 360.0)?,
+        julian_to_utc(transit + hour_angle / 360.0)?,
+    ))
+}
+
+fn minutes_of_day<Tz: TimeZone>(time: &DateTime<Tz>) -> i32 {
+    (time.hour() * 60 + time.minute()) as i32
+}
+
+/// Comienzo y fin de la noche en minutos locales, según el horario elegido.
+fn night_bounds(settings: &NightLightSettings, now: &DateTime<Local>) -> Option<(i32, i32)> {
+    if settings.schedule == NightLightSchedule::Sun {
+        if let (Some(latitude), Some(longitude)) = (settings.latitude, settings.longitude) {
+            let date = NaiveDate::from_ymd_opt(now.year(), now.month(), now.day())?;
+            if let Some((sunrise, sunset)) = sun_times(date, latitude, longitude) {
+                return Some((
+                    minutes_of_day(&sunset.with_timezone(&Local)),
+                    minutes_of_day(&sunrise.with_timezone(&Local)),
+                ));
+            }
+        }
+        // Sin coordenadas, o en pleno verano/invierno polar: horario fijo.
+    }
+
+    let start = parse_time(&settings.start);
+    let end = parse_time(&settings.end);
+    if start.is_none() || end.is_none() {
+        log_warning(&format!(
+            "Luz nocturna: horario inválido {}-{}",
+            settings.start, settings.end
+        ));
+    }
+    Some((start?, end?))
+}
+
+/// Temperatura que le corresponde a la pantalla en `now`.
+pub fn target_temperature(settings: &NightLightSettings, now: &DateTime<Local>) -> u32 {
+    if !settings.enabled {
+        return DAYLIGHT;
+    }
+
+    let factor = match settings.schedule {
+        NightLightSchedule::Always => 1.0,
+        _ => match night_bounds(settings, now) {
+            Some((start, end)) => night_factor(
+                minutes_of_day(now),
+                start,
+                end,
+                settings.transition_minutes as i32,
+            ),
+            None => 0.0,
+        },
+    };
+
+    let night = settings.temperature.clamp(WARMEST, DAYLIGHT) as f64;
+    (DAYLIGHT as f64 - (DAYLIGHT as f64 - night) * factor).round() as u32
+}
+
+static CURRENT: AtomicU32 = AtomicU32::new(DAYLIGHT);
+
+/// La temperatura que tienen las salidas ahora mismo.
+pub fn current_temperature() -> u32 {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_current_temperature(kelvin: u32) {
+    CURRENT.store(kelvin, Ordering::Relaxed);
+}
+
+/// Despierta al applet para que recalcule ya, sin esperar al próximo
+/// control.
+pub(crate) fn changed() -> &'static Notify {
+    static CHANGED: OnceLock<Notify> = OnceLock::new();
+    CHANGED.get_or_init(Notify::new)
+}
+
+/// Las preferencias cambiaron.
+pub fn reload() {
+    changed().notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_parse_and_reject_garbage() {
+        assert_eq!(parse_time("20:30"), Some(20 * 60 + 30));
+        assert_eq!(parse_time("07:00"), Some(420));
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("tarde"), None);
+    }
+
+    #[test]
+    fn night_wraps_around_midnight_with_gradual_edges() {
+        let (start, end) = (20 * 60, 7 * 60);
+        assert_eq!(night_factor(12 * 60, start, end, 30), 0.0);
+        assert_eq!(night_factor(23 * 60, start, end, 30), 1.0);
+        assert_eq!(night_factor(3 * 60, start, end, 30), 1.0);
+        assert_eq!(night_factor(20 * 60, start, end, 30), 0.5);
+        assert_eq!(night_factor(7 * 60, start, end, 30), 0.5);
+
+        let dusk: Vec<f64> = (19 * 60 + 40..=20 * 60 + 20)
+            .step_by(5)
+            .map(|m| night_factor(m, start, end, 30))
+            .collect();
+        assert!(dusk.windows(2).all(|w| w[0] <= w[1]), "{:?}", dusk);
+        assert!(dusk.iter().any(|f| *f > 0.0 && *f < 1.0));
+    }
+
+    #[test]
+    fn sun_times_match_known_values() {
+        // Buenos Aires, solsticio de invierno: amanece ~11:00 UTC y se pone
+        // ~20:50 UTC.
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let (sunrise, sunset) = sun_times(date, -34.6, -58.4).unwrap();
+        assert!((minutes_of_day(&sunrise) - (11 * 60 + 1)).abs() <= 10, "{}", sunrise);
+        assert!((minutes_of_day(&sunset) - (20 * 60 + 50)).abs() <= 10, "{}", sunset);
+
+        // Tromsø en junio: sol de medianoche.
+        assert!(sun_times(date, 69.6, 18.9).is_none());
+    }
+
+    #[test]
+    fn disabled_or_daytime_is_daylight() {
+        let mut settings = NightLightSettings::default();
+        let noon = Local.with_ymd_and_hms(2024, 6, 21, 12, 0, 0).unwrap();
+        let night = Local.with_ymd_and_hms(2024, 6, 21, 23, 0, 0).unwrap();
+
+        assert_eq!(target_temperature(&settings, &night), DAYLIGHT);
+        settings.enabled = true;
+        assert_eq!(target_temperature(&settings, &noon), DAYLIGHT);
+        assert_eq!(target_temperature(&settings, &night), settings.temperature);
+
+        settings.schedule = NightLightSchedule::Always;
+        assert_eq!(target_temperature(&settings, &noon), settings.temperature);
+    }
+}
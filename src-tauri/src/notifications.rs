@@ -13,7 +13,7 @@ use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, LazyLock};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::RwLock;
 use zbus::zvariant::Value;
 use zbus::{Connection, Proxy};
@@ -134,6 +134,14 @@ pub async fn invoke_action(id: u32, action_key: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Translates a locale key for notifications the backend raises on its own.
+/// Falls back to the key, like the frontend does when a translation is missing.
+pub fn localized(app: &AppHandle, key: &str) -> String {
+    app.try_state::<tauri_plugin_i18n_vsk::PluginI18n<tauri::Wry>>()
+        .and_then(|i18n| i18n.translate(key).map(str::to_string))
+        .unwrap_or_else(|| key.to_string())
+}
+
 pub async fn send_system_notification(
     summary: String,
     body: Option<String>,
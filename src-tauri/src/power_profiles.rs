@@ -0,0 +1,380 @@
+//! Modos de energía de power-profiles-daemon.
+//!
+//! El servicio se llama `org.freedesktop.UPower.PowerProfiles` desde la
+//! versión 0.20; las anteriores solo publican `net.hadess.PowerProfiles`, con
+//! la misma interfaz. Se prueba primero el nombre nuevo.
+//!
+//! Opcionalmente, con batería por debajo de un porcentaje se pasa a ahorro de
+//! energía, y al conectar el cargador se vuelve al modo que había antes.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_info, log_warning};
+use crate::session_actions::system_bus;
+use crate::settings;
+use crate::structs::{BatteryInfo, PowerProfile, PowerProfileHold, PowerProfilesState};
+use futures_util::stream::select_all;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use zbus::zvariant::OwnedValue;
+use zbus::{proxy, Connection};
+
+pub const POWER_SAVER: &str = "power-saver";
+
+#[proxy(
+    interface = "org.freedesktop.UPower.PowerProfiles",
+    default_service = "org.freedesktop.UPower.PowerProfiles",
+    default_path = "/org/freedesktop/UPower/PowerProfiles"
+)]
+trait PowerProfiles {
+    /// ActiveProfile property
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+
+    /// PerformanceDegraded property: motivo, o vacío si no está degradado
+    #[zbus(property)]
+    fn performance_degraded(&self) -> zbus::Result<String>;
+
+    /// Profiles property
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    /// ActiveProfileHolds property
+    #[zbus(property)]
+    fn active_profile_holds(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+/// Proxy al daemon con el nombre que esté publicado.
+pub(crate) async fn connect(connection: &Connection) -> zbus::Result<PowerProfilesProxy<'static>> {
+    let proxy = PowerProfilesProxy::new(connection).await?;
+    match proxy.active_profile().await {
+        Ok(_) => Ok(proxy),
+        Err(_) => {
+            let legacy = PowerProfilesProxy::builder(connection)
+                .destination("net.hadess.PowerProfiles")?
+                .path("/net/hadess/PowerProfiles")?
+                .interface("net.hadess.PowerProfiles")?
+                .build()
+                .await?;
+            legacy.active_profile().await?;
+            Ok(legacy)
+        }
+    }
+}
+
+fn string_of(props: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    props
+        .get(key)
+        .and_then(|value| <&str>::try_from(value).ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_profile(props: &HashMap<String, OwnedValue>) -> Option<PowerProfile> {
+    Some(PowerProfile {
+        name: string_of(props, "Profile")?,
+        // Desde la 0.20 hay un driver de CPU y otro de plataforma
+        driver: string_of(props, "Driver")
+            .or_else(|| string_of(props, "PlatformDriver"))
+            .or_else(|| string_of(props, "CpuDriver")),
+    })
+}
+
+fn parse_hold(props: &HashMap<String, OwnedValue>) -> Option<PowerProfileHold> {
+    Some(PowerProfileHold {
+        profile: string_of(props, "Profile")?,
+        application_id: string_of(props, "ApplicationId").unwrap_or_default(),
+        reason: string_of(props, "Reason").unwrap_or_default(),
+    })
+}
+
+/// Modo activo visto por última vez, para decidir sin ir al bus.
+static ACTIVE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Estado completo del daemon.
+pub async fn read_state(proxy: &PowerProfilesProxy<'_>) -> Result<PowerProfilesState> {
+    let active = proxy.active_profile().await?;
+    let profiles = proxy
+        .profiles()
+        .await?
+        .iter()
+        .filter_map(parse_profile)
+        .collect();
+    let holds = proxy
+        .active_profile_holds()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter_map(parse_hold)
+        .collect();
+    let degraded = proxy
+        .performance_degraded()
+        .await
+        .ok()
+        .filter(|reason| !reason.is_empty());
+
+    if let Ok(mut cached) = ACTIVE.lock() {
+        *cached = Some(active.clone());
+    }
+
+    Ok(PowerProfilesState {
+        available: true,
+        active: Some(active),
+        profiles,
+        degraded,
+        holds,
+    })
+}
+
+/// Estado para el frontend; sin daemon, `available` queda en falso.
+pub async fn state(connection: &Connection) -> PowerProfilesState {
+    let result = match connect(connection).await {
+        Ok(proxy) => read_state(&proxy).await,
+        Err(e) => Err(e.into()),
+    };
+    result.unwrap_or_else(|_| PowerProfilesState {
+        available: false,
+        active: None,
+        profiles: Vec::new(),
+        degraded: None,
+        holds: Vec::new(),
+    })
+}
+
+/// Cambia el modo activo. Solo se aceptan los modos que ofrece el equipo.
+pub async fn set_profile(connection: &Connection, profile: &str) -> Result<()> {
+    let proxy = connect(connection).await?;
+    let known = proxy
+        .profiles()
+        .await?
+        .iter()
+        .filter_map(parse_profile)
+        .any(|p| p.name == profile);
+    if !known {
+        return Err(VasakError::NotFound(format!("modo de energía {}", profile)));
+    }
+
+    proxy.set_active_profile(profile).await?;
+    if let Ok(mut cached) = ACTIVE.lock() {
+        *cached = Some(profile.to_string());
+    }
+    log_info(&format!("Modo de energía: {}", profile));
+    Ok(())
+}
+
+/// Espera a que cambie cualquier propiedad y llama a `on_change`. Termina
+/// cuando el daemon se va del bus.
+pub(crate) async fn watch<F: FnMut()>(proxy: &PowerProfilesProxy<'_>, mut on_change: F) {
+    let mut changes = select_all([
+        proxy.receive_active_profile_changed().await.map(|_| ()).boxed(),
+        proxy.receive_profiles_changed().await.map(|_| ()).boxed(),
+        proxy.receive_active_profile_holds_changed().await.map(|_| ()).boxed(),
+        proxy.receive_performance_degraded_changed().await.map(|_| ()).boxed(),
+    ]);
+    while changes.next().await.is_some() {
+        on_change();
+    }
+}
+
+/// Pasa a ahorro de energía con poca batería y recuerda qué había antes.
+#[derive(Debug, Default)]
+pub(crate) struct AutoSaver {
+    previous: Option<String>,
+}
+
+impl AutoSaver {
+    pub(crate) const fn new() -> Self {
+        Self { previous: None }
+    }
+
+    /// El modo al que hay que cambiar, si hay que cambiar.
+    pub(crate) fn update(
+        &mut self,
+        threshold: Option<u32>,
+        percentage: f64,
+        on_battery: bool,
+        active: &str,
+    ) -> Option<String> {
+        if !on_battery {
+            let previous = self.previous.take()?;
+            // Si el usuario ya eligió otro modo, se respeta.
+            return (active == POWER_SAVER && previous != POWER_SAVER).then_some(previous);
+        }
+
+        let threshold = threshold?;
+        if self.previous.is_some() || active == POWER_SAVER || percentage > threshold as f64 {
+            return None;
+        }
+        self.previous = Some(active.to_string());
+        Some(POWER_SAVER.to_string())
+    }
+}
+
+static AUTO_SAVER: Mutex<AutoSaver> = Mutex::new(AutoSaver::new());
+
+/// Revisa una lectura de la batería del equipo por si hay que entrar o salir
+/// del ahorro de energía.
+pub fn observe_battery(app: &AppHandle, info: &BatteryInfo) {
+    if !info.has_battery {
+        return;
+    }
+    let Some(active) = ACTIVE.lock().ok().and_then(|active| active.clone()) else {
+        return;
+    };
+
+    let threshold = settings::get().battery.power_saver_percent;
+    let on_battery = crate::battery_warnings::on_battery(info);
+    let target = match AUTO_SAVER.lock() {
+        Ok(mut saver) => saver.update(threshold, info.percentage, on_battery, &active),
+        Err(_) => return,
+    };
+
+    let Some(profile) = target else {
+        return;
+    };
+    let app = app.clone();
+    tokio::spawn(async move {
+        let Ok(connection) = system_bus(&app).await else {
+            return;
+        };
+        if let Err(e) = set_profile(&connection, &profile).await {
+            log_warning(&format!(
+                "No se pudo cambiar automáticamente a {}: {}",
+                profile, e
+            ));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saver_kicks_in_once_and_restores_on_charger() {
+        let mut saver = AutoSaver::new();
+
+        assert_eq!(saver.update(Some(20), 50.0, true, "balanced"), None);
+        assert_eq!(
+            saver.update(Some(20), 19.0, true, "balanced"),
+            Some(POWER_SAVER.to_string())
+        );
+        // El usuario volvió a "balanced" a mano: no se insiste.
+        assert_eq!(saver.update(Some(20), 15.0, true, "balanced"), None);
+        assert_eq!(saver.update(Some(20), 15.0, false, "balanced"), None);
+
+        assert_eq!(
+            saver.update(Some(20), 10.0, true, "performance"),
+            Some(POWER_SAVER.to_string())
+        );
+        assert_eq!(
+            saver.update(Some(20), 10.0, false, POWER_SAVER),
+            Some("performance".to_string())
+        );
+        assert_eq!(saver.update(Some(20), 30.0, false, "performance"), None);
+    }
+
+    #[test]
+    fn saver_is_off_without_threshold_or_when_already_saving() {
+        let mut saver = AutoSaver::new();
+        assert_eq!(saver.update(None, 3.0, true, "balanced"), None);
+        assert_eq!(saver.update(Some(20), 3.0, true, POWER_SAVER), None);
+        assert_eq!(saver.update(Some(20), 3.0, false, POWER_SAVER), None);
+    }
+
+    mod fake_daemon {
+        use super::*;
+        use std::os::unix::net::UnixStream;
+        use zbus::zvariant::Value;
+        use zbus::{connection, interface, Guid};
+
+        struct FakePowerProfiles {
+            active: String,
+        }
+
+        fn dict(entries: &[(&str, &str)]) -> HashMap<String, OwnedValue> {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), Value::from(*value).try_into().unwrap()))
+                .collect()
+        }
+
+        // Solo el nombre viejo, como power-profiles-daemon < 0.20.
+        #[interface(name = "net.hadess.PowerProfiles")]
+        impl FakePowerProfiles {
+            #[zbus(property)]
+            fn active_profile(&self) -> String {
+                self.active.clone()
+            }
+
+            #[zbus(property)]
+            fn set_active_profile(&mut self, profile: String) {
+                self.active = profile;
+            }
+
+            #[zbus(property)]
+            fn performance_degraded(&self) -> String {
+                "lap-detected".to_string()
+            }
+
+            #[zbus(property)]
+            fn profiles(&self) -> Vec<HashMap<String, OwnedValue>> {
+                vec![
+                    dict(&[("Profile", "power-saver"), ("Driver", "platform_profile")]),
+                    dict(&[("Profile", "balanced"), ("Driver", "platform_profile")]),
+                    dict(&[("Profile", "performance"), ("Driver", "platform_profile")]),
+                ]
+            }
+
+            #[zbus(property)]
+            fn active_profile_holds(&self) -> Vec<HashMap<String, OwnedValue>> {
+                vec![dict(&[
+                    ("Profile", "performance"),
+                    ("ApplicationId", "org.gnome.Builder"),
+                    ("Reason", "Compilando"),
+                ])]
+            }
+        }
+
+        #[test]
+        fn reads_and_sets_through_the_legacy_name() {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let (server_side, client_side) = UnixStream::pair().unwrap();
+                    let server = connection::Builder::unix_stream(server_side)
+                        .server(Guid::generate())
+                        .unwrap()
+                        .p2p()
+                        .serve_at(
+                            "/net/hadess/PowerProfiles",
+                            FakePowerProfiles { active: "balanced".to_string() },
+                        )
+                        .unwrap()
+                        .build();
+                    let client = connection::Builder::unix_stream(client_side).p2p().build();
+                    let (_server, client) = futures_util::future::try_join(server, client)
+                        .await
+                        .unwrap();
+
+                    let state = state(&client).await;
+                    assert!(state.available);
+                    assert_eq!(state.active.as_deref(), Some("balanced"));
+                    assert_eq!(state.profiles.len(), 3);
+                    assert_eq!(state.profiles[0].driver.as_deref(), Some("platform_profile"));
+                    assert_eq!(state.degraded.as_deref(), Some("lap-detected"));
+                    assert_eq!(state.holds[0].application_id, "org.gnome.Builder");
+
+                    set_profile(&client, POWER_SAVER).await.unwrap();
+                    let proxy = connect(&client).await.unwrap();
+                    assert_eq!(proxy.active_profile().await.unwrap(), POWER_SAVER);
+                    assert!(set_profile(&client, "turbo").await.is_err());
+                });
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Acciones de energía programadas.
+//!
+//! "Apagar en una hora", "suspender a las 23:30". Todas las lleva un
+//! temporizador propio que al llegar la hora pasa por
+//! `session_actions::perform`, así apagar y reiniciar cierran primero las
+//! aplicaciones como cuando se piden a mano. Para esas dos además se le pide a
+//! logind un `ScheduleShutdown` de respaldo para después de la espera de
+//! cierre: si el escritorio ya no está para cumplirlo, lo cumple logind. Al
+//! arrancar se lee el que haya en logind, por si el escritorio se reinició con
+//! uno pendiente. Cinco minutos antes llega una notificación, y el panel
+//! recibe `session-schedule` para mostrar la cuenta regresiva.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_error, log_info, log_warning};
+use crate::logind;
+use crate::night_light::parse_time;
+use crate::notifications::{localized, send_system_notification};
+use crate::session_actions::{self, SessionAction};
+use crate::shutdown;
+use crate::structs::ScheduledAction;
+use chrono::{DateTime, Local, TimeZone, Timelike};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const PATH: &str = "/org/freedesktop/login1";
+const MANAGER: &str = "org.freedesktop.login1.Manager";
+
+/// Cuánto antes se avisa.
+const WARNING: u64 = 5 * 60;
+
+/// Cuánto después de la hora se apaga logind si el escritorio no lo hizo:
+/// la espera de cierre de aplicaciones y un margen.
+const BACKSTOP: u64 = shutdown::CLOSE_TIMEOUT as u64 + 30;
+
+/// Cada cuánto se mira el reloj mientras se espera. El temporizador de tokio
+/// no cuenta el tiempo suspendido; el reloj de pared sí.
+const TICK: u64 = 30;
+
+/// Palabras con las que se nombra cada acción en la búsqueda.
+const ACTION_WORDS: &[(&str, SessionAction)] = &[
+    ("shutdown", SessionAction::PowerOff),
+    ("shut down", SessionAction::PowerOff),
+    ("poweroff", SessionAction::PowerOff),
+    ("power off", SessionAction::PowerOff),
+    ("apagar", SessionAction::PowerOff),
+    ("reboot", SessionAction::Reboot),
+    ("restart", SessionAction::Reboot),
+    ("reiniciar", SessionAction::Reboot),
+    ("suspend", SessionAction::Suspend),
+    ("sleep", SessionAction::Suspend),
+    ("suspender", SessionAction::Suspend),
+    ("hibernate", SessionAction::Hibernate),
+    ("hibernar", SessionAction::Hibernate),
+];
+
+/// Cuándo, tal como lo escribió el usuario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum When {
+    /// Dentro de tantos segundos
+    In(u64),
+    /// A esta hora, en minutos desde la medianoche
+    At(i32),
+}
+
+impl When {
+    /// Segundos que faltan desde `now`. Una hora que ya pasó es la de mañana.
+    pub(crate) fn delay<Tz: TimeZone>(self, now: &DateTime<Tz>) -> u64 {
+        match self {
+            When::In(seconds) => seconds,
+            When::At(minutes) => {
+                let target = i64::from(minutes) * 60;
+                let now = i64::from(now.num_seconds_from_midnight());
+                match (target - now).rem_euclid(86_400) {
+                    0 => 86_400,
+                    seconds => seconds as u64,
+                }
+            }
+        }
+    }
+}
+
+/// Entiende "shutdown in 30m", "reboot at 23:30", "apagar en 1 hora",
+/// "suspender a las 23:30"...
+pub(crate) fn parse_query(query: &str) -> Option<(SessionAction, When)> {
+    let query = query.trim().to_lowercase();
+    let (action, rest) = ACTION_WORDS
+        .iter()
+        .find_map(|(word, action)| Some((action.clone(), after_word(&query, word)?)))?;
+
+    if let Some(time) = ["at", "a las", "a la"]
+        .iter()
+        .find_map(|word| after_word(rest, word))
+    {
+        return Some((action, When::At(parse_time(time)?)));
+    }
+    let duration = ["in", "en", "dentro de"]
+        .iter()
+        .find_map(|word| after_word(rest, word))?;
+    Some((action, When::In(parse_duration(duration)?)))
+}
+
+/// Lo que sigue a `word` si el texto empieza con esa palabra entera.
+fn after_word<'a>(text: &'a str, word: &str) -> Option<&'a str> {
+    let rest = text.strip_prefix(word)?;
+    rest.starts_with(char::is_whitespace)
+        .then(|| rest.trim_start())
+}
+
+fn unit_seconds(unit: &str) -> Option<u64> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" | "segundo" | "segundos" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" | "minuto" | "minutos" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" | "hora" | "horas" => Some(3600),
+        _ => None,
+    }
+}
+
+/// "30m", "1h30m", "1 hour 30 minutes", "una hora"... a segundos.
+pub(crate) fn parse_duration(text: &str) -> Option<u64> {
+    // "1h30m" → "1 h 30 m"
+    let mut spaced = String::with_capacity(text.len() * 2);
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        if let Some(previous) = previous {
+            if !c.is_whitespace()
+                && !previous.is_whitespace()
+                && previous.is_ascii_digit() != c.is_ascii_digit()
+            {
+                spaced.push(' ');
+            }
+        }
+        spaced.push(c);
+        previous = Some(c);
+    }
+
+    let mut total = 0u64;
+    let mut count: Option<u64> = None;
+    for word in spaced.split_whitespace() {
+        if let Ok(number) = word.parse::<u64>() {
+            if count.replace(number).is_some() {
+                return None;
+            }
+            continue;
+        }
+        match (word, count) {
+            ("a" | "an" | "one" | "un" | "una", None) => count = Some(1),
+            ("and" | "y", None) => {}
+            _ => total = total.checked_add(count.take()?.checked_mul(unit_seconds(word)?)?)?,
+        }
+    }
+    (count.is_none() && total > 0).then_some(total)
+}
+
+/// Sube con cada programación y con cada cancelación; la espera que ve otro
+/// número sabe que la reemplazaron.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static PENDING: Mutex<Option<ScheduledAction>> = Mutex::new(None);
+
+/// Si logind tiene un respaldo nuestro. Sigue en pie después de la hora,
+/// mientras se cierran las aplicaciones.
+static BACKSTOP_ARMED: AtomicBool = AtomicBool::new(false);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// "23:30", para avisos y resultados de búsqueda.
+pub(crate) fn clock(at: u64) -> String {
+    Local
+        .timestamp_opt(at as i64, 0)
+        .single()
+        .map(|time| time.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// El tipo de `ScheduleShutdown` para las acciones que logind sabe programar.
+fn shutdown_type(action: &SessionAction) -> Option<&'static str> {
+    match action {
+        SessionAction::PowerOff => Some("poweroff"),
+        SessionAction::Reboot => Some("reboot"),
+        _ => None,
+    }
+}
+
+/// Lo contrario, para lo que se encuentra programado en logind. Los "dry-*"
+/// y "halt" no los programa el escritorio.
+fn action_for_type(kind: &str) -> Option<SessionAction> {
+    match kind {
+        "poweroff" => Some(SessionAction::PowerOff),
+        "reboot" => Some(SessionAction::Reboot),
+        _ => None,
+    }
+}
+
+fn emit(app: &AppHandle, scheduled: Option<&ScheduledAction>) {
+    let _ = app.emit("session-schedule", scheduled);
+}
+
+/// La acción programada, si hay una.
+pub fn current() -> Option<ScheduledAction> {
+    PENDING.lock().ok().and_then(|pending| pending.clone())
+}
+
+/// Programa `action` para dentro de `delay` segundos. Reemplaza la que hubiera.
+pub async fn schedule(app: &AppHandle, action: SessionAction, delay: u64) -> Result<ScheduledAction> {
+    // La entrada de arranque no se guarda con lo programado; ir al firmware
+    // se decide en el momento.
+    if matches!(
+        action,
+        SessionAction::Lock
+            | SessionAction::Logout
+            | SessionAction::RebootToFirmware
+            | SessionAction::RebootToEntry(_)
+    ) {
+        return Err(VasakError::InvalidState(format!(
+            "No se puede programar {}",
+            action.name()
+        )));
+    }
+    cancel(app).await;
+
+    let at = now_secs() + delay;
+    let mut logind = false;
+    if let Some(kind) = shutdown_type(&action) {
+        let usec = (at + BACKSTOP).saturating_mul(1_000_000);
+        match session_actions::system_bus(app).await {
+            Ok(connection) => {
+                match connection
+                    .call_method(
+                        Some(DESTINATION),
+                        PATH,
+                        Some(MANAGER),
+                        "ScheduleShutdown",
+                        &(kind, usec),
+                    )
+                    .await
+                {
+                    Ok(_) => logind = true,
+                    Err(e) => log_warning(&format!(
+                        "logind no programó el respaldo de {}: {}",
+                        kind, e
+                    )),
+                }
+            }
+            Err(e) => log_warning(&format!("Sin bus del sistema, sin respaldo en logind: {}", e)),
+        }
+    }
+    if logind {
+        BACKSTOP_ARMED.store(true, Ordering::SeqCst);
+    }
+
+    let scheduled = ScheduledAction {
+        action: action.name().to_string(),
+        at,
+        logind,
+    };
+    let generation = {
+        let mut pending = PENDING
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("schedule"))?;
+        *pending = Some(scheduled.clone());
+        GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+    };
+    log_info(&format!(
+        "Acción programada: {} a las {}{}",
+        scheduled.action,
+        clock(at),
+        if logind { " (con respaldo en logind)" } else { "" }
+    ));
+    emit(app, Some(&scheduled));
+    tokio::spawn(run(app.clone(), action, scheduled.clone(), generation));
+    Ok(scheduled)
+}
+
+/// Cancela la acción programada. Devuelve si había una.
+pub async fn cancel(app: &AppHandle) -> bool {
+    let scheduled = match PENDING.lock() {
+        Ok(mut pending) => {
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+            pending.take()
+        }
+        Err(_) => return false,
+    };
+    let Some(scheduled) = scheduled else {
+        return false;
+    };
+
+    if BACKSTOP_ARMED.swap(false, Ordering::SeqCst) {
+        cancel_backstop(app).await;
+    }
+    log_info(&format!("Acción programada cancelada: {}", scheduled.action));
+    emit(app, None);
+    true
+}
+
+async fn cancel_backstop(app: &AppHandle) {
+    let cancelled = match session_actions::system_bus(app).await {
+        Ok(connection) => connection
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(MANAGER),
+                "CancelScheduledShutdown",
+                &(),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = cancelled {
+        log_error(&format!(
+            "No se pudo cancelar el apagado programado en logind: {}",
+            e
+        ));
+    }
+}
+
+/// Se canceló el apagado que empezó una acción programada: logind no tiene
+/// que cumplirlo por su cuenta.
+pub(crate) fn drop_backstop(app: &AppHandle) {
+    if BACKSTOP_ARMED.swap(false, Ordering::SeqCst) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { cancel_backstop(&app).await });
+    }
+}
+
+/// Retoma el apagado que logind tenga programado, que puede ser el respaldo
+/// de una acción de antes de reiniciar el escritorio o uno pedido con
+/// `shutdown` desde una terminal. No hay forma de distinguirlos, así que su
+/// hora pasa a ser la de la acción y el respaldo se corre detrás: a lo sumo
+/// el apagado llega un minuto más tarde de lo pedido.
+pub async fn restore(app: &AppHandle) {
+    let Ok(connection) = session_actions::system_bus(app).await else {
+        return;
+    };
+    let scheduled = match logind::scheduled_shutdown(&connection).await {
+        Ok(scheduled) => scheduled,
+        Err(e) => {
+            log_warning(&format!("No se pudo leer el apagado programado en logind: {}", e));
+            return;
+        }
+    };
+    let Some((action, at)) = scheduled
+        .and_then(|(kind, usec)| Some((action_for_type(&kind)?, usec / 1_000_000)))
+    else {
+        return;
+    };
+    if current().is_some() {
+        return;
+    }
+    log_info(&format!("logind tiene programado {} a las {}", action.name(), clock(at)));
+    if let Err(e) = schedule(app, action, at.saturating_sub(now_secs())).await {
+        log_error(&format!("No se pudo retomar la acción programada: {}", e));
+    }
+}
+
+/// Espera hasta `at` según el reloj de pared. Devuelve false si la
+/// reemplazaron o cancelaron mientras tanto.
+async fn wait_until(at: u64, generation: u64) -> bool {
+    loop {
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return false;
+        }
+        let left = at.saturating_sub(now_secs());
+        if left == 0 {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_secs(left.min(TICK))).await;
+    }
+}
+
+async fn run(app: AppHandle, action: SessionAction, scheduled: ScheduledAction, generation: u64) {
+    if !wait_until(scheduled.at.saturating_sub(WARNING), generation).await {
+        return;
+    }
+    let summary = localized(&app, &format!("schedule.warning.{}", scheduled.action))
+        .replace("{0}", &clock(scheduled.at));
+    let body = localized(&app, "schedule.cancelHint");
+    if let Err(e) = send_system_notification(summary, Some(body), Some("critical".to_string())).await {
+        log_warning(&format!("No se pudo avisar de la acción programada: {}", e));
+    }
+
+    if !wait_until(scheduled.at, generation).await {
+        return;
+    }
+    // Se revisa con el candado tomado para no pisar una cancelación de último
+    // momento.
+    {
+        let Ok(mut pending) = PENDING.lock() else {
+            return;
+        };
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        *pending = None;
+    }
+    emit(&app, None);
+
+    // El respaldo de logind, si lo hay, sigue en pie mientras se cierran las
+    // aplicaciones.
+    log_info(&format!("Ejecutando la acción programada: {}", scheduled.action));
+    let _ = session_actions::perform(&app, action).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn queries_in_both_languages() {
+        assert_eq!(
+            parse_query("shutdown in 30m"),
+            Some((SessionAction::PowerOff, When::In(1800)))
+        );
+        assert_eq!(
+            parse_query("Reboot in 1h30m"),
+            Some((SessionAction::Reboot, When::In(5400)))
+        );
+        assert_eq!(
+            parse_query("apagar en una hora"),
+            Some((SessionAction::PowerOff, When::In(3600)))
+        );
+        assert_eq!(
+            parse_query("suspender a las 23:30"),
+            Some((SessionAction::Suspend, When::At(23 * 60 + 30)))
+        );
+        assert_eq!(
+            parse_query("suspend at 7:05"),
+            Some((SessionAction::Suspend, When::At(7 * 60 + 5)))
+        );
+        assert_eq!(parse_query("shutdown"), None);
+        assert_eq!(parse_query("shutdown in 30"), None);
+        assert_eq!(parse_query("shutdownin 30m"), None);
+        assert_eq!(parse_query("reboot at 25:00"), None);
+    }
+
+    #[test]
+    fn durations_add_up() {
+        assert_eq!(parse_duration("1 hour 30 minutes"), Some(5400));
+        assert_eq!(parse_duration("2 horas y 15 minutos"), Some(8100));
+        assert_eq!(parse_duration("45s"), Some(45));
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("10 20 m"), None);
+        assert_eq!(parse_duration("5 lightyears"), None);
+    }
+
+    #[test]
+    fn past_times_mean_tomorrow() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 30).unwrap();
+        assert_eq!(When::At(23 * 60).delay(&now), 3570);
+        assert_eq!(When::At(21 * 60).delay(&now), 23 * 3600 - 30);
+        assert_eq!(When::In(60).delay(&now), 60);
+        let exact = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+        assert_eq!(When::At(22 * 60).delay(&exact), 86_400);
+    }
+}
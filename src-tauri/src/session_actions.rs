@@ -0,0 +1,272 @@
+//! Acciones de sesión.
+//!
+//! Bloquear, cerrar sesión, suspender, hibernar, apagar y reiniciar (también
+//! al firmware o en otra entrada de arranque) se hacen siempre igual, vengan
+//! del popup de sesión, de la búsqueda, de un atajo o de D-Bus: logind para
+//! dormir el equipo, el apagado ordenado para apagar y reiniciar, uwsm para
+//! cerrar la sesión. Lo que no viene del popup pasa antes por la política de
+//! confirmación: si la acción la necesita, se abre el popup en vez de
+//! ejecutarla.
+
+use crate::commands::show_session_popup;
+use crate::dbus_pool::DbusPool;
+use crate::error::{Result, VasakError};
+use crate::logger::{log_error, log_info, log_warning};
+use crate::logind;
+use crate::settings::{self, ConfirmPolicy};
+use crate::shutdown::{self, ShutdownAction};
+use tauri::{AppHandle, Manager};
+use zbus::Connection;
+
+extern "C" {
+    fn getuid() -> u32;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionAction {
+    Lock,
+    Logout,
+    Suspend,
+    Hibernate,
+    HybridSleep,
+    SuspendThenHibernate,
+    PowerOff,
+    Reboot,
+    RebootToFirmware,
+    /// Reinicia en una entrada del gestor de arranque, por su id
+    RebootToEntry(String),
+}
+
+impl SessionAction {
+    /// El nombre de [`SessionAction::name`]; la entrada de arranque va
+    /// después de dos puntos (`reboot-to-entry:arch.conf`).
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(entry) = name.strip_prefix("reboot-to-entry:") {
+            return (!entry.is_empty()).then(|| SessionAction::RebootToEntry(entry.to_string()));
+        }
+        Some(match name {
+            "lock" => SessionAction::Lock,
+            "logout" => SessionAction::Logout,
+            "suspend" => SessionAction::Suspend,
+            "hibernate" => SessionAction::Hibernate,
+            "hybrid-sleep" => SessionAction::HybridSleep,
+            "suspend-then-hibernate" => SessionAction::SuspendThenHibernate,
+            "shutdown" | "poweroff" => SessionAction::PowerOff,
+            "reboot" => SessionAction::Reboot,
+            "reboot-to-firmware" => SessionAction::RebootToFirmware,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionAction::Lock => "lock",
+            SessionAction::Logout => "logout",
+            SessionAction::Suspend => "suspend",
+            SessionAction::Hibernate => "hibernate",
+            SessionAction::HybridSleep => "hybrid-sleep",
+            SessionAction::SuspendThenHibernate => "suspend-then-hibernate",
+            SessionAction::PowerOff => "shutdown",
+            SessionAction::Reboot => "reboot",
+            SessionAction::RebootToFirmware => "reboot-to-firmware",
+            SessionAction::RebootToEntry(_) => "reboot-to-entry",
+        }
+    }
+
+    /// La pantalla del popup que la confirma; las variantes de suspender y de
+    /// reiniciar se eligen en la de suspender y en la de reiniciar.
+    fn popup(&self) -> &'static str {
+        match self {
+            SessionAction::HybridSleep | SessionAction::SuspendThenHibernate => "suspend",
+            SessionAction::RebootToFirmware | SessionAction::RebootToEntry(_) => "reboot",
+            action => action.name(),
+        }
+    }
+
+    /// Las que cierran las aplicaciones.
+    fn destructive(&self) -> bool {
+        matches!(
+            self,
+            SessionAction::Logout
+                | SessionAction::PowerOff
+                | SessionAction::Reboot
+                | SessionAction::RebootToFirmware
+                | SessionAction::RebootToEntry(_)
+        )
+    }
+
+    /// El apagado ordenado que la ejecuta, para las que apagan o reinician.
+    fn shutdown_action(&self) -> Option<ShutdownAction> {
+        match self {
+            SessionAction::PowerOff => Some(ShutdownAction::PowerOff),
+            SessionAction::Reboot => Some(ShutdownAction::Reboot),
+            SessionAction::RebootToFirmware => Some(ShutdownAction::RebootToFirmware),
+            SessionAction::RebootToEntry(entry) => Some(ShutdownAction::RebootToEntry {
+                entry: entry.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Método del Manager de logind, para las que duermen el equipo.
+    fn sleep_method(&self) -> Option<&'static str> {
+        match self {
+            SessionAction::Suspend => Some("Suspend"),
+            SessionAction::Hibernate => Some("Hibernate"),
+            SessionAction::HybridSleep => Some("HybridSleep"),
+            SessionAction::SuspendThenHibernate => Some("SuspendThenHibernate"),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn needs_confirmation(action: &SessionAction, policy: ConfirmPolicy) -> bool {
+    match policy {
+        ConfirmPolicy::Always => true,
+        ConfirmPolicy::Destructive => action.destructive(),
+        ConfirmPolicy::Never => false,
+    }
+}
+
+/// Pide una acción desde afuera del popup. `origin` es solo para el log.
+pub async fn request(app: &AppHandle, action: SessionAction, origin: &str) -> Result<()> {
+    if needs_confirmation(&action, settings::get().session.confirm) {
+        log_info(&format!(
+            "Acción de sesión {} pedida desde {}: se pide confirmación",
+            action.name(),
+            origin
+        ));
+        return show_session_popup(action.popup().to_string(), app.clone())
+            .await
+            .map_err(VasakError::InvalidState);
+    }
+    log_info(&format!(
+        "Acción de sesión {} pedida desde {}",
+        action.name(),
+        origin
+    ));
+    perform(app, action).await
+}
+
+/// Ejecuta la acción, ya confirmada.
+pub async fn perform(app: &AppHandle, action: SessionAction) -> Result<()> {
+    let result = match &action {
+        SessionAction::Lock => crate::lock::lock(app).await,
+        SessionAction::Logout => logout(app).await,
+        _ => match (action.shutdown_action(), action.sleep_method()) {
+            (Some(shutdown), _) => shutdown::begin(app.clone(), shutdown),
+            (None, Some(method)) => sleep(app, method).await,
+            (None, None) => Ok(()),
+        },
+    };
+    if let Err(e) = &result {
+        log_error(&format!("No se pudo ejecutar {}: {}", action.name(), e));
+    }
+    result
+}
+
+pub(crate) async fn system_bus(app: &AppHandle) -> Result<Connection> {
+    let pooled = match app.try_state::<DbusPool>() {
+        Some(pool) => pool.system().await,
+        None => None,
+    };
+    match pooled {
+        Some(connection) => Ok(connection),
+        None => Ok(Connection::system().await?),
+    }
+}
+
+/// Suspende o hiberna. Si alguien lo impide, queda en el log quién: logind
+/// le pide a polkit permiso para pasarlo por alto.
+async fn sleep(app: &AppHandle, method: &str) -> Result<()> {
+    let connection = system_bus(app).await?;
+    if let Ok(inhibitors) = logind::inhibitors(&connection).await {
+        for inhibitor in inhibitors
+            .iter()
+            .filter(|inhibitor| logind::blocks(inhibitor, "sleep"))
+        {
+            log_warning(&format!(
+                "{} impide dormir el equipo: {}",
+                inhibitor.who, inhibitor.why
+            ));
+        }
+    }
+    logind::call(&connection, method, true).await
+}
+
+async fn logout(app: &AppHandle) -> Result<()> {
+    // The graphical session is managed by uwsm as a systemd user unit hierarchy.
+    // `uwsm stop` tears down graphical-session.target and its daemons in order.
+    // Spawn without waiting: uwsm ends the session (and this process) itself.
+    match std::process::Command::new("uwsm").arg("stop").spawn() {
+        Ok(_) => {
+            log_info("Cierre de sesión iniciado vía uwsm stop");
+            return Ok(());
+        }
+        Err(e) => {
+            log_error(&format!(
+                "uwsm no disponible ({}); usando logind TerminateUser como fallback",
+                e
+            ));
+        }
+    }
+
+    // Fallback (sin uwsm, p. ej. en dev): terminar la sesión vía logind.
+    let connection = system_bus(app).await?;
+    let uid = unsafe { getuid() };
+    connection
+        .call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1",
+            Some("org.freedesktop.login1.Manager"),
+            "TerminateUser",
+            &(uid,),
+        )
+        .await?;
+
+    log_info("Sesión cerrada correctamente");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for action in [
+            SessionAction::Lock,
+            SessionAction::Logout,
+            SessionAction::Suspend,
+            SessionAction::Hibernate,
+            SessionAction::HybridSleep,
+            SessionAction::SuspendThenHibernate,
+            SessionAction::PowerOff,
+            SessionAction::Reboot,
+            SessionAction::RebootToFirmware,
+        ] {
+            assert_eq!(SessionAction::parse(action.name()), Some(action));
+        }
+        assert_eq!(SessionAction::parse("poweroff"), Some(SessionAction::PowerOff));
+        assert_eq!(SessionAction::parse("settings"), None);
+        assert_eq!(SessionAction::HybridSleep.popup(), "suspend");
+
+        let entry = SessionAction::parse("reboot-to-entry:arch.conf").unwrap();
+        assert_eq!(entry, SessionAction::RebootToEntry("arch.conf".to_string()));
+        assert_eq!(entry.popup(), "reboot");
+        assert_eq!(SessionAction::parse("reboot-to-entry:"), None);
+    }
+
+    #[test]
+    fn only_destructive_actions_are_confirmed_by_default() {
+        let policy = ConfirmPolicy::default();
+        assert!(needs_confirmation(&SessionAction::PowerOff, policy));
+        assert!(needs_confirmation(&SessionAction::Logout, policy));
+        assert!(!needs_confirmation(&SessionAction::Lock, policy));
+        assert!(!needs_confirmation(&SessionAction::Suspend, policy));
+
+        assert!(needs_confirmation(&SessionAction::Lock, ConfirmPolicy::Always));
+        assert!(!needs_confirmation(&SessionAction::Reboot, ConfirmPolicy::Never));
+        assert!(needs_confirmation(&SessionAction::RebootToFirmware, policy));
+    }
+}
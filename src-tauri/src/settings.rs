@@ -0,0 +1,405 @@
+//! Preferencias del shell.
+//!
+//! `vasak.conf` es del plugin de configuración y describe la apariencia; acá
+//! va lo que decide el backend por su cuenta (qué salida de audio preferir,
+//! por ejemplo). Se guardan en `~/.config/vasak/desktop.json`. Si el archivo
+//! no existe, o le faltan campos porque lo escribió una versión anterior, se
+//! usan los valores por defecto en vez de fallar.
+
+use crate::error::{Result, VasakError};
+use crate::logger::log_warning;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ShellSettings {
+    pub audio: AudioSettings,
+    pub brightness: BrightnessSettings,
+    pub night_light: NightLightSettings,
+    pub battery: BatterySettings,
+    pub caffeine: CaffeineSettings,
+    pub lock: LockSettings,
+    pub session: SessionSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Pasar solo al dispositivo preferido cuando se conecta o desconecta uno
+    pub auto_switch: bool,
+    /// Salidas por nombre de nodo (`AudioDevice::description`), la preferida primero
+    pub sink_priority: Vec<String>,
+    /// Entradas por nombre de nodo, la preferida primero
+    pub source_priority: Vec<String>,
+    /// Cuánto sube o baja el volumen cada tecla, en puntos porcentuales
+    pub volume_step: i64,
+    /// Dejar que las teclas pasen del 100 % (distorsiona en muchos parlantes)
+    pub allow_over_amplification: bool,
+    /// Tope con la sobreamplificación activada
+    pub max_volume: i64,
+    /// Usar la entrada virtual con supresión de ruido y cancelación de eco
+    pub noise_suppression: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            auto_switch: true,
+            sink_priority: Vec::new(),
+            source_priority: Vec::new(),
+            volume_step: 5,
+            allow_over_amplification: false,
+            max_volume: 150,
+            noise_suppression: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Volumen máximo al que llegan las teclas.
+    pub fn volume_limit(&self) -> i64 {
+        if self.allow_over_amplification {
+            self.max_volume.clamp(100, 200)
+        } else {
+            100
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BrightnessSettings {
+    /// Cuánto sube o baja el brillo cada tecla, en puntos porcentuales
+    pub step: u32,
+    /// Brillo mínimo garantizado, en porcentaje del máximo del panel: ni con
+    /// el control en 0 queda la pantalla en negro
+    pub min_percent: u32,
+    /// Curva entre el control y el panel: 1 es lineal; valores más altos dan
+    /// más recorrido a los niveles bajos, que es donde el ojo nota la
+    /// diferencia
+    pub curve_exponent: f64,
+    /// Duración de los cambios de brillo, en milisegundos (0: instantáneo)
+    pub transition_ms: u32,
+    /// Ajustar el brillo solo según el sensor de luz ambiente
+    pub auto: bool,
+    /// Brillo que corresponde a cada nivel de luz; entre dos puntos se
+    /// interpola
+    pub auto_curve: Vec<CurvePoint>,
+    /// Brillo por software de los monitores sin retroiluminación, por
+    /// conector ("DP-1")
+    pub outputs: BTreeMap<String, u32>,
+}
+
+impl Default for BrightnessSettings {
+    fn default() -> Self {
+        Self {
+            step: 5,
+            min_percent: 5,
+            curve_exponent: 2.0,
+            transition_ms: 200,
+            auto: false,
+            auto_curve: vec![
+                CurvePoint { lux: 0.0, percent: 10 },
+                CurvePoint { lux: 20.0, percent: 25 },
+                CurvePoint { lux: 100.0, percent: 40 },
+                CurvePoint { lux: 400.0, percent: 60 },
+                CurvePoint { lux: 1500.0, percent: 80 },
+                CurvePoint { lux: 5000.0, percent: 100 },
+            ],
+            outputs: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CurvePoint {
+    /// Luz ambiente en lux
+    pub lux: f64,
+    /// Brillo de la pantalla con esa luz
+    pub percent: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NightLightSchedule {
+    /// Entre `start` y `end`
+    #[default]
+    Fixed,
+    /// De la puesta del sol al amanecer, calculados con las coordenadas
+    Sun,
+    /// Todo el tiempo
+    Always,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NightLightSettings {
+    /// Filtrar la luz azul según el horario
+    pub enabled: bool,
+    pub schedule: NightLightSchedule,
+    /// Comienzo de la noche con horario fijo, "HH:MM"
+    pub start: String,
+    /// Fin de la noche con horario fijo, "HH:MM"
+    pub end: String,
+    /// Coordenadas para el horario solar; sin ellas se usa el fijo
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Temperatura de color de noche, en kelvin (6500 es luz de día)
+    pub temperature: u32,
+    /// Minutos que dura el paso del día a la noche y al revés
+    pub transition_minutes: u32,
+}
+
+impl Default for NightLightSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: NightLightSchedule::Fixed,
+            start: "20:00".to_string(),
+            end: "07:00".to_string(),
+            latitude: None,
+            longitude: None,
+            temperature: 4000,
+            transition_minutes: 30,
+        }
+    }
+}
+
+/// Qué hace el shell cuando la batería llega al nivel crítico.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CriticalBatteryAction {
+    /// Solo avisar
+    Nothing,
+    #[default]
+    Suspend,
+    Hibernate,
+    PowerOff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BatterySettings {
+    /// Porcentajes en los que se avisa que queda poca batería, una vez por
+    /// descarga
+    pub warning_levels: Vec<u32>,
+    /// Por debajo de este porcentaje se ejecuta `critical_action`
+    pub critical_percent: u32,
+    pub critical_action: CriticalBatteryAction,
+    /// Segundos que tiene el usuario para cancelar la acción crítica
+    pub action_delay_seconds: u32,
+    /// Con batería por debajo de este porcentaje se pasa al modo de ahorro de
+    /// energía; `None` lo desactiva
+    pub power_saver_percent: Option<u32>,
+    /// Porcentaje en el que se deja de cargar con el límite de carga activo
+    pub charge_limit: u32,
+}
+
+impl Default for BatterySettings {
+    fn default() -> Self {
+        Self {
+            warning_levels: vec![20, 10],
+            critical_percent: 5,
+            critical_action: CriticalBatteryAction::Suspend,
+            action_delay_seconds: 60,
+            power_saver_percent: None,
+            charge_limit: 80,
+        }
+    }
+}
+
+/// Cuándo se activa solo el modo cafeína.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CaffeineSettings {
+    /// Mientras la ventana enfocada está a pantalla completa
+    pub auto_fullscreen: bool,
+    /// Mientras un reproductor está reproduciendo
+    pub auto_media: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LockSettings {
+    /// Programa que bloquea la pantalla: `auto` usa el primero instalado de
+    /// los conocidos, si no se toma como línea de comando. No tiene que pasar
+    /// a segundo plano (`swaylock -f`): que termine es señal de desbloqueo. A
+    /// los conocidos se les quita esa opción; un comando propio tiene que
+    /// quedarse en primer plano
+    pub locker: String,
+    /// Bloquear antes de suspender o hibernar
+    pub lock_before_sleep: bool,
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        Self {
+            locker: "auto".to_string(),
+            lock_before_sleep: true,
+        }
+    }
+}
+
+/// Qué acciones de sesión piden confirmación cuando llegan de un atajo, de la
+/// búsqueda o por D-Bus.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmPolicy {
+    Always,
+    /// Las que cierran aplicaciones: apagar, reiniciar y cerrar sesión
+    #[default]
+    Destructive,
+    Never,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SessionSettings {
+    pub confirm: ConfirmPolicy,
+}
+
+static SETTINGS: OnceLock<Mutex<ShellSettings>> = OnceLock::new();
+
+fn settings_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("VASAK_DESKTOP_SETTINGS") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join("vasak").join("desktop.json"))
+}
+
+fn load_from(path: &Path) -> ShellSettings {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return ShellSettings::default();
+    };
+
+    serde_json::from_str(&raw).unwrap_or_else(|e| {
+        log_warning(&format!(
+            "No se pudo leer {}: {}; usando valores por defecto",
+            path.display(),
+            e
+        ));
+        ShellSettings::default()
+    })
+}
+
+/// Escribe a un temporal y lo renombra, para que un corte a mitad de camino
+/// no deje un JSON truncado.
+fn save_to(path: &Path, settings: &ShellSettings) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| VasakError::Parse(format!("desktop.json: {}", e)))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn settings() -> &'static Mutex<ShellSettings> {
+    SETTINGS.get_or_init(|| {
+        Mutex::new(
+            settings_path()
+                .map(|path| load_from(&path))
+                .unwrap_or_default(),
+        )
+    })
+}
+
+/// Copia de las preferencias actuales.
+pub fn get() -> ShellSettings {
+    settings()
+        .lock()
+        .map(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Modifica las preferencias y las guarda en disco.
+pub fn update<F: FnOnce(&mut ShellSettings)>(change: F) -> Result<ShellSettings> {
+    let mut settings = settings()
+        .lock()
+        .map_err(|_| VasakError::LockPoisoned("settings"))?;
+
+    let mut updated = settings.clone();
+    change(&mut updated);
+    if updated == *settings {
+        return Ok(updated);
+    }
+
+    let path = settings_path()
+        .ok_or_else(|| VasakError::NotFound("directorio de configuración".to_string()))?;
+    save_to(&path, &updated)?;
+    *settings = updated.clone();
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vasak-settings-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let settings = load_from(&temp_path("missing.json"));
+        assert_eq!(settings, ShellSettings::default());
+        assert!(settings.audio.auto_switch);
+    }
+
+    /// Un archivo escrito por una versión anterior no trae los campos nuevos.
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let path = temp_path("partial.json");
+        std::fs::write(&path, r#"{"audio": {"sink_priority": ["usb-headset"]}}"#).unwrap();
+
+        let settings = load_from(&path);
+        assert_eq!(settings.audio.sink_priority, vec!["usb-headset"]);
+        assert!(settings.audio.auto_switch);
+        assert!(settings.audio.source_priority.is_empty());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn saved_settings_load_back() {
+        let path = temp_path("dir").join("desktop.json");
+        let mut settings = ShellSettings::default();
+        settings.audio.auto_switch = false;
+        settings.audio.source_priority = vec!["echo-cancel-source".to_string()];
+
+        save_to(&path, &settings).unwrap();
+        assert_eq!(load_from(&path), settings);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn over_amplification_is_opt_in_and_bounded() {
+        let mut audio = AudioSettings::default();
+        assert_eq!(audio.volume_limit(), 100);
+
+        audio.allow_over_amplification = true;
+        assert_eq!(audio.volume_limit(), 150);
+
+        audio.max_volume = 400;
+        assert_eq!(audio.volume_limit(), 200);
+        audio.max_volume = 50;
+        assert_eq!(audio.volume_limit(), 100);
+    }
+
+    #[test]
+    fn garbage_falls_back_to_defaults() {
+        let path = temp_path("garbage.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        assert_eq!(load_from(&path), ShellSettings::default());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
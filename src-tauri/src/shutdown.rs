@@ -0,0 +1,218 @@
+//! Apagado y reinicio ordenados.
+//!
+//! Llamar a `PowerOff` de una se lleva puestas las aplicaciones con trabajo
+//! sin guardar. Antes se le pide a cada ventana que se cierre, como si el
+//! usuario apretara su botón de cerrar, y se espera con una cuenta regresiva
+//! mostrando qué sigue abierto y quién tiene tomado un bloqueo de logind. Si
+//! todo se cierra antes, no se espera más. El usuario puede cancelar en
+//! cualquier momento antes de que se le pida a logind.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_error, log_info, log_warning};
+use crate::logind;
+use crate::session_actions::system_bus;
+use crate::structs::{Inhibitor, ShutdownProgress, WMState};
+use crate::window_manager::WindowInfo;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use zbus::Connection;
+
+/// Cuánto se espera a que las aplicaciones se cierren.
+pub(crate) const CLOSE_TIMEOUT: u32 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ShutdownAction {
+    PowerOff,
+    Reboot,
+    RebootToFirmware,
+    RebootToEntry { entry: String },
+}
+
+impl ShutdownAction {
+    fn name(&self) -> &'static str {
+        match self {
+            ShutdownAction::PowerOff => "poweroff",
+            ShutdownAction::Reboot => "reboot",
+            ShutdownAction::RebootToFirmware => "reboot-to-firmware",
+            ShutdownAction::RebootToEntry { .. } => "reboot-to-entry",
+        }
+    }
+
+    async fn run(&self, connection: &Connection) -> Result<()> {
+        match self {
+            ShutdownAction::PowerOff => logind::call(connection, "PowerOff", true).await,
+            ShutdownAction::Reboot => logind::call(connection, "Reboot", true).await,
+            ShutdownAction::RebootToFirmware => logind::reboot_to_firmware_setup(connection).await,
+            ShutdownAction::RebootToEntry { entry } => {
+                logind::reboot_to_boot_loader_entry(connection, entry).await
+            }
+        }
+    }
+}
+
+/// Sube con cada apagado que empieza y con cada cancelación; el que ve otro
+/// número sabe que lo cancelaron.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static PENDING: Mutex<Option<ShutdownAction>> = Mutex::new(None);
+
+fn emit(
+    app: &AppHandle,
+    action: &ShutdownAction,
+    state: &str,
+    remaining: u32,
+    windows: Vec<WindowInfo>,
+    inhibitors: Vec<Inhibitor>,
+) {
+    let _ = app.emit(
+        "shutdown-progress",
+        ShutdownProgress {
+            action: action.name().to_string(),
+            state: state.to_string(),
+            remaining,
+            windows,
+            inhibitors,
+        },
+    );
+}
+
+/// Empieza a apagar o reiniciar. Si ya había uno en curso, lo reemplaza.
+pub fn begin(app: AppHandle, action: ShutdownAction) -> Result<()> {
+    let generation = {
+        let mut pending = PENDING
+            .lock()
+            .map_err(|_| VasakError::LockPoisoned("shutdown"))?;
+        *pending = Some(action.clone());
+        GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+    };
+    log_info(&format!("Apagado ordenado: {}", action.name()));
+    tokio::spawn(run(app, action, generation));
+    Ok(())
+}
+
+/// Si hay un apagado esperando a que se cierren las aplicaciones.
+pub fn is_pending() -> bool {
+    PENDING.lock().map(|pending| pending.is_some()).unwrap_or(false)
+}
+
+/// Cancela el apagado en curso. Devuelve si había uno.
+pub fn cancel(app: &AppHandle) -> bool {
+    let action = match PENDING.lock() {
+        Ok(mut pending) => {
+            GENERATION.fetch_add(1, Ordering::SeqCst);
+            pending.take()
+        }
+        Err(_) => return false,
+    };
+    match action {
+        Some(action) => {
+            log_info(&format!("Apagado cancelado: {}", action.name()));
+            // Si lo empezó una acción programada, logind tiene un respaldo
+            // que ya no corresponde.
+            crate::schedule::drop_backstop(app);
+            emit(app, &action, "cancelled", 0, Vec::new(), Vec::new());
+            true
+        }
+        None => false,
+    }
+}
+
+async fn run(app: AppHandle, action: ShutdownAction, generation: u64) {
+    let connection = system_bus(&app).await.ok();
+
+    let asked = close_windows(&app).await;
+    log_info(&format!("Apagado: se pidió cerrar {} ventanas", asked));
+
+    for remaining in (0..=CLOSE_TIMEOUT).rev() {
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let windows = open_windows(&app).await;
+        let inhibitors = match &connection {
+            Some(connection) => logind::inhibitors(connection)
+                .await
+                .map(|list| {
+                    list.into_iter()
+                        .filter(|inhibitor| logind::blocks(inhibitor, "shutdown"))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let done = windows.is_empty() && inhibitors.is_empty();
+        emit(&app, &action, "waiting", remaining, windows, inhibitors);
+        if done || remaining == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    // Se revisa con el candado tomado para no pisar una cancelación de último
+    // momento.
+    {
+        let Ok(mut pending) = PENDING.lock() else {
+            return;
+        };
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        *pending = None;
+    }
+
+    emit(&app, &action, "running", 0, Vec::new(), Vec::new());
+    let result = match &connection {
+        Some(connection) => action.run(connection).await,
+        None => match system_bus(&app).await {
+            Ok(connection) => action.run(&connection).await,
+            Err(e) => Err(e),
+        },
+    };
+    if let Err(e) = result {
+        // Por ejemplo, si alguien no dejó apagar y no se autorizó a pasarlo
+        // por alto.
+        log_error(&format!("No se pudo ejecutar {}: {}", action.name(), e));
+        emit(&app, &action, "failed", 0, Vec::new(), Vec::new());
+    }
+}
+
+/// Ventanas de aplicaciones abiertas, sin las del escritorio.
+async fn open_windows(app: &AppHandle) -> Vec<WindowInfo> {
+    let Some(state) = app.try_state::<WMState>() else {
+        return Vec::new();
+    };
+    let manager = state.window_manager.clone();
+    tokio::task::spawn_blocking(move || {
+        manager
+            .read()
+            .ok()
+            .and_then(|manager| manager.get_window_list().ok())
+            .unwrap_or_default()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Pide a cada ventana que se cierre. Devuelve a cuántas se les pidió.
+async fn close_windows(app: &AppHandle) -> usize {
+    let Some(state) = app.try_state::<WMState>() else {
+        return 0;
+    };
+    let manager = state.window_manager.clone();
+    tokio::task::spawn_blocking(move || {
+        let Ok(manager) = manager.read() else {
+            return 0;
+        };
+        let windows = manager.get_window_list().unwrap_or_default();
+        for window in &windows {
+            if let Err(e) = manager.close_window(&window.id) {
+                log_warning(&format!("No se pudo cerrar {}: {}", window.title, e));
+            }
+        }
+        windows.len()
+    })
+    .await
+    .unwrap_or(0)
+}
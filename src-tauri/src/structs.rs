@@ -83,6 +83,228 @@ pub struct BrightnessInfo {
     pub max: u32,
     /// Nivel mínimo de brillo
     pub min: u32,
+    /// Dispositivo al que corresponde (`backlight/intel_backlight`)
+    pub device: String,
+}
+
+/// Un dispositivo de brillo: una pantalla o la luz del teclado
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct BrightnessDevice {
+    /// Id para los comandos (`backlight/intel_backlight`, `leds/tpacpi::kbd_backlight`, `output/DP-1`)
+    pub id: String,
+    /// Nombre del dispositivo en sysfs
+    pub name: String,
+    /// "display", "keyboard" u "output" (monitor oscurecido por software)
+    pub kind: String,
+    /// Brillo actual (0-100)
+    pub current: u32,
+    /// Niveles que distingue el driver; los teclados suelen tener 2 o 3
+    pub levels: u32,
+}
+
+/// Estado del brillo automático
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct AutoBrightnessStatus {
+    /// Si el usuario lo activó
+    pub enabled: bool,
+    /// Si hay un sensor de luz ambiente
+    pub available: bool,
+    /// Si está en pausa por un ajuste manual
+    pub paused: bool,
+    /// Última lectura del sensor
+    pub lux: Option<f64>,
+}
+
+/// Estado de la luz nocturna
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct NightLightState {
+    /// Si el usuario la activó
+    pub enabled: bool,
+    /// Si la pantalla está filtrada ahora mismo
+    pub active: bool,
+    /// Temperatura de color actual, en kelvin
+    pub temperature: u32,
+    /// Si el compositor permite cambiar la gamma
+    pub available: bool,
+}
+
+/// Cuenta regresiva de la acción por batería crítica
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct CriticalBatteryCountdown {
+    /// "suspend", "hibernate" o "poweroff"
+    pub action: String,
+    /// Segundos que faltan; `None` si se canceló o se conectó el cargador
+    pub remaining: Option<u32>,
+}
+
+/// Un modo de energía que ofrece el equipo
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct PowerProfile {
+    /// "power-saver", "balanced" o "performance"
+    pub name: String,
+    /// Driver que lo implementa (platform_profile, amd_pstate, ...)
+    pub driver: Option<String>,
+}
+
+/// Una aplicación que pidió mantener un modo de energía
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct PowerProfileHold {
+    pub profile: String,
+    pub application_id: String,
+    pub reason: String,
+}
+
+/// Estado de power-profiles-daemon
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct PowerProfilesState {
+    /// Si el daemon está corriendo
+    pub available: bool,
+    pub active: Option<String>,
+    pub profiles: Vec<PowerProfile>,
+    /// Por qué el modo rendimiento está limitado ("lap-detected",
+    /// "high-operating-temperature"), si lo está
+    pub degraded: Option<String>,
+    pub holds: Vec<PowerProfileHold>,
+}
+
+/// Una muestra del historial de la batería
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BatterySample {
+    /// Segundos desde la época Unix
+    pub time: i64,
+    pub percentage: f64,
+    /// Potencia que entra o sale de la batería (W)
+    pub rate: Option<f64>,
+    pub state: String,
+}
+
+/// Capacidad real frente a la de diseño en un momento dado
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BatteryHealthSample {
+    pub time: i64,
+    /// Energía máxima actual (Wh)
+    pub energy_full: f64,
+    /// Energía máxima de diseño (Wh)
+    pub energy_full_design: f64,
+}
+
+/// Historial para el gráfico del centro de control
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BatteryHistory {
+    /// Muestras del período pedido, de la más vieja a la más nueva
+    pub samples: Vec<BatterySample>,
+    /// De dónde salieron: "local", "upower", "mixed" o "none"
+    pub source: String,
+    /// Salud de la batería a lo largo de las sesiones, una muestra por día
+    pub health: Vec<BatteryHealthSample>,
+}
+
+/// Estado del modo cafeína
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct CaffeineState {
+    /// Si el equipo se mantiene despierto ahora
+    pub active: bool,
+    /// "manual", "fullscreen" o "media"
+    pub reason: Option<String>,
+    /// Si lo activó el usuario
+    pub manual: bool,
+    /// Segundos que faltan, si se activó por un rato
+    pub remaining: Option<u64>,
+    /// Si dura hasta que se desconecte el cargador
+    pub until_unplugged: bool,
+}
+
+/// Acciones de energía que logind permite en este equipo
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct SessionCapabilities {
+    pub power_off: bool,
+    pub reboot: bool,
+    pub suspend: bool,
+    pub hibernate: bool,
+    /// Suspender guardando también a disco, por si se corta la energía
+    pub hybrid_sleep: bool,
+    /// Suspender y, pasado un rato, hibernar
+    pub suspend_then_hibernate: bool,
+    pub reboot_to_firmware_setup: bool,
+    /// Si se puede elegir en qué entrada arrancar al reiniciar
+    pub reboot_to_boot_loader_entry: bool,
+}
+
+/// Entrada del gestor de arranque
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct BootEntry {
+    /// Id para `SetRebootToBootLoaderEntry` (p. ej. "arch.conf")
+    pub id: String,
+    pub title: String,
+}
+
+/// Un bloqueo tomado en logind (`ListInhibitors`)
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Inhibitor {
+    /// Qué bloquea, separado por ":" (p. ej. "shutdown:sleep")
+    pub what: String,
+    /// Quién lo tomó, tal como se presentó
+    pub who: String,
+    pub why: String,
+    /// "block" o "delay"
+    pub mode: String,
+    pub uid: u32,
+    pub pid: u32,
+}
+
+/// Cómo va un apagado o reinicio ordenado
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ShutdownProgress {
+    /// "poweroff", "reboot", "reboot-to-firmware" o "reboot-to-entry"
+    pub action: String,
+    /// "waiting", "running", "cancelled" o "failed"
+    pub state: String,
+    /// Segundos que quedan de espera
+    pub remaining: u32,
+    /// Ventanas que siguen abiertas
+    pub windows: Vec<WindowInfo>,
+    /// Quiénes frenan el apagado
+    pub inhibitors: Vec<Inhibitor>,
+}
+
+/// Una acción de energía programada
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ScheduledAction {
+    /// "shutdown", "reboot", "suspend", "hibernate"...
+    pub action: String,
+    /// Cuándo se ejecuta, en segundos desde 1970
+    pub at: u64,
+    /// Si logind tiene un apagado de respaldo por si el escritorio no llega
+    pub logind: bool,
+}
+
+/// Una cuenta de usuario, según AccountsService
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct UserAccount {
+    pub uid: u32,
+    pub user_name: String,
+    /// Nombre completo; vacío si no lo cargó
+    pub real_name: String,
+    /// Ruta de la imagen de la cuenta, si tiene
+    pub avatar: Option<String>,
+}
+
+/// Otra sesión abierta en el mismo asiento
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SeatSession {
+    /// Id de logind, para `ActivateSession`
+    pub id: String,
+    pub user: UserAccount,
+}
+
+/// Quién está usando el equipo, para el popup de sesión
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct SessionUser {
+    pub user: UserAccount,
+    /// Las demás sesiones gráficas del asiento
+    pub sessions: Vec<SeatSession>,
+    /// Si hay un gestor de pantalla que sepa mostrar el greeter
+    pub can_switch_user: bool,
 }
 
 /// Información sobre el volumen del sistema
@@ -105,6 +327,66 @@ pub struct AudioDevice {
     pub description: String,
     pub is_default: bool,
     pub volume: f64,
+    /// Indica si el dispositivo está silenciado
+    #[serde(default)]
+    pub is_muted: bool,
+    /// Puertos del dispositivo (parlantes, auriculares, micrófono interno...)
+    #[serde(default)]
+    pub ports: Vec<AudioPort>,
+    /// Nombre del puerto activo
+    #[serde(default)]
+    pub active_port: Option<String>,
+    /// Id de la tarjeta a la que pertenece, para cambiarle el perfil
+    #[serde(default)]
+    pub card_id: Option<String>,
+}
+
+/// Nivel de entrada del micrófono en un intervalo, lineal entre 0 y 1
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct MicLevel {
+    /// Muestra más alta del intervalo
+    pub peak: f32,
+    /// Valor eficaz del intervalo, más cercano a lo que se percibe
+    pub rms: f32,
+}
+
+/// Un puerto de un dispositivo de audio
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioPort {
+    /// Nombre interno del puerto (ej: "analog-output-headphones")
+    pub name: String,
+    /// Nombre legible del puerto
+    pub description: String,
+    /// Prioridad del puerto según el servidor de audio
+    pub priority: u32,
+    /// Si hay algo conectado al puerto; `None` si el hardware no lo informa
+    pub available: Option<bool>,
+}
+
+/// Un perfil de una tarjeta de audio (HDMI, estéreo dúplex, A2DP, HFP...)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioProfile {
+    /// Nombre interno del perfil (ej: "a2dp-sink", "headset-head-unit")
+    pub name: String,
+    /// Nombre legible del perfil
+    pub description: String,
+    /// Prioridad del perfil según el servidor de audio
+    pub priority: u32,
+    /// Si el perfil se puede activar ahora mismo
+    pub available: bool,
+}
+
+/// Una tarjeta de audio con sus perfiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioCard {
+    pub id: String,
+    /// Nombre interno de la tarjeta
+    pub name: String,
+    /// Nombre legible de la tarjeta
+    pub description: String,
+    pub profiles: Vec<AudioProfile>,
+    /// Nombre del perfil activo
+    pub active_profile: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,6 +471,25 @@ pub struct MediaInfo {
     pub status: Option<String>,
 }
 
+/// Batería de un periférico (ratón, teclado, auriculares, teléfono, SAI)
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PeripheralBattery {
+    /// Ruta del dispositivo en UPower
+    pub id: String,
+    /// Tipo: "mouse", "keyboard", "headset", "phone", "ups", ...
+    pub kind: String,
+    /// Modelo, si el dispositivo lo informa
+    pub model: Option<String>,
+    /// Porcentaje de carga (0.0 - 100.0)
+    pub percentage: f64,
+    /// Estado (Charging, Discharging, FullyCharged, ...)
+    pub state: String,
+    /// Icono que sugiere UPower
+    pub icon: Option<String>,
+    /// Nivel de aviso de UPower: 3 batería baja, 4 crítica
+    pub warning_level: u32,
+}
+
 /// Información detallada sobre la batería del sistema
 #[derive(Debug, Serialize, Clone)]
 pub struct BatteryInfo {
@@ -218,6 +519,16 @@ pub struct BatteryInfo {
     pub energy_full: Option<f64>,
     /// Energía máxima de diseño (Wh)
     pub energy_full_design: Option<f64>,
+    /// Potencia que entra o sale de la batería (W)
+    pub energy_rate: Option<f64>,
+    /// Si la batería permite limitar la carga
+    pub charge_threshold_supported: bool,
+    /// Si el límite de carga está activo
+    pub charge_threshold_enabled: bool,
+    /// Porcentaje por debajo del cual vuelve a cargar
+    pub charge_start_threshold: Option<u32>,
+    /// Porcentaje en el que deja de cargar
+    pub charge_end_threshold: Option<u32>,
     /// Voltaje actual (V)
     pub voltage: Option<f64>,
     /// Temperatura (°C)
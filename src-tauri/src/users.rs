@@ -0,0 +1,228 @@
+//! Usuarios y cambio de usuario.
+//!
+//! El nombre y la imagen de la cuenta salen de AccountsService. Las demás
+//! sesiones del asiento, de logind: a una que ya está abierta se pasa con
+//! `ActivateSession`; para entrar con otra cuenta se le pide al gestor de
+//! pantalla (LightDM, SDDM) que muestre el greeter con `SwitchToGreeter`. En
+//! los dos casos la sesión propia queda bloqueada antes: quien vuelva a ella
+//! por el asiento tiene que poner la contraseña.
+
+use crate::error::{Result, VasakError};
+use crate::logger::{log_info, log_warning};
+use crate::logind::{self, ListedSession};
+use crate::session_actions::system_bus;
+use crate::structs::{SeatSession, SessionUser, UserAccount};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::Connection;
+
+const ACCOUNTS: &str = "org.freedesktop.Accounts";
+const ACCOUNTS_PATH: &str = "/org/freedesktop/Accounts";
+const ACCOUNTS_USER: &str = "org.freedesktop.Accounts.User";
+
+const DISPLAY_MANAGER: &str = "org.freedesktop.DisplayManager";
+const DISPLAY_MANAGER_PATH: &str = "/org/freedesktop/DisplayManager";
+const DISPLAY_MANAGER_SEAT: &str = "org.freedesktop.DisplayManager.Seat";
+
+fn text(props: &HashMap<String, OwnedValue>, key: &str) -> String {
+    props
+        .get(key)
+        .and_then(|value| <&str>::try_from(value).ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Si vale la pena ofrecer pasar a `session`: otra sesión de usuario del mismo
+/// asiento que sigue abierta. Las de los greeters y las que se están cerrando
+/// no.
+pub(crate) fn switchable(own: &ListedSession, session: &ListedSession, class: &str, state: &str) -> bool {
+    session.id != own.id
+        && !own.seat.is_empty()
+        && session.seat == own.seat
+        && class == "user"
+        && matches!(state, "active" | "online")
+}
+
+async fn account(connection: &Connection, uid: u32) -> Result<UserAccount> {
+    let reply = connection
+        .call_method(
+            Some(ACCOUNTS),
+            ACCOUNTS_PATH,
+            Some(ACCOUNTS),
+            "FindUserById",
+            &(i64::from(uid),),
+        )
+        .await?;
+    let path: OwnedObjectPath = reply.body().deserialize()?;
+    let reply = connection
+        .call_method(
+            Some(ACCOUNTS),
+            &path,
+            Some("org.freedesktop.DBus.Properties"),
+            "GetAll",
+            &(ACCOUNTS_USER,),
+        )
+        .await?;
+    let props: HashMap<String, OwnedValue> = reply.body().deserialize()?;
+
+    // AccountsService informa una ruta aunque nunca se haya elegido imagen.
+    let avatar = Some(text(&props, "IconFile")).filter(|icon| Path::new(icon).is_file());
+    Ok(UserAccount {
+        uid,
+        user_name: text(&props, "UserName"),
+        real_name: text(&props, "RealName"),
+        avatar,
+    })
+}
+
+/// Sin AccountsService al menos se sabe el nombre de usuario.
+fn bare_account(uid: u32, user_name: String) -> UserAccount {
+    UserAccount {
+        uid,
+        user_name,
+        ..UserAccount::default()
+    }
+}
+
+async fn other_sessions(connection: &Connection) -> Result<Vec<SeatSession>> {
+    let own_path = crate::lock::session_path(connection).await?;
+    let sessions = logind::sessions(connection).await?;
+    let Some(own) = sessions.iter().find(|session| session.path == own_path).cloned() else {
+        return Ok(Vec::new());
+    };
+
+    let mut others = Vec::new();
+    for session in sessions {
+        let Ok(props) = logind::session_properties(connection, &session.path).await else {
+            continue;
+        };
+        if !switchable(&own, &session, &text(&props, "Class"), &text(&props, "State")) {
+            continue;
+        }
+        let user = match account(connection, session.uid).await {
+            Ok(user) => user,
+            Err(_) => bare_account(session.uid, session.user.clone()),
+        };
+        others.push(SeatSession {
+            id: session.id,
+            user,
+        });
+    }
+    Ok(others)
+}
+
+/// El asiento del gestor de pantalla. LightDM lo deja en `XDG_SEAT_PATH`; si
+/// no está, el primero que publique.
+async fn display_manager_seat(connection: &Connection) -> Option<OwnedObjectPath> {
+    if let Some(path) = std::env::var("XDG_SEAT_PATH")
+        .ok()
+        .and_then(|path| OwnedObjectPath::try_from(path).ok())
+    {
+        return Some(path);
+    }
+    let reply = connection
+        .call_method(
+            Some(DISPLAY_MANAGER),
+            DISPLAY_MANAGER_PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(DISPLAY_MANAGER, "Seats"),
+        )
+        .await
+        .ok()?;
+    let seats: OwnedValue = reply.body().deserialize().ok()?;
+    Vec::<OwnedObjectPath>::try_from(seats).ok()?.into_iter().next()
+}
+
+/// Quién usa el equipo y a qué sesiones se puede pasar.
+pub async fn session_user(app: &AppHandle) -> SessionUser {
+    let uid = unsafe { libc::getuid() };
+    let user_name = std::env::var("USER").unwrap_or_default();
+    let connection = match system_bus(app).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            log_warning(&format!("Sin bus del sistema para leer la cuenta: {}", e));
+            return SessionUser {
+                user: bare_account(uid, user_name),
+                ..SessionUser::default()
+            };
+        }
+    };
+
+    let user = match account(&connection, uid).await {
+        Ok(user) => user,
+        Err(e) => {
+            log_warning(&format!("No se pudo leer la cuenta en AccountsService: {}", e));
+            bare_account(uid, user_name)
+        }
+    };
+    let sessions = match other_sessions(&connection).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            log_warning(&format!("No se pudieron listar las sesiones: {}", e));
+            Vec::new()
+        }
+    };
+    SessionUser {
+        user,
+        sessions,
+        can_switch_user: display_manager_seat(&connection).await.is_some(),
+    }
+}
+
+/// Muestra el greeter para entrar con otra cuenta.
+pub async fn switch_user(app: &AppHandle) -> Result<()> {
+    let connection = system_bus(app).await?;
+    let seat = display_manager_seat(&connection)
+        .await
+        .ok_or_else(|| VasakError::NotFound("gestor de pantalla".to_string()))?;
+    crate::lock::lock(app).await?;
+    log_info("Cambiando de usuario");
+    connection
+        .call_method(
+            Some(DISPLAY_MANAGER),
+            &seat,
+            Some(DISPLAY_MANAGER_SEAT),
+            "SwitchToGreeter",
+            &(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Pasa a otra sesión ya abierta en el asiento.
+pub async fn activate_session(app: &AppHandle, id: &str) -> Result<()> {
+    let connection = system_bus(app).await?;
+    crate::lock::lock(app).await?;
+    log_info(&format!("Pasando a la sesión {}", id));
+    logind::activate_session(&connection, id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(id: &str, seat: &str) -> ListedSession {
+        ListedSession {
+            id: id.to_string(),
+            uid: 1001,
+            user: "ana".to_string(),
+            seat: seat.to_string(),
+            path: OwnedObjectPath::try_from(format!("/org/freedesktop/login1/session/_3{}", id)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn only_open_user_sessions_on_our_seat() {
+        let own = listed("2", "seat0");
+        assert!(switchable(&own, &listed("5", "seat0"), "user", "online"));
+        assert!(!switchable(&own, &own, "user", "active"));
+        assert!(!switchable(&own, &listed("5", "seat1"), "user", "online"));
+        assert!(!switchable(&own, &listed("5", ""), "user", "online"));
+        assert!(!switchable(&own, &listed("c1", "seat0"), "greeter", "online"));
+        assert!(!switchable(&own, &listed("5", "seat0"), "user", "closing"));
+        assert!(!switchable(&listed("2", ""), &listed("5", ""), "user", "online"));
+    }
+}
@@ -282,6 +282,29 @@ pub fn get_system_actions(query: &str) -> Vec<SearchResult> {
         .collect()
 }
 
+/// "shutdown in 30m", "suspender a las 23:30": program a power action. The id
+/// keeps the query so the time is worked out again when it is picked.
+fn get_schedule_action(query: &str) -> Option<SearchResult> {
+    let (action, when) = crate::schedule::parse_query(query)?;
+    let now = chrono::Local::now();
+    let at = now.timestamp().max(0) as u64 + when.delay(&now);
+    let icon = match action.name() {
+        "reboot" => "system-reboot",
+        "shutdown" => "system-shutdown",
+        _ => "system-suspend",
+    };
+    Some(SearchResult {
+        id: format!("schedule:{}", query.trim()),
+        title: format!("search.actions.schedule.{}", action.name()),
+        description: crate::schedule::clock(at),
+        icon: Some(icon.to_string()),
+        category: SearchCategory::Action,
+        exec: None,
+        path: None,
+        score: 100.0,
+    })
+}
+
 /// Main search function combining all sources
 pub fn search(query: &str, limit: usize) -> Vec<SearchResult> {
     if query.trim().is_empty() {
@@ -297,6 +320,7 @@ pub fn search(query: &str, limit: usize) -> Vec<SearchResult> {
     // Search system actions
     let action_results = get_system_actions(query);
     results.extend(action_results);
+    results.extend(get_schedule_action(query));
     
     // Sort all results by score
     results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
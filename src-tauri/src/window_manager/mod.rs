@@ -18,6 +18,7 @@ pub trait WindowManagerBackend: Send + Sync {
     fn get_window_list(&self) -> Result<Vec<WindowInfo>, Box<dyn std::error::Error>>;
     fn setup_event_monitoring(&mut self, tx: Sender<()>) -> Result<(), Box<dyn std::error::Error>>;
     fn toggle_window(&self, win_id: &str) -> Result<(), Box<dyn std::error::Error>>;
+    fn close_window(&self, win_id: &str) -> Result<(), Box<dyn std::error::Error>>;
 }
 
 pub struct WindowManager {
@@ -52,4 +53,8 @@ impl WindowManager {
     pub fn toggle_window(&self, win_id: &str) -> Result<(), Box<dyn std::error::Error>> {
         self.backend.toggle_window(win_id)
     }
+
+    pub fn close_window(&self, win_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.backend.close_window(win_id)
+    }
 }
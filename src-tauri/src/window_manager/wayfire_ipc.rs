@@ -359,6 +359,12 @@ impl WayfireClient {
         self.send_and_wait("window-rules/focus-view", json!({ "id": view_id })).await
     }
 
+    /// Asks the view to close, as its close button would: the client can
+    /// still ask about unsaved work, or refuse.
+    pub async fn close_view(&self, view_id: u64) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        self.send_and_wait("window-rules/close-view", json!({ "id": view_id })).await
+    }
+
     pub async fn set_minimized(&self, view_id: u64, state: bool) -> Result<Value, Box<dyn Error + Send + Sync>> {
         self.send_and_wait("wm-actions/set-minimized", json!({ "view_id": view_id, "state": state })).await
     }
@@ -166,6 +166,17 @@ impl WindowManagerBackend for WaylandManager {
 
         Ok(())
     }
+
+    fn close_window(&self, win_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let view_id = win_id.parse::<u64>().map_err(|error| format!("invalid Wayfire view id {win_id}: {error}"))?;
+
+        Self::block_on_async(async move {
+            let client = get_wayfire_client().await.ok_or("Unable to connect to Wayfire IPC")?;
+            client.close_view(view_id).await.map(|_| ())
+        })?;
+
+        Ok(())
+    }
 }
 
 impl Default for WaylandManager {
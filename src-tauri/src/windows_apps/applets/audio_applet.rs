@@ -4,7 +4,12 @@ use tauri::{
 };
 
 
-use crate::{app_url::get_app_url, monitor_manager::get_primary_monitor};
+use crate::{app_url::get_app_url, mic_level, monitor_manager::get_primary_monitor};
+
+/// Losing focus closes the applet; either way it is gone from the screen.
+fn dismisses(event: &WindowEvent) -> bool {
+    matches!(event, WindowEvent::Focused(false) | WindowEvent::Destroyed)
+}
 
 pub async fn create_applet_audio_window(
     app: AppHandle,
@@ -27,8 +32,13 @@ pub async fn create_applet_audio_window(
 
     let win_for_blur = window.clone();
     window.on_window_event(move |event| {
-        if matches!(event, WindowEvent::Focused(false)) {
-            let _ = win_for_blur.close();
+        if dismisses(event) {
+            // Closing it here bypasses the toggle, which is what otherwise
+            // stops the microphone meter.
+            mic_level::unwatch(mic_level::WATCHER_AUDIO_APPLET);
+            if matches!(event, WindowEvent::Focused(false)) {
+                let _ = win_for_blur.close();
+            }
         }
     });
 
@@ -54,3 +64,15 @@ pub async fn create_applet_audio_window(
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_and_destroy_dismiss_the_applet() {
+        assert!(dismisses(&WindowEvent::Focused(false)));
+        assert!(dismisses(&WindowEvent::Destroyed));
+        assert!(!dismisses(&WindowEvent::Focused(true)));
+    }
+}
@@ -5,6 +5,12 @@ use crate::app_url::get_app_url;
 use crate::gtk_utils;
 use crate::monitor_manager::get_primary_monitor;
 
+/// Tall enough for the user row on top plus the reboot target list, the
+/// sessions to switch to, or the windows and inhibitors shown while shutting
+/// down.
+const WIDTH: i32 = 400;
+const HEIGHT: i32 = 460;
+
 fn set_window_properties(window: &tauri::WebviewWindow) -> Result<(), Box<dyn std::error::Error>> {
     let gtk_window = window.gtk_window()?;
 
@@ -34,14 +40,16 @@ pub async fn create_session_popup_window(
     .title("Vasak Session")
     .decorations(false)
     .transparent(true)
-    .inner_size(400.0, 280.0)
+    .inner_size(WIDTH as f64, HEIGHT as f64)
     .visible(true)
     .skip_taskbar(true)
     .build()?;
 
     let win_for_blur = window.clone();
     window.on_window_event(move |event| {
-        if matches!(event, WindowEvent::Focused(false)) {
+        // While shutting down it is the only way to see what is still open
+        // and to cancel, and a polkit prompt is enough to take the focus.
+        if matches!(event, WindowEvent::Focused(false)) && !crate::shutdown::is_pending() {
             let _ = win_for_blur.close();
         }
     });
@@ -54,8 +62,8 @@ pub async fn create_session_popup_window(
     let url = Url::parse(&complete_url).expect("Failed to parse URL");
     let _ = window.navigate(url);
 
-    let center_x = monitor_position.x + (monitor_size.width as i32 / 2) - (400 / 2);
-    let center_y = monitor_position.y + (monitor_size.height as i32 / 2) - (280 / 2);
+    let center_x = monitor_position.x + (monitor_size.width as i32 / 2) - (WIDTH / 2);
+    let center_y = monitor_position.y + (monitor_size.height as i32 / 2) - (HEIGHT / 2);
 
     window
         .set_position(tauri::PhysicalPosition {
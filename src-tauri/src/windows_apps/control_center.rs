@@ -2,6 +2,7 @@ use gtk_layer_shell::{KeyboardMode, Layer};
 use tauri::AppHandle;
 
 use crate::logger::log_info;
+use crate::mic_level;
 use crate::monitor_manager::{find_gdk_monitor, get_primary_monitor};
 use crate::windows_apps::shell_layer::{spawn_layer_window, LayerSpec};
 
@@ -55,6 +56,8 @@ pub fn create_control_center_window(app: &AppHandle) -> Result<(), Box<dyn std::
             keyboard: KeyboardMode::OnDemand,
             start_hidden: true,
             dismiss_on_unfocus: true,
+            // The microphone meter runs only while the centre is on screen.
+            on_dismiss: Some(|| mic_level::unwatch(mic_level::WATCHER_CONTROL_CENTER)),
         },
     )
 }
@@ -37,6 +37,10 @@ pub struct LayerSpec {
     /// what makes a popup behave like a popup — it used to stay open until it
     /// was toggled again, over whatever the person clicked next.
     pub dismiss_on_unfocus: bool,
+    /// Called after the surface dismisses itself, so whoever opened it can let
+    /// go of what it held while shown — the toggle that hides it is not
+    /// involved.
+    pub on_dismiss: Option<fn()>,
 }
 
 impl Default for LayerSpec {
@@ -50,6 +54,7 @@ impl Default for LayerSpec {
             keyboard: KeyboardMode::None,
             start_hidden: false,
             dismiss_on_unfocus: false,
+            on_dismiss: None,
         }
     }
 }
@@ -129,16 +134,23 @@ pub fn spawn_layer_window(
         // Escape and focus loss are handled here rather than in the page: the
         // surface owns the keyboard, and a click that lands on another window
         // never reaches the webview at all.
-        layer_win.connect_key_press_event(|window, event| {
+        let on_dismiss = spec.on_dismiss;
+        layer_win.connect_key_press_event(move |window, event| {
             if event.keyval() == gdk::keys::constants::Escape {
                 window.hide();
+                if let Some(on_dismiss) = on_dismiss {
+                    on_dismiss();
+                }
                 return glib::Propagation::Stop;
             }
             glib::Propagation::Proceed
         });
 
-        layer_win.connect_focus_out_event(|window, _| {
+        layer_win.connect_focus_out_event(move |window, _| {
             window.hide();
+            if let Some(on_dismiss) = on_dismiss {
+                on_dismiss();
+            }
             glib::Propagation::Proceed
         });
     }
@@ -12,6 +12,11 @@ export interface BatteryInfo {
 	energy?: number;
 	energy_full?: number;
 	energy_full_design?: number;
+	energy_rate?: number;
+	charge_threshold_supported: boolean;
+	charge_threshold_enabled: boolean;
+	charge_start_threshold?: number;
+	charge_end_threshold?: number;
 	voltage?: number;
 	temperature?: number;
 	serial?: string;
@@ -0,0 +1,8 @@
+export interface ScheduledAction {
+	/** "shutdown", "reboot", "suspend", "hibernate"... */
+	action: string;
+	/** Unix time, in seconds, when it runs. */
+	at: number;
+	/** Whether logind holds a backstop in case the desktop is gone by then. */
+	logind: boolean;
+}
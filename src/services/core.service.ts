@@ -8,10 +8,74 @@ export const setAudioDevice = <T = any>(args: any): Promise<T> => {
 	return invoke<T>('set_audio_device', args);
 };
 
+export const getAudioSources = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_audio_sources', args);
+};
+
+export const setAudioSource = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_audio_source', args);
+};
+
+export const getAudioSourceVolume = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_audio_source_volume', args);
+};
+
+export const setAudioSourceVolume = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_audio_source_volume', args);
+};
+
+export const toggleAudioSourceMute = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('toggle_audio_source_mute', args);
+};
+
+export const getAudioCards = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_audio_cards', args);
+};
+
+export const setAudioPort = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_audio_port', args);
+};
+
+export const setAudioCardProfile = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_audio_card_profile', args);
+};
+
+export const getAudioNoiseSuppression = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_audio_noise_suppression', args);
+};
+
+export const setAudioNoiseSuppression = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_audio_noise_suppression', args);
+};
+
 export const getBrightnessInfo = <T = any>(args?: any): Promise<T> => {
 	return invoke<T>('get_brightness_info', args);
 };
 
+export const getBrightnessDevices = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_brightness_devices', args);
+};
+
+export const getAutoBrightness = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_auto_brightness', args);
+};
+
+export const setAutoBrightness = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('set_auto_brightness', args);
+};
+
+export const getNightLight = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_night_light', args);
+};
+
+export const setNightLight = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('set_night_light', args);
+};
+
+export const toggleNightLight = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('toggle_night_light', args);
+};
+
 export const setBrightnessInfo = <T = any>(args: any): Promise<T> => {
 	return invoke<T>('set_brightness_info', args);
 };
@@ -32,6 +96,42 @@ export const batteryExists = <T = any>(args?: any): Promise<T> => {
 	return invoke<T>('battery_exists', args);
 };
 
+export const getPeripheralBatteries = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_peripheral_batteries', args);
+};
+
+export const cancelCriticalBatteryAction = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('cancel_critical_battery_action', args);
+};
+
+export const getBatteryHistory = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_battery_history', args);
+};
+
+export const setChargeLimit = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('set_charge_limit', args);
+};
+
+export const getPowerProfiles = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_power_profiles', args);
+};
+
+export const setPowerProfile = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('set_power_profile', args);
+};
+
+export const getCaffeine = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_caffeine', args);
+};
+
+export const setCaffeine = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('set_caffeine', args);
+};
+
+export const toggleCaffeine = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('toggle_caffeine', args);
+};
+
 export const logFromFrontend = <T = any>(args: any): Promise<T> => {
 	return invoke<T>('log_from_frontend', args);
 };
@@ -59,3 +159,11 @@ export const getAudioVolume = <T = any>(args?: any): Promise<T> => {
 export const setAudioVolume = <T = any>(args?: any): Promise<T> => {
 	return invoke<T>('set_audio_volume', args);
 };
+
+export const getShellSettings = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_shell_settings', args);
+};
+
+export const setShellSettings = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('set_shell_settings', args);
+};
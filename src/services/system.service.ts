@@ -19,3 +19,67 @@ export const reboot = <T = any>(args?: any): Promise<T> => {
 export const suspend = <T = any>(args: any): Promise<T> => {
 	return invoke<T>('suspend', args);
 };
+
+export const lock = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('lock', args);
+};
+
+export const hibernate = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('hibernate', args);
+};
+
+export const hybridSleep = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('hybrid_sleep', args);
+};
+
+export const suspendThenHibernate = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('suspend_then_hibernate', args);
+};
+
+export const rebootToFirmwareSetup = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('reboot_to_firmware_setup', args);
+};
+
+export const rebootToBootEntry = <T = any>(args: any): Promise<T> => {
+	return invoke<T>('reboot_to_boot_entry', args);
+};
+
+export const getBootEntries = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_boot_entries', args);
+};
+
+export const getSessionCapabilities = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_session_capabilities', args);
+};
+
+export const cancelShutdown = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('cancel_shutdown', args);
+};
+
+export const requestSessionAction = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('request_session_action', args);
+};
+
+export const scheduleSessionAction = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('schedule_session_action', args);
+};
+
+export const cancelScheduledAction = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('cancel_scheduled_action', args);
+};
+
+export const getScheduledAction = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_scheduled_action', args);
+};
+
+export const getSessionUser = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('get_session_user', args);
+};
+
+export const switchUser = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('switch_user', args);
+};
+
+export const activateSession = <T = any>(args?: any): Promise<T> => {
+	return invoke<T>('activate_session', args);
+};